@@ -0,0 +1,24 @@
+use rusoto_core::credential::ProfileProvider;
+use rusoto_core::request::HttpClient;
+use rusoto_core::Region;
+use rusoto_ec2::Ec2Client;
+
+/// Returns the credentials provider for `profile` as configured in
+/// ~/.aws/credentials.
+pub fn profile_provider(profile: &str) -> ProfileProvider {
+    let aws_creds_dir: String =
+        dirs::home_dir().unwrap().to_str().unwrap().to_owned() + "/.aws/credentials";
+
+    ProfileProvider::with_configuration(aws_creds_dir, profile)
+}
+
+pub fn new_ec2client(
+    region: &Region,
+    profile: &str,
+) -> Result<Ec2Client, rusoto_core::request::TlsError> {
+    let http_client = HttpClient::new()?;
+
+    let client = Ec2Client::new_with(http_client, profile_provider(profile), region.clone());
+
+    Ok(client)
+}
@@ -4,38 +4,99 @@ extern crate rusoto_core;
 extern crate rusoto_ec2;
 extern crate tokio;
 
+use chrono::Utc;
 use clap::Clap;
 use cursive::align::HAlign;
 use cursive::direction::Orientation;
 use cursive::event::{Event, EventResult, Key};
-use cursive::theme::{BaseColor, Color, ColorStyle, PaletteColor};
+use cursive::theme::{BaseColor, Color, ColorStyle};
 use cursive::traits::*;
 use cursive::view::View;
 use cursive::view::*;
 use cursive::views::{
-    Canvas, Dialog, EditView, LinearLayout, OnEventView, ResizedView, SelectView, TextContent,
-    TextView,
+    Checkbox, Dialog, EditView, LinearLayout, ListView, OnEventView, ResizedView, SelectView,
+    TextArea, TextContent, TextView,
 };
 use cursive::Cursive;
 use cursive::CursiveExt;
-use rusoto_core::credential::ProfileProvider;
-use rusoto_core::request::HttpClient;
 use rusoto_core::Region;
 use rusoto_core::Region::*;
 use rusoto_ec2::{
-    DescribeInstancesRequest, Ec2, Ec2Client, Instance, RebootInstancesRequest,
-    StartInstancesRequest, StopInstancesRequest, Tag,
+    DescribeImagesRequest, DescribeInstancesRequest, Ec2, Instance, InstanceTypeInfo,
+    LaunchTemplate, RebootInstancesRequest, Tag, Volume,
 };
+use std::cell::{Cell, RefCell};
 use std::cmp::Ordering;
-use std::env;
+use std::collections::HashMap;
 use std::error::Error;
 use std::hash::Hash;
+use std::ops::Deref;
 use std::panic;
-use std::process::Command;
+use std::rc::Rc;
 use std::str::FromStr;
-
+use std::sync::atomic::{self, AtomicBool};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use cloudman_rs::aws::new_ec2client;
+use cloudman_rs::config::Config;
+use cloudman_rs::details::{
+    instance_document, instance_tabs, overview_with_status, security_with_rules,
+    storage_with_volumes, tags as tag_lines, METRICS, OVERVIEW, SECURITY, STORAGE, TAGS,
+};
+use cloudman_rs::images::{
+    copy_image, create_image, create_instance_snapshots, deregister_image, get_image, get_images,
+    get_launch_permissions, image_rows, image_snapshot_ids, modify_launch_permissions,
+    parse_accounts, ImageColumn, ImageRow, ImageScope,
+};
+use cloudman_rs::insights::{
+    get_query_results, start_query, stop_query, QueryColumn, QueryResults, QueryRow, DEFAULT_QUERY,
+};
+use cloudman_rs::instance_connect::{
+    send_serial_console_ssh_public_key, send_ssh_public_key, serial_console_host, EphemeralKey,
+};
+use cloudman_rs::instance_status::{get_instance_statuses, Status};
+use cloudman_rs::instance_types::{get_instance_types, modify_instance_type, InstanceTypeColumn};
+use cloudman_rs::instances::{get_instance_state, start_instance, stop_instance};
+use cloudman_rs::launch::{
+    get_key_pairs, get_launch_template_versions, get_launch_templates, get_subnets, run_instances,
+    subnet_label, version_label, LaunchSpec, TemplateSource,
+};
+use cloudman_rs::logs::{
+    filter_log_events, format_event, get_log_groups, get_log_streams, EventQuery, LogCursor,
+    TimeRange,
+};
+use cloudman_rs::metrics::{get_metrics, metric_lines, next_range, InstanceMetrics, Meter};
+use cloudman_rs::run_command::{
+    command_batches, get_command_invocation, send_batches, send_command, update_invocations,
+    Invocation, InvocationColumn,
+};
+use cloudman_rs::security_groups::{exposure, get_security_groups, Exposure};
+use cloudman_rs::spot::{
+    cancel_spot_request, get_spot_price_history, get_spot_requests, price_lines, SpotColumn,
+    SpotKind, SpotRequest,
+};
+use cloudman_rs::ssh;
+use cloudman_rs::ssm::{port_forwarding_command, ssh_proxy_command, start_session_command};
+use cloudman_rs::tags::{
+    change_tags, common_tags, copyable_tags, format_tags, parse_tags,
+    preview as preview_tag_changes, TagChanges,
+};
+use cloudman_rs::terminal;
+use cloudman_rs::tunnels::{
+    TunnelColumn, TunnelInfo, Tunnels, STARTUP_GRACE as TUNNEL_STARTUP_GRACE,
+};
+use cloudman_rs::user_data::{diff, get_user_data, is_gzip, modify_user_data, user_data_syntax};
 use cloudman_rs::views::{
-    BottomBarType, BottomBarView, Foo, Header, InstancesView, KeyCodeView, LogView, TableViewItem,
+    BottomBarType, BottomBarView, DetailLine, DetailsView, DocumentView, Foo, Header,
+    InstancesView, KeyCodeView, LogView, NotificationLevel, NotificationView, ScreenshotView,
+    Syntax, TableViewItem,
+};
+use cloudman_rs::volumes::{
+    attach_volume, attachment, create_snapshot, delete_snapshot, delete_volume, detach_volume,
+    get_instance_volumes, get_snapshots, get_volumes, modify_volume, snapshot_rows, unattached,
+    volume_tag, SnapshotColumn, SnapshotRow, VolumeColumn, VOLUME_TYPES,
 };
 
 // Use of a mod or pub mod is not actually necessary.
@@ -55,12 +116,75 @@ enum BasicColumn {
     State,
     PublicIp,
     PrivateIp,
+    SgOpen,
+    Status,
+    Cpu,
+    Network,
+    Disk,
+}
+
+/// An instance, with what is known about it besides `DescribeInstances`.
+#[derive(Clone, Default)]
+struct InstanceRow {
+    instance: Instance,
+    exposure: Option<Exposure>,
+    status: Option<Status>,
+    metrics: Option<InstanceMetrics>,
+    /// Launched from cloudman a moment ago.
+    launched: bool,
+}
+
+// the metrics are updated in place, which should not drop the marks
+impl PartialEq for InstanceRow {
+    fn eq(&self, other: &Self) -> bool {
+        self.instance == other.instance
+            && self.exposure == other.exposure
+            && self.status == other.status
+    }
+}
+
+impl InstanceRow {
+    fn meter(&self, meter: Meter, width: usize) -> String {
+        self.metrics
+            .as_ref()
+            .map(|metrics| metrics.meter(meter, width))
+            .unwrap_or_default()
+    }
+
+    fn latest(&self, meter: Meter) -> Option<f64> {
+        self.metrics
+            .as_ref()
+            .and_then(|metrics| metrics.latest(meter))
+    }
+
+    fn set_metrics(&mut self, metrics: &HashMap<String, InstanceMetrics>) {
+        if let Some(m) = self.instance_id.as_ref().and_then(|id| metrics.get(id)) {
+            self.metrics = Some(m.clone());
+        }
+    }
+
+    /// Whether the status checks fail or events are scheduled.
+    fn needs_attention(&self) -> bool {
+        self.status
+            .as_ref()
+            .is_some_and(|status| status.needs_attention())
+    }
+}
+
+impl Deref for InstanceRow {
+    type Target = Instance;
+
+    fn deref(&self) -> &Instance {
+        &self.instance
+    }
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 enum Actions {
     Start,
     Stop,
+    ModifyType,
+    LaunchMore,
 
     Reboot,
 }
@@ -78,9 +202,12 @@ fn find_tag(key: String, tags: Option<Vec<Tag>>) -> Option<String> {
     }
 }
 
-impl TableViewItem<BasicColumn> for Instance {
+impl TableViewItem<BasicColumn> for InstanceRow {
     fn to_column_color(&self, column: BasicColumn) -> ColorStyle {
         match column {
+            BasicColumn::InstanceID | BasicColumn::Name if self.launched => {
+                ColorStyle::new(Color::Light(BaseColor::Magenta), Color::TerminalDefault)
+            }
             BasicColumn::Name => {
                 ColorStyle::new(Color::Dark(BaseColor::Green), Color::TerminalDefault)
             }
@@ -88,6 +215,36 @@ impl TableViewItem<BasicColumn> for Instance {
                 Some(16) => ColorStyle::new(Color::TerminalDefault, Color::TerminalDefault),
                 _ => ColorStyle::new(Color::Light(BaseColor::Red), Color::TerminalDefault),
             },
+            BasicColumn::SgOpen => match &self.exposure {
+                Some(exposure) if exposure.is_risky() => {
+                    ColorStyle::new(Color::Light(BaseColor::Red), Color::TerminalDefault)
+                }
+                Some(exposure) if exposure.is_open() => {
+                    ColorStyle::new(Color::Light(BaseColor::Yellow), Color::TerminalDefault)
+                }
+                _ => ColorStyle::primary(),
+            },
+            BasicColumn::Status => match &self.status {
+                Some(status) if status.is_impaired() => {
+                    ColorStyle::new(Color::Light(BaseColor::Red), Color::TerminalDefault)
+                }
+                Some(status) if status.needs_attention() => {
+                    ColorStyle::new(Color::Light(BaseColor::Yellow), Color::TerminalDefault)
+                }
+                _ => ColorStyle::primary(),
+            },
+            BasicColumn::Cpu => match self.latest(Meter::Cpu) {
+                Some(cpu) if cpu >= 90.0 => {
+                    ColorStyle::new(Color::Light(BaseColor::Red), Color::TerminalDefault)
+                }
+                Some(cpu) if cpu >= 70.0 => {
+                    ColorStyle::new(Color::Light(BaseColor::Yellow), Color::TerminalDefault)
+                }
+                _ => ColorStyle::new(Color::Dark(BaseColor::Cyan), Color::TerminalDefault),
+            },
+            BasicColumn::Network | BasicColumn::Disk => {
+                ColorStyle::new(Color::Dark(BaseColor::Cyan), Color::TerminalDefault)
+            }
             _ => ColorStyle::primary(),
         }
     }
@@ -118,6 +275,19 @@ impl TableViewItem<BasicColumn> for Instance {
                 .private_ip_address
                 .clone()
                 .unwrap_or_else(|| "".to_string()),
+            BasicColumn::SgOpen => self
+                .exposure
+                .as_ref()
+                .map(|exposure| exposure.summary())
+                .unwrap_or_default(),
+            BasicColumn::Status => self
+                .status
+                .as_ref()
+                .map(|status| status.summary())
+                .unwrap_or_default(),
+            BasicColumn::Cpu => self.meter(Meter::Cpu, 10),
+            BasicColumn::Network => self.meter(Meter::Network, 8),
+            BasicColumn::Disk => self.meter(Meter::Disk, 8),
         }
     }
 
@@ -126,23 +296,32 @@ impl TableViewItem<BasicColumn> for Instance {
         Self: Sized,
     {
         match column {
-            BasicColumn::Name => self.instance_id.cmp(&other.instance_id),
-            BasicColumn::InstanceID => self.instance_id.cmp(&other.instance_id),
-            BasicColumn::Architecture => self.architecture.cmp(&other.architecture),
-            BasicColumn::VpcID => self.architecture.cmp(&other.architecture),
-            BasicColumn::Type => self.architecture.cmp(&other.architecture),
-            BasicColumn::Key => self.architecture.cmp(&other.architecture),
-            BasicColumn::State => self.architecture.cmp(&other.architecture),
-            BasicColumn::PublicIp => self.architecture.cmp(&other.architecture),
-            BasicColumn::PrivateIp => self.architecture.cmp(&other.architecture),
+            BasicColumn::State => self
+                .state
+                .as_ref()
+                .and_then(|state| state.code)
+                .cmp(&other.state.as_ref().and_then(|state| state.code)),
+            BasicColumn::Cpu => compare_latest(self, other, Meter::Cpu),
+            BasicColumn::Network => compare_latest(self, other, Meter::Network),
+            BasicColumn::Disk => compare_latest(self, other, Meter::Disk),
+            _ => self
+                .to_column(column)
+                .to_lowercase()
+                .cmp(&other.to_column(column).to_lowercase()),
         }
     }
 }
 
+fn compare_latest(a: &InstanceRow, b: &InstanceRow, meter: Meter) -> Ordering {
+    a.latest(meter)
+        .partial_cmp(&b.latest(meter))
+        .unwrap_or(Ordering::Equal)
+}
+
 fn get_instances_with_region(
     profile: &str,
     region: &Region,
-) -> Result<Vec<Instance>, Box<dyn Error>> {
+) -> Result<Vec<InstanceRow>, Box<dyn Error>> {
     let client = new_ec2client(&region, profile)?;
 
     let req: DescribeInstancesRequest = ec2_describe_input();
@@ -164,7 +343,27 @@ fn get_instances_with_region(
         }
     }
 
-    Ok(instances)
+    // without permission to describe security groups the exposure is unknown
+    let groups = get_security_groups(region, profile, None).ok();
+
+    // the same goes for the status checks
+    let statuses = get_instance_statuses(region, profile, None).ok();
+
+    Ok(instances
+        .into_iter()
+        .map(|instance| InstanceRow {
+            exposure: groups.as_ref().map(|groups| exposure(&instance, groups)),
+            status: statuses.as_ref().and_then(|statuses| {
+                instance
+                    .instance_id
+                    .as_ref()
+                    .and_then(|id| statuses.get(id).cloned())
+            }),
+            metrics: None,
+            launched: false,
+            instance,
+        })
+        .collect())
 }
 
 #[derive(Clap)]
@@ -201,6 +400,11 @@ impl Header for BasicColumn {
             BasicColumn::State => 10,
             BasicColumn::PublicIp => 15,
             BasicColumn::PrivateIp => 15,
+            BasicColumn::SgOpen => 10,
+            BasicColumn::Status => (30 * w) / 160,
+            BasicColumn::Cpu => 15,
+            BasicColumn::Network => 16,
+            BasicColumn::Disk => 16,
         }
     }
 
@@ -215,6 +419,11 @@ impl Header for BasicColumn {
             BasicColumn::State => "state".to_string(),
             BasicColumn::PublicIp => "public-ip".to_string(),
             BasicColumn::PrivateIp => "private-ip".to_string(),
+            BasicColumn::SgOpen => "sg-open".to_string(),
+            BasicColumn::Status => "status".to_string(),
+            BasicColumn::Cpu => "cpu".to_string(),
+            BasicColumn::Network => "net".to_string(),
+            BasicColumn::Disk => "disk".to_string(),
         }
     }
 }
@@ -241,6 +450,14 @@ fn run() {
         None => "default".to_string(),
     };
 
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Could not load the configuration\n\n{}", err);
+            std::process::exit(1);
+        }
+    };
+
     let instances = match get_instances_with_region(&profile, &region) {
         Ok(instances) => instances,
         Err(err) => {
@@ -256,6 +473,7 @@ fn run() {
     rv.instances = instances.clone();
     rv.region = region.clone();
     rv.dry_run = !opts.disable_dry_run;
+    rv.config = config;
 
     siv.set_user_data::<ReturnValues>(rv);
 
@@ -267,7 +485,7 @@ fn run() {
 
     layout.add_child(dialog_title);
 
-    let mut iv = InstancesView::<Instance, BasicColumn>::scrollable(&instances)
+    let mut iv = InstancesView::<InstanceRow, BasicColumn>::scrollable(&instances)
         .column(BasicColumn::InstanceID)
         .column(BasicColumn::Name)
         .column(BasicColumn::Architecture)
@@ -276,11 +494,16 @@ fn run() {
         .column(BasicColumn::Key)
         .column(BasicColumn::State)
         .column(BasicColumn::PublicIp)
-        .column(BasicColumn::PrivateIp);
-
-    iv.set_on_submit(|s: &mut Cursive, _instance: Option<Instance>| {
+        .column(BasicColumn::PrivateIp)
+        .column(BasicColumn::SgOpen)
+        .column(BasicColumn::Status)
+        .column(BasicColumn::Cpu)
+        .column(BasicColumn::Network)
+        .column(BasicColumn::Disk);
+
+    iv.set_on_submit(|s: &mut Cursive, _instance: Option<InstanceRow>| {
         let table = s
-            .find_name::<InstancesView<Instance, BasicColumn>>("instances")
+            .find_name::<InstancesView<InstanceRow, BasicColumn>>("instances")
             .unwrap();
 
         if let Some(instance) = table.item() {
@@ -290,6 +513,8 @@ fn run() {
 
     layout.add_child(iv.with_name("instances"));
 
+    layout.add_child(NotificationView::new().with_name("notifications"));
+
     let bottom_bar = BottomBarView::new(&"".to_string(), &region).with_name("bottom_bar");
 
     layout.add_child(bottom_bar);
@@ -305,13 +530,85 @@ fn run() {
             })
             .on_event('l', |s| {
                 let table = s
-                    .find_name::<InstancesView<Instance, BasicColumn>>("instances")
+                    .find_name::<InstancesView<InstanceRow, BasicColumn>>("instances")
                     .unwrap();
 
                 if let Some(instance) = table.item() {
                     instance_log(s, instance);
                 }
             })
+            .on_event('p', |s| {
+                let table = s
+                    .find_name::<InstancesView<InstanceRow, BasicColumn>>("instances")
+                    .unwrap();
+
+                if let Some(instance) = table.item() {
+                    instance_screenshot(s, instance);
+                }
+            })
+            .on_event('c', |s| {
+                let table = s
+                    .find_name::<InstancesView<InstanceRow, BasicColumn>>("instances")
+                    .unwrap();
+
+                if let Some(instance) = table.item() {
+                    if let Err(err) = serial_console(s, instance) {
+                        error_dialog(
+                            s,
+                            "Error",
+                            &format!("Could not connect to the serial console.\n\n{}", err),
+                        );
+                    }
+                }
+            })
+            .on_event('f', copy_files)
+            .on_event('w', cloudwatch_logs)
+            .on_event('i', logs_insights)
+            .on_event('r', run_command)
+            .on_event('t', port_forward)
+            .on_event('T', tunnels)
+            .on_event('!', toggle_status_filter)
+            .on_event('e', |s| {
+                let table = s
+                    .find_name::<InstancesView<InstanceRow, BasicColumn>>("instances")
+                    .unwrap();
+
+                let instances: Vec<Instance> = table
+                    .marked_or_selected()
+                    .into_iter()
+                    .map(|row| row.instance.clone())
+                    .collect();
+
+                edit_tags(s, instances);
+            })
+            .on_event('m', |s| {
+                s.with_user_data(|v: &mut ReturnValues| {
+                    v.metrics_range = next_range(v.metrics_range);
+                });
+            })
+            .on_event('A', images)
+            .on_event('a', |s| {
+                let table = s
+                    .find_name::<InstancesView<InstanceRow, BasicColumn>>("instances")
+                    .unwrap();
+
+                if let Some(instance) = table.item().map(|row| row.instance.clone()) {
+                    create_image_dialog(s, instance);
+                }
+            })
+            .on_event('n', |s| launch_wizard(s, LaunchSpec::new()))
+            .on_event('o', spot_requests)
+            .on_event('v', volumes)
+            .on_event('V', snapshots)
+            .on_event('S', |s| {
+                let table = s
+                    .find_name::<InstancesView<InstanceRow, BasicColumn>>("instances")
+                    .unwrap();
+
+                if let Some(instance) = table.item().map(|row| row.instance.clone()) {
+                    snapshot_instance_dialog(s, instance);
+                }
+            })
             .on_event(Key::Esc, |s| reset_filter(s))
             .on_event(Key::F9, |s| change_profile(s))
             .on_event(Key::F7, |s| change_region(s))
@@ -320,22 +617,31 @@ fn run() {
             .on_event(Key::F1, |s| help(s))
             .on_event(Key::F2, |s| {
                 let table = s
-                    .find_name::<InstancesView<Instance, BasicColumn>>("instances")
+                    .find_name::<InstancesView<InstanceRow, BasicColumn>>("instances")
                     .unwrap();
 
                 if let Some(instance) = table.item() {
-                    let ud = s.user_data::<ReturnValues>().unwrap();
-
-                    if connect(instance, &ud.profile, &ud.region).is_err() {
-                        let d = Dialog::around(TextView::new("Not running within tmux."))
-                            .title("Error")
-                            .button("Cancel", |s| {
-                                s.pop_layer();
-                            });
-
-                        let dl = event_view(d);
+                    if let Err(err) = connect(s, instance) {
+                        error_dialog(
+                            s,
+                            "Error",
+                            &format!("Could not connect to the instance.\n\n{}", err),
+                        );
+                    }
+                }
+            })
+            .on_event(Key::F8, |s| {
+                let table = s
+                    .find_name::<InstancesView<InstanceRow, BasicColumn>>("instances")
+                    .unwrap();
 
-                        s.add_layer(dl);
+                if let Some(instance) = table.item() {
+                    if let Err(err) = ssh_connect(s, instance) {
+                        error_dialog(
+                            s,
+                            "Error",
+                            &format!("Could not connect to the instance.\n\n{}", err),
+                        );
                     }
                 }
             })
@@ -344,11 +650,25 @@ fn run() {
 
     update_bottom_bar(&mut siv);
 
+    let cb_sink = siv.cb_sink().clone();
+    thread::spawn(move || loop {
+        thread::sleep(METRICS_POLL_INTERVAL);
+
+        if cb_sink.send(Box::new(load_visible_metrics)).is_err() {
+            break;
+        }
+    });
+
     siv.add_global_callback(Key::F10, |s| s.quit());
 
     siv.add_global_callback('s', |s| s.toggle_debug_console());
 
-    siv.run()
+    siv.run();
+
+    // don't leave port forwarding sessions behind
+    if let Some(ud) = siv.user_data::<ReturnValues>() {
+        ud.tunnels.stop_all();
+    }
 }
 
 struct ReturnValues {
@@ -359,8 +679,18 @@ struct ReturnValues {
     searching: bool,
     filter: String,
     filtering: bool,
-    instances: Vec<Instance>,
+    status_filter: bool,
+    instances: Vec<InstanceRow>,
+    metrics_range: TimeRange,
+    metrics_loading: bool,
+    launched: HashMap<String, Instant>,
+    image_scope: ImageScope,
+    image_pattern: String,
     dry_run: bool,
+    config: Config,
+    tunnels: Tunnels,
+    ephemeral_key: Option<EphemeralKey>,
+    command_history: Vec<String>,
 }
 
 impl ReturnValues {
@@ -373,8 +703,18 @@ impl ReturnValues {
             search_found: false,
             filter: "".to_string(),
             filtering: false,
+            status_filter: false,
             instances: vec![],
+            metrics_range: TimeRange::Hour1,
+            metrics_loading: false,
+            launched: HashMap::new(),
+            image_scope: ImageScope::Owned,
+            image_pattern: "".to_string(),
             dry_run: false,
+            config: Config::default(),
+            tunnels: Tunnels::new(),
+            ephemeral_key: None,
+            command_history: vec![],
         }
     }
 }
@@ -395,19 +735,10 @@ fn on_filter(s: &mut Cursive) {
 
         let ud = s.user_data::<ReturnValues>().unwrap();
 
-        let filtered_instances: Vec<Instance> = ud
-            .instances
-            .clone()
-            .into_iter()
-            .filter(|i| {
-                find_tag("Name".to_string(), i.tags.clone())
-                    .unwrap()
-                    .contains(ss)
-            })
-            .collect();
+        let filtered_instances = filter_instances(ud, ud.instances.clone());
 
         let mut table = s
-            .find_name::<InstancesView<Instance, BasicColumn>>("instances")
+            .find_name::<InstancesView<InstanceRow, BasicColumn>>("instances")
             .unwrap();
 
         let item = table.item();
@@ -450,10 +781,11 @@ fn reset_filter(s: &mut Cursive) {
     s.with_user_data(|v: &mut ReturnValues| {
         v.filtering = false;
         v.filter = "".to_string();
+        v.status_filter = false;
     });
 
     let mut table = s
-        .find_name::<InstancesView<Instance, BasicColumn>>("instances")
+        .find_name::<InstancesView<InstanceRow, BasicColumn>>("instances")
         .unwrap();
 
     let ud = s.user_data::<ReturnValues>().unwrap();
@@ -476,6 +808,45 @@ fn reset_filter(s: &mut Cursive) {
     update_bottom_bar(s);
 }
 
+/// Applies the name filter and the status filter to `instances`.
+fn filter_instances(ud: &ReturnValues, instances: Vec<InstanceRow>) -> Vec<InstanceRow> {
+    instances
+        .into_iter()
+        .filter(|i| {
+            ud.filter.is_empty()
+                || find_tag("Name".to_string(), i.tags.clone())
+                    .unwrap_or_default()
+                    .contains(&ud.filter)
+        })
+        .filter(|i| !ud.status_filter || i.needs_attention())
+        .collect()
+}
+
+/// Toggles showing only the instances with failing status checks or
+/// scheduled events.
+fn toggle_status_filter(s: &mut Cursive) {
+    s.with_user_data(|v: &mut ReturnValues| {
+        v.status_filter = !v.status_filter;
+    });
+
+    let ud = s.user_data::<ReturnValues>().unwrap();
+    let instances = filter_instances(ud, ud.instances.clone());
+
+    let mut table = s
+        .find_name::<InstancesView<InstanceRow, BasicColumn>>("instances")
+        .unwrap();
+
+    match table.item().cloned() {
+        Some(item) => {
+            table.set_instances(instances);
+            table.set_item(&item);
+        }
+        None => {
+            table.set_instances(instances);
+        }
+    }
+}
+
 fn on_search(s: &mut Cursive) {
     s.with_user_data(|v: &mut ReturnValues| {
         v.search = String::new();
@@ -485,7 +856,7 @@ fn on_search(s: &mut Cursive) {
     let mut overlay = Foo::default();
     overlay.set_on_search(|s, ss, _| {
         let mut table = s
-            .find_name::<InstancesView<Instance, BasicColumn>>("instances")
+            .find_name::<InstancesView<InstanceRow, BasicColumn>>("instances")
             .unwrap();
 
         let instances = table.items();
@@ -516,7 +887,7 @@ fn on_search(s: &mut Cursive) {
 
     overlay.set_on_search_next(|s, ss, _| {
         let mut table = s
-            .find_name::<InstancesView<Instance, BasicColumn>>("instances")
+            .find_name::<InstancesView<InstanceRow, BasicColumn>>("instances")
             .unwrap();
 
         let instances = table.items();
@@ -589,45 +960,146 @@ fn update_bottom_bar(s: &mut Cursive) {
     }
 }
 
-fn connect(instance: &Instance, profile: &str, region: &Region) -> Result<(), Box<dyn Error>> {
-    env::var("TMUX")?;
+fn connect(s: &mut Cursive, instance: &Instance) -> Result<(), Box<dyn Error>> {
+    let ud = s.user_data::<ReturnValues>().unwrap();
+
+    let command = start_session_command(
+        &ud.profile,
+        &ud.region,
+        &instance.instance_id.clone().unwrap(),
+        &ud.config.ssm,
+    );
+
+    let target = ud.config.terminal;
+
+    terminal::launch(s, target, &command)?;
+
+    Ok(())
+}
+
+/// Resolves how to reach the instance with ssh, directly or tunneled through
+/// ssm, pushing a key with EC2 Instance Connect when the key pair is not at
+/// hand.
+fn resolve_ssh_target(
+    s: &mut Cursive,
+    instance: &Instance,
+    via_ssm: bool,
+) -> Result<ssh::SshTarget, Box<dyn Error>> {
+    let ud = s.user_data::<ReturnValues>().unwrap();
+
+    // the AMI name only helps guessing the login user
+    let image_name = instance
+        .image_id
+        .as_ref()
+        .and_then(|image_id| get_image_name(&ud.region, &ud.profile, image_id).ok())
+        .flatten();
+
+    let mut ssh_target = if via_ssm {
+        let proxy = ssh_proxy_command(&ud.profile, &ud.region);
+        ssh::resolve_ssm(instance, image_name.as_deref(), &ud.config.ssh, proxy)?
+    } else {
+        ssh::resolve(instance, image_name.as_deref(), &ud.config.ssh)?
+    };
+
+    if ssh_target.key.is_none() && ud.config.ssh.instance_connect {
+        if ud.ephemeral_key.is_none() {
+            ud.ephemeral_key = Some(EphemeralKey::generate()?);
+        }
+
+        let key = ud.ephemeral_key.as_ref().unwrap();
+
+        let availability_zone = instance
+            .placement
+            .as_ref()
+            .and_then(|p| p.availability_zone.clone())
+            .unwrap_or_default();
+
+        send_ssh_public_key(
+            &ud.region,
+            &ud.profile,
+            &instance.instance_id.clone().unwrap(),
+            &availability_zone,
+            &ssh_target.user,
+            &key.public_key,
+        )?;
+
+        ssh_target.key = Some(key.private_key.clone());
+    }
+
+    Ok(ssh_target)
+}
+
+fn ssh_connect(s: &mut Cursive, instance: &Instance) -> Result<(), Box<dyn Error>> {
+    let command = resolve_ssh_target(s, instance, false)?.command();
+
+    let target = s.user_data::<ReturnValues>().unwrap().config.terminal;
+
+    terminal::launch(s, target, &command)?;
+
+    Ok(())
+}
+
+/// Connects to the EC2 serial console, which works without network access to
+/// the instance.
+fn serial_console(s: &mut Cursive, instance: &Instance) -> Result<(), Box<dyn Error>> {
+    let ud = s.user_data::<ReturnValues>().unwrap();
+
+    if ud.ephemeral_key.is_none() {
+        ud.ephemeral_key = Some(EphemeralKey::generate()?);
+    }
+
+    let key = ud.ephemeral_key.as_ref().unwrap();
+
+    let instance_id = instance.instance_id.clone().unwrap();
+
+    send_serial_console_ssh_public_key(&ud.region, &ud.profile, &instance_id, &key.public_key)?;
 
-    Command::new("tmux")
-        .arg("split-window")
-        .arg("-h")
-        .arg("bash")
-        .arg("-c")
-        .arg(format!(r#"aws ssm start-session --profile "{:?}" --region "{:?}" --target "{:}"; read -n 1 -s -r -p "Press any key to continue""#, profile, region.name(), instance.instance_id.clone().unwrap()))
-        .output()?;
+    let ssh_target = ssh::SshTarget {
+        user: format!("{}.port0", instance_id),
+        host: serial_console_host(&ud.region),
+        key: Some(key.private_key.clone()),
+        jump: None,
+        proxy: None,
+    };
+
+    let command = ssh_target.command();
+
+    let target = ud.config.terminal;
+
+    terminal::launch(s, target, &command)?;
 
     Ok(())
 }
 
 fn refresh(s: &mut Cursive) {
     let mut iv = s
-        .find_name::<InstancesView<Instance, BasicColumn>>("instances")
+        .find_name::<InstancesView<InstanceRow, BasicColumn>>("instances")
         .unwrap();
 
     let ud = s.user_data::<ReturnValues>().unwrap();
 
     match get_instances_with_region(&ud.profile, &ud.region) {
-        Ok(instances) => {
-            ud.instances = instances.clone();
+        Ok(mut instances) => {
+            ud.launched
+                .retain(|_, launched| launched.elapsed() < LAUNCH_HIGHLIGHT);
+
+            // keep the metrics, these are fetched again once outdated
+            for row in instances.iter_mut() {
+                row.metrics = ud
+                    .instances
+                    .iter()
+                    .find(|old| old.instance_id == row.instance_id)
+                    .and_then(|old| old.metrics.clone());
+
+                row.launched = row
+                    .instance_id
+                    .as_ref()
+                    .is_some_and(|id| ud.launched.contains_key(id));
+            }
 
-            let instances: Vec<Instance> = if ud.filter != "" {
-                instances
-                    .into_iter()
-                    .filter(|i| {
-                        find_tag("Name".to_string(), i.tags.clone())
-                            .unwrap()
-                            .contains(&ud.filter)
-                    })
-                    .collect()
-            } else {
-                instances
-            };
+            ud.instances = instances.clone();
 
-            iv.set_instances(instances);
+            iv.set_instances(filter_instances(ud, instances));
         }
         Err(err) => {
             let d = Dialog::around(TextView::new(format!(
@@ -646,19 +1118,97 @@ fn refresh(s: &mut Cursive) {
     }
 }
 
-fn new_ec2client(
-    region: &rusoto_core::Region,
+const METRICS_POLL_INTERVAL: Duration = Duration::from_secs(2);
+const METRICS_MAX_AGE: Duration = Duration::from_secs(5 * 60);
+
+/// Fetches the metrics of the running instances in view without metrics, or
+/// with outdated ones. Only one fetch runs at a time.
+fn load_visible_metrics(s: &mut Cursive) {
+    let ud = s.user_data::<ReturnValues>().unwrap();
+    if ud.metrics_loading {
+        return;
+    }
+
+    let (region, profile, range) = (ud.region.clone(), ud.profile.clone(), ud.metrics_range);
+
+    let instance_ids: Vec<String> =
+        match s.find_name::<InstancesView<InstanceRow, BasicColumn>>("instances") {
+            Some(table) => table
+                .visible_items()
+                .iter()
+                .filter(|row| row.state.as_ref().and_then(|state| state.code) == Some(16))
+                .filter(|row| match &row.metrics {
+                    Some(metrics) => {
+                        metrics.range != range || metrics.fetched.elapsed() > METRICS_MAX_AGE
+                    }
+                    None => true,
+                })
+                .filter_map(|row| row.instance_id.clone())
+                .collect(),
+            None => return,
+        };
+
+    if instance_ids.is_empty() {
+        return;
+    }
+
+    s.with_user_data(|v: &mut ReturnValues| {
+        v.metrics_loading = true;
+    });
+
+    let cb_sink = s.cb_sink().clone();
+
+    thread::spawn(move || {
+        // on errors the instances get empty metrics, so these are retried once
+        // outdated instead of on every poll
+        let metrics = get_metrics(&region, &profile, &instance_ids, range).unwrap_or_else(|_| {
+            instance_ids
+                .iter()
+                .map(|id| (id.clone(), InstanceMetrics::new(range)))
+                .collect()
+        });
+
+        let _ = cb_sink.send(Box::new(move |s: &mut Cursive| {
+            s.with_user_data(|v: &mut ReturnValues| {
+                v.metrics_loading = false;
+
+                for row in v.instances.iter_mut() {
+                    row.set_metrics(&metrics);
+                }
+            });
+
+            s.call_on_name(
+                "instances",
+                |v: &mut InstancesView<InstanceRow, BasicColumn>| {
+                    v.update_items(|row| row.set_metrics(&metrics));
+                },
+            );
+        }));
+    });
+}
+
+fn get_image_name(
+    region: &Region,
     profile: &str,
-) -> Result<rusoto_ec2::Ec2Client, rusoto_core::request::TlsError> {
-    let aws_creds_dir: String =
-        dirs::home_dir().unwrap().to_str().unwrap().to_owned() + "/.aws/credentials";
-    let provider: ProfileProvider = ProfileProvider::with_configuration(aws_creds_dir, profile);
+    image_id: &str,
+) -> Result<Option<String>, Box<dyn Error>> {
+    let client = new_ec2client(region, profile)?;
+
+    let req = DescribeImagesRequest {
+        image_ids: Some(vec![image_id.to_string()]),
+        ..Default::default()
+    };
 
-    let http_client = HttpClient::new()?;
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
 
-    let client = Ec2Client::new_with(http_client, provider, region.clone());
+    let response = runtime.block_on(client.describe_images(req))?;
 
-    Ok(client)
+    Ok(response
+        .images
+        .unwrap_or_default()
+        .into_iter()
+        .next()
+        .and_then(|image| image.name))
 }
 
 fn get_instance_log(
@@ -732,197 +1282,4281 @@ fn instance_log(siv: &mut Cursive, instance: &Instance) {
     }
 }
 
-fn event_view<V: View + 'static>(v: V) -> OnEventView<V> {
-    OnEventView::new(v)
-        .on_event(Key::Esc, |s| {
-            s.pop_layer();
-        })
-        .on_event('q', |s| {
-            s.pop_layer();
-        })
-}
+fn get_instance_screenshot(
+    region: &Region,
+    profile: &str,
+    instance: &Instance,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let client = new_ec2client(region, profile)?;
 
-fn instance_details(siv: &mut Cursive, instance: &Instance) {
-    let mut dl = LinearLayout::new(Orientation::Vertical);
+    let req = rusoto_ec2::GetConsoleScreenshotRequest {
+        instance_id: instance.instance_id.clone().unwrap(),
+        ..Default::default()
+    };
 
-    let instance = instance.clone();
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
 
-    let dialog_title = TextView::new(format!(
-        "{} ({:})",
-        built_info::PKG_NAME,
-        instance.instance_id.clone().unwrap()
-    ))
-    .h_align(HAlign::Center)
-    .with_name("title");
+    let ft = client.get_console_screenshot(req);
 
-    dl.add_child(dialog_title);
+    let response = runtime.block_on(ft)?;
 
-    let canvas = Canvas::new(instance).with_draw(|instance, printer| {
-        let x = [
-            ("instance-id", &instance.instance_id.clone().unwrap()),
-            (
-                "name",
-                &find_tag("name".to_string(), instance.tags.clone())
-                    .unwrap_or_else(|| "".to_string()),
-            ),
-            (
-                "architecture",
-                &instance
-                    .architecture
-                    .clone()
-                    .unwrap_or_else(|| "".to_string()),
-            ),
-            (
-                "vpc-id",
-                &instance.vpc_id.clone().unwrap_or_else(|| "".to_string()),
-            ),
-            (
-                "subnet_type",
-                &instance.subnet_id.clone().unwrap_or_else(|| "".to_string()),
-            ),
-            (
-                "instance_type",
-                &instance
-                    .instance_type
-                    .clone()
-                    .unwrap_or_else(|| "".to_string()),
-            ),
-            (
-                "key_name",
-                &instance.key_name.clone().unwrap_or_else(|| "".to_string()),
-            ),
-            ("state", &instance.state.clone().unwrap().name.unwrap()),
-            (
-                "public ip",
-                &instance
-                    .public_ip_address
-                    .clone()
-                    .unwrap_or_else(|| "".to_string()),
-            ),
-            (
-                "public dns",
-                &instance
-                    .public_dns_name
-                    .clone()
-                    .unwrap_or_else(|| "".to_string()),
-            ),
-            (
-                "private ip",
-                &instance
-                    .private_ip_address
-                    .clone()
-                    .unwrap_or_else(|| "".to_string()),
-            ),
-            (
-                "private dns",
-                &instance
-                    .private_dns_name
-                    .clone()
-                    .unwrap_or_else(|| "".to_string()),
-            ),
-            (
-                "placement",
-                &instance.placement.clone().unwrap().group_name.unwrap(),
-            ),
-            (
-                "lifecycle",
-                &instance
-                    .instance_lifecycle
-                    .clone()
-                    .unwrap_or_else(|| "".to_string()),
-            ),
-            (
-                "image-id",
-                &instance.image_id.clone().unwrap_or_else(|| "".to_string()),
-            ),
-            (
-                "ramdisk-id",
-                &instance
-                    .ramdisk_id
-                    .clone()
-                    .unwrap_or_else(|| "".to_string()),
-            ),
-            (
-                "root device",
-                &format!(
-                    "{:} ({:})",
-                    &instance
-                        .root_device_name
-                        .clone()
-                        .unwrap_or_else(|| "".to_string()),
-                    &instance
-                        .root_device_type
-                        .clone()
-                        .unwrap_or_else(|| "".to_string()),
-                ),
-            ),
-            ("state", &instance.state.clone().unwrap().name.unwrap()),
-            //("state-reason", &instance.state_reason.clone().unwrap().message.unwrap()),
-        ];
-
-        for (i, pair) in x.iter().enumerate() {
-            printer.with_color(
-                ColorStyle::new(PaletteColor::TitleSecondary, PaletteColor::Background),
-                |p| p.print((0, i + 1), &format!("{:>20}:", pair.0)),
-            );
-            printer.print((22, i + 1), &pair.1.to_string());
-        }
+    let image_data = response.image_data.unwrap_or_default();
 
-        let mut y = 22;
+    Ok(base64::decode(&image_data)?)
+}
 
-        printer.with_color(
-            ColorStyle::new(PaletteColor::TitleSecondary, PaletteColor::Background),
-            |p| p.print((0, y), "security groups"),
+fn instance_screenshot(siv: &mut Cursive, instance: &Instance) {
+    let ud = siv.user_data::<ReturnValues>().unwrap();
+
+    match get_instance_screenshot(&ud.region, &ud.profile, instance)
+        .and_then(|buf| Ok(ScreenshotView::from_jpeg(&buf)?))
+    {
+        Ok(view) => {
+            let mut dl = LinearLayout::new(Orientation::Vertical);
+
+            let dialog_title = TextView::new(format!(
+                "{} ({:})",
+                built_info::PKG_NAME,
+                instance.instance_id.clone().unwrap()
+            ))
+            .h_align(HAlign::Center)
+            .with_name("title");
+
+            dl.add_child(dialog_title);
+
+            dl.add_child(ResizedView::new(
+                SizeConstraint::Full,
+                SizeConstraint::Full,
+                view,
+            ));
+
+            let dl = event_view(dl);
+
+            siv.add_fullscreen_layer(dl);
+        }
+        Err(err) => {
+            error_dialog(
+                siv,
+                "Error",
+                &format!("Could not retrieve the console screenshot.\n\n{}", err),
+            );
+        }
+    }
+}
+
+/// Interval between polls while following a log stream.
+const LOG_FOLLOW_INTERVAL: Duration = Duration::from_secs(5);
+
+struct LogsState {
+    query: EventQuery,
+    // shared with the follow thread, which moves it along as events arrive
+    cursor: Arc<Mutex<LogCursor>>,
+    follow: Option<Arc<AtomicBool>>,
+}
+
+fn matches_instance(name: &str, patterns: &[String]) -> bool {
+    let name = name.to_lowercase();
+    patterns.iter().any(|p| name.contains(p))
+}
+
+/// Returns the lowercased id and name tag of the selected instance, as log
+/// groups and streams are commonly named after them.
+fn instance_patterns(s: &mut Cursive) -> Vec<String> {
+    let table = s
+        .find_name::<InstancesView<InstanceRow, BasicColumn>>("instances")
+        .unwrap();
+
+    match table.item() {
+        Some(instance) => vec![
+            instance.instance_id.clone(),
+            find_tag("name".to_string(), instance.tags.clone()),
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|p| !p.is_empty())
+        .map(|p| p.to_lowercase())
+        .collect(),
+        None => vec![],
+    }
+}
+
+fn cloudwatch_logs(s: &mut Cursive) {
+    let patterns = instance_patterns(s);
+
+    let ud = s.user_data::<ReturnValues>().unwrap();
+
+    let names: Vec<String> = match get_log_groups(&ud.region, &ud.profile) {
+        Ok(groups) => groups
+            .into_iter()
+            .filter_map(|group| group.log_group_name)
+            .collect(),
+        Err(err) => {
+            error_dialog(
+                s,
+                "Error",
+                &format!("Could not retrieve log groups.\n\n{}", err),
+            );
+            return;
+        }
+    };
+
+    let instance_only = Rc::new(Cell::new(false));
+
+    let mut select = SelectView::<String>::new();
+    select.add_all_str(names.clone());
+
+    let (only, p) = (instance_only.clone(), patterns.clone());
+    select.set_on_submit(move |s, group: &str| log_streams(s, group, &p, only.clone()));
+
+    let d = Dialog::around(
+        select
+            .with_name("log_groups")
+            .scrollable()
+            .fixed_size((60, 20)),
+    )
+    .title("Log groups (i: instance only)")
+    .button("Cancel", |s| {
+        s.pop_layer();
+    });
+
+    s.add_layer(event_view(d).on_event('i', move |s| {
+        instance_only.set(!instance_only.get());
+
+        let names: Vec<String> = names
+            .iter()
+            .filter(|name| !instance_only.get() || matches_instance(name, &patterns))
+            .cloned()
+            .collect();
+
+        s.call_on_name("log_groups", |v: &mut SelectView<String>| {
+            v.clear();
+            v.add_all_str(names);
+        });
+    }));
+}
+
+fn log_streams(s: &mut Cursive, group: &str, patterns: &[String], instance_only: Rc<Cell<bool>>) {
+    let ud = s.user_data::<ReturnValues>().unwrap();
+
+    let streams = match get_log_streams(&ud.region, &ud.profile, group) {
+        Ok(streams) => streams,
+        Err(err) => {
+            error_dialog(
+                s,
+                "Error",
+                &format!("Could not retrieve log streams.\n\n{}", err),
+            );
+            return;
+        }
+    };
+
+    let items: Vec<(String, Option<String>)> = std::iter::once(("(all streams)".to_string(), None))
+        .chain(streams.into_iter().filter_map(|stream| {
+            let name = stream.log_stream_name?;
+            let last = stream
+                .last_event_timestamp
+                .map(cloudman_rs::logs::format_timestamp)
+                .unwrap_or_default();
+
+            Some((format!("{:<50} {}", name, last), Some(name)))
+        }))
+        .collect();
+
+    let filtered = move |only: bool, patterns: &[String]| -> Vec<(String, Option<String>)> {
+        items
+            .iter()
+            .filter(|(_, name)| match name {
+                Some(name) => !only || matches_instance(name, patterns),
+                None => true,
+            })
+            .cloned()
+            .collect()
+    };
+
+    let mut select = SelectView::<Option<String>>::new();
+    select.add_all(filtered(instance_only.get(), patterns));
+
+    let group = group.to_string();
+    select.set_on_submit(move |s, stream: &Option<String>| {
+        log_events(s, EventQuery::new(&group, stream.clone()));
+    });
+
+    let d = Dialog::around(
+        select
+            .with_name("log_streams")
+            .scrollable()
+            .fixed_size((72, 20)),
+    )
+    .title("Log streams (i: instance only)")
+    .button("Cancel", |s| {
+        s.pop_layer();
+    });
+
+    let patterns = patterns.to_vec();
+    s.add_layer(event_view(d).on_event('i', move |s| {
+        instance_only.set(!instance_only.get());
+
+        let items = filtered(instance_only.get(), &patterns);
+
+        s.call_on_name("log_streams", |v: &mut SelectView<Option<String>>| {
+            v.clear();
+            v.add_all(items);
+        });
+    }));
+}
+
+fn log_events_title(state: &LogsState) -> String {
+    format!(
+        "{} ({} / {})  range: {}  filter: {}  follow: {}  [f: filter, t: range, F: follow]",
+        built_info::PKG_NAME,
+        state.query.group,
+        state
+            .query
+            .stream
+            .clone()
+            .unwrap_or_else(|| "*".to_string()),
+        state.query.range.name(),
+        state.query.pattern,
+        if state.follow.is_some() { "on" } else { "off" },
+    )
+}
+
+fn fetch_log_events(s: &mut Cursive, query: &EventQuery) -> Option<(Vec<String>, LogCursor)> {
+    let ud = s.user_data::<ReturnValues>().unwrap();
+
+    let start = query.range.start();
+
+    match filter_log_events(&ud.region, &ud.profile, query, start) {
+        Ok(events) => {
+            let mut cursor = LogCursor::new(start);
+            let events = cursor.advance(events);
+
+            Some((events.iter().flat_map(format_event).collect(), cursor))
+        }
+        Err(err) => {
+            error_dialog(
+                s,
+                "Error",
+                &format!("Could not retrieve log events.\n\n{}", err),
+            );
+            None
+        }
+    }
+}
+
+fn log_events(s: &mut Cursive, query: EventQuery) {
+    let (lines, cursor) = match fetch_log_events(s, &query) {
+        Some(result) => result,
+        None => return,
+    };
+
+    let state = Rc::new(RefCell::new(LogsState {
+        query,
+        cursor: Arc::new(Mutex::new(cursor)),
+        follow: None,
+    }));
+
+    let mut dl = LinearLayout::new(Orientation::Vertical);
+
+    dl.add_child(
+        TextView::new(log_events_title(&state.borrow()))
+            .h_align(HAlign::Center)
+            .with_name("log_events_title"),
+    );
+
+    dl.add_child(ResizedView::new(
+        SizeConstraint::Full,
+        SizeConstraint::Full,
+        LogView::with_lines(lines).with_name("log_events"),
+    ));
+
+    let (close_state, quit_state, filter_state, range_state, follow_state) = (
+        state.clone(),
+        state.clone(),
+        state.clone(),
+        state.clone(),
+        state,
+    );
+
+    s.add_fullscreen_layer(
+        OnEventView::new(dl)
+            .on_event(Key::Esc, move |s| {
+                stop_follow_log_events(s, &close_state);
+                s.pop_layer();
+            })
+            .on_event('q', move |s| {
+                stop_follow_log_events(s, &quit_state);
+                s.pop_layer();
+            })
+            .on_event('f', move |s| {
+                let state = filter_state.clone();
+                let pattern = state.borrow().query.pattern.clone();
+
+                let apply = move |s: &mut Cursive, pattern: &str| {
+                    state.borrow_mut().query.pattern = pattern.to_string();
+                    s.pop_layer();
+                    reload_log_events(s, &state);
+                };
+
+                let submit = apply.clone();
+                let d = Dialog::around(
+                    EditView::new()
+                        .content(pattern)
+                        .on_submit(move |s, pattern| submit(s, pattern))
+                        .with_name("log_filter")
+                        .fixed_width(50),
+                )
+                .title("Filter pattern")
+                .button("Ok", move |s| {
+                    let pattern = s
+                        .call_on_name("log_filter", |v: &mut EditView| v.get_content())
+                        .unwrap();
+                    apply(s, &pattern);
+                })
+                .button("Cancel", |s| {
+                    s.pop_layer();
+                });
+
+                s.add_layer(OnEventView::new(d).on_event(Key::Esc, |s| {
+                    s.pop_layer();
+                }));
+            })
+            .on_event('t', move |s| {
+                {
+                    let mut state = range_state.borrow_mut();
+                    state.query.range = state.query.range.next();
+                }
+
+                reload_log_events(s, &range_state);
+            })
+            .on_event('F', move |s| {
+                if follow_state.borrow().follow.is_some() {
+                    stop_follow_log_events(s, &follow_state);
+                } else {
+                    start_follow_log_events(s, &follow_state);
+                }
+            }),
+    );
+}
+
+fn reload_log_events(s: &mut Cursive, state: &Rc<RefCell<LogsState>>) {
+    let query = state.borrow().query.clone();
+
+    if let Some((lines, cursor)) = fetch_log_events(s, &query) {
+        let following = state.borrow().follow.is_some();
+
+        stop_follow_log_events(s, state);
+        // a stopped thread still holds the old cursor
+        state.borrow_mut().cursor = Arc::new(Mutex::new(cursor));
+
+        s.call_on_name("log_events", |v: &mut LogView| v.set_lines(lines));
+
+        if following {
+            start_follow_log_events(s, state);
+        }
+
+        let title = log_events_title(&state.borrow());
+        s.call_on_name("log_events_title", |v: &mut TextView| v.set_content(title));
+    }
+}
+
+fn start_follow_log_events(s: &mut Cursive, state: &Rc<RefCell<LogsState>>) {
+    let running = Arc::new(AtomicBool::new(true));
+
+    let ud = s.user_data::<ReturnValues>().unwrap();
+    let (region, profile) = (ud.region.clone(), ud.profile.clone());
+
+    let (query, shared_cursor) = {
+        let mut state = state.borrow_mut();
+        state.follow = Some(running.clone());
+        (state.query.clone(), state.cursor.clone())
+    };
+
+    let mut cursor = shared_cursor.lock().unwrap().clone();
+
+    let cb_sink = s.cb_sink().clone();
+
+    thread::spawn(move || {
+        while running.load(atomic::Ordering::Relaxed) {
+            thread::sleep(LOG_FOLLOW_INTERVAL);
+
+            if !running.load(atomic::Ordering::Relaxed) {
+                break;
+            }
+
+            // from the last timestamp, as more events may have arrived in the
+            // same millisecond
+            let events = match filter_log_events(&region, &profile, &query, cursor.last) {
+                Ok(events) => cursor.advance(events),
+                Err(_) => continue,
+            };
+
+            if events.is_empty() {
+                continue;
+            }
+
+            let lines: Vec<String> = events.iter().flat_map(format_event).collect();
+            let (shared, position) = (shared_cursor.clone(), cursor.clone());
+
+            if cb_sink
+                .send(Box::new(move |s: &mut Cursive| {
+                    s.call_on_name("log_events", |v: &mut LogView| v.append_lines(lines));
+                    *shared.lock().unwrap() = position;
+                }))
+                .is_err()
+            {
+                break;
+            }
+        }
+    });
+
+    s.call_on_name("log_events", |v: &mut LogView| v.set_follow(true));
+
+    let title = log_events_title(&state.borrow());
+    s.call_on_name("log_events_title", |v: &mut TextView| v.set_content(title));
+}
+
+fn stop_follow_log_events(s: &mut Cursive, state: &Rc<RefCell<LogsState>>) {
+    if let Some(running) = state.borrow_mut().follow.take() {
+        running.store(false, atomic::Ordering::Relaxed);
+    }
+
+    s.call_on_name("log_events", |v: &mut LogView| v.set_follow(false));
+
+    let title = log_events_title(&state.borrow());
+    s.call_on_name("log_events_title", |v: &mut TextView| v.set_content(title));
+}
+
+/// Interval between polls of a running Logs Insights query.
+const INSIGHTS_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+fn logs_insights(s: &mut Cursive) {
+    let patterns = instance_patterns(s);
+
+    let ud = s.user_data::<ReturnValues>().unwrap();
+
+    let names: Vec<String> = match get_log_groups(&ud.region, &ud.profile) {
+        Ok(groups) => groups
+            .into_iter()
+            .filter_map(|group| group.log_group_name)
+            .collect(),
+        Err(err) => {
+            error_dialog(
+                s,
+                "Error",
+                &format!("Could not retrieve log groups.\n\n{}", err),
+            );
+            return;
+        }
+    };
+
+    let mut groups = ListView::new();
+    for (i, name) in names.iter().enumerate() {
+        groups.add_child(
+            name,
+            Checkbox::new()
+                .with_checked(matches_instance(name, &patterns))
+                .with_name(format!("insights_group_{}", i)),
+        );
+    }
+
+    let mut range = SelectView::<TimeRange>::new().popup();
+    for r in TimeRange::all() {
+        range.add_item(r.name(), r);
+    }
+    let range = range.selected(1);
+
+    let mut layout = LinearLayout::new(Orientation::Vertical);
+    layout.add_child(TextView::new("Query"));
+    layout.add_child(
+        TextArea::new()
+            .content(DEFAULT_QUERY)
+            .with_name("insights_query")
+            .fixed_size((80, 6)),
+    );
+    layout.add_child(
+        LinearLayout::new(Orientation::Horizontal)
+            .child(TextView::new("Time range: "))
+            .child(range.with_name("insights_range")),
+    );
+    layout.add_child(TextView::new("Log groups"));
+    layout.add_child(groups.scrollable().fixed_height(10));
+
+    let d = Dialog::around(layout)
+        .title("Logs Insights")
+        .button("Run", move |s| {
+            let query = s
+                .call_on_name("insights_query", |v: &mut TextArea| {
+                    v.get_content().to_string()
+                })
+                .unwrap();
+
+            let range = s
+                .call_on_name("insights_range", |v: &mut SelectView<TimeRange>| {
+                    v.selection()
+                })
+                .unwrap()
+                .map(|r| *r)
+                .unwrap_or(TimeRange::Hour1);
+
+            let groups: Vec<String> = names
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| {
+                    s.call_on_name(&format!("insights_group_{}", i), |v: &mut Checkbox| {
+                        v.is_checked()
+                    })
+                    .unwrap_or(false)
+                })
+                .map(|(_, name)| name.clone())
+                .collect();
+
+            if groups.is_empty() {
+                error_dialog(s, "Error", "Select at least one log group.");
+                return;
+            }
+
+            run_insights_query(s, &groups, &query, range);
+        })
+        .button("Cancel", |s| {
+            s.pop_layer();
+        });
+
+    s.add_layer(OnEventView::new(d).on_event(Key::Esc, |s| {
+        s.pop_layer();
+    }));
+}
+
+fn run_insights_query(s: &mut Cursive, groups: &[String], query: &str, range: TimeRange) {
+    let ud = s.user_data::<ReturnValues>().unwrap();
+    let (region, profile) = (ud.region.clone(), ud.profile.clone());
+
+    let query_id = match start_query(&region, &profile, groups, query, range) {
+        Ok(query_id) => query_id,
+        Err(err) => {
+            error_dialog(
+                s,
+                "Error",
+                &format!("Could not start the query.\n\n{}", err),
+            );
+            return;
+        }
+    };
+
+    let running = Arc::new(AtomicBool::new(true));
+
+    let cancel = running.clone();
+    let d = Dialog::around(TextView::new("Scheduled").with_name("insights_progress"))
+        .title("Running query")
+        .button("Cancel", move |s| {
+            cancel.store(false, atomic::Ordering::Relaxed);
+            s.pop_layer();
+        });
+
+    s.add_layer(d);
+
+    let cb_sink = s.cb_sink().clone();
+
+    thread::spawn(move || {
+        let started = std::time::Instant::now();
+
+        loop {
+            thread::sleep(INSIGHTS_POLL_INTERVAL);
+
+            if !running.load(atomic::Ordering::Relaxed) {
+                let _ = stop_query(&region, &profile, &query_id);
+                break;
+            }
+
+            // completed and failed queries are not polled again
+            let mut finished = true;
+
+            let cb: Box<dyn FnOnce(&mut Cursive) + Send> =
+                match get_query_results(&region, &profile, &query_id) {
+                    Ok(results) if results.is_done() => {
+                        let running = running.clone();
+                        Box::new(move |s: &mut Cursive| {
+                            if running.load(atomic::Ordering::Relaxed) {
+                                s.pop_layer();
+                                insights_results(s, results);
+                            }
+                        })
+                    }
+                    Ok(results) => {
+                        finished = false;
+
+                        let status = format!(
+                        "{} ({}s)\n\nrecords matched: {}\nrecords scanned: {}\nbytes scanned: {}",
+                        results.status,
+                        started.elapsed().as_secs(),
+                        results.records_matched,
+                        results.records_scanned,
+                        results.bytes_scanned,
+                    );
+
+                        Box::new(move |s: &mut Cursive| {
+                            s.call_on_name("insights_progress", |v: &mut TextView| {
+                                v.set_content(status)
+                            });
+                        })
+                    }
+                    Err(err) => {
+                        let err = err.to_string();
+                        let running = running.clone();
+                        Box::new(move |s: &mut Cursive| {
+                            if running.load(atomic::Ordering::Relaxed) {
+                                s.pop_layer();
+                                error_dialog(
+                                    s,
+                                    "Error",
+                                    &format!("Could not retrieve query results.\n\n{}", err),
+                                );
+                            }
+                        })
+                    }
+                };
+
+            let done = cb_sink.send(cb).is_err();
+
+            if done || finished || !running.load(atomic::Ordering::Relaxed) {
+                break;
+            }
+        }
+    });
+}
+
+fn insights_results(s: &mut Cursive, results: QueryResults) {
+    let mut table = InstancesView::<QueryRow, QueryColumn>::scrollable(&results.rows);
+    for column in results.columns() {
+        table = table.column(column);
+    }
+
+    let mut dl = LinearLayout::new(Orientation::Vertical);
+
+    dl.add_child(
+        TextView::new(format!(
+            "{} (insights: {} rows, {} records matched, {} scanned)  [<, >: sort, I: invert, e: export]",
+            built_info::PKG_NAME,
+            results.rows.len(),
+            results.records_matched,
+            results.records_scanned,
+        ))
+        .h_align(HAlign::Center),
+    );
+
+    dl.add_child(ResizedView::new(
+        SizeConstraint::Full,
+        SizeConstraint::Full,
+        table.with_name("insights_results"),
+    ));
+
+    let results = Rc::new(results);
+
+    s.add_fullscreen_layer(event_view(dl).on_event('e', move |s| {
+        let results = results.clone();
+
+        let export = move |s: &mut Cursive, path: &str| {
+            let content = if path.ends_with(".json") {
+                results.to_json()
+            } else {
+                results.to_csv()
+            };
+
+            s.pop_layer();
+
+            match std::fs::write(path, content) {
+                Ok(_) => {
+                    let d = Dialog::around(TextView::new(format!(
+                        "Exported {} rows to {}.",
+                        results.rows.len(),
+                        path
+                    )))
+                    .title("Export")
+                    .button("Ok", |s| {
+                        s.pop_layer();
+                    });
+
+                    s.add_layer(event_view(d));
+                }
+                Err(err) => error_dialog(
+                    s,
+                    "Error",
+                    &format!("Could not export the results.\n\n{}", err),
+                ),
+            }
+        };
+
+        let submit = export.clone();
+        let d = Dialog::around(
+            EditView::new()
+                .content("cloudman-insights.csv")
+                .on_submit(move |s, path| submit(s, path))
+                .with_name("insights_export")
+                .fixed_width(50),
+        )
+        .title("Export to (.csv or .json)")
+        .button("Ok", move |s| {
+            let path = s
+                .call_on_name("insights_export", |v: &mut EditView| v.get_content())
+                .unwrap();
+            export(s, &path);
+        })
+        .button("Cancel", |s| {
+            s.pop_layer();
+        });
+
+        s.add_layer(OnEventView::new(d).on_event(Key::Esc, |s| {
+            s.pop_layer();
+        }));
+    }));
+}
+
+fn port_forward(s: &mut Cursive) {
+    let table = s
+        .find_name::<InstancesView<InstanceRow, BasicColumn>>("instances")
+        .unwrap();
+
+    let instance_id = match table.item() {
+        Some(instance) => instance.instance_id.clone().unwrap(),
+        None => return,
+    };
+
+    let form = ListView::new()
+        .child(
+            "local port",
+            EditView::new()
+                .with_name("tunnel_local_port")
+                .fixed_width(10),
+        )
+        .child(
+            "remote host",
+            EditView::new()
+                .with_name("tunnel_remote_host")
+                .fixed_width(40),
+        )
+        .child(
+            "remote port",
+            EditView::new()
+                .with_name("tunnel_remote_port")
+                .fixed_width(10),
+        );
+
+    let title = format!("Port forwarding ({})", instance_id);
+
+    let d = Dialog::around(form)
+        .title(title)
+        .button("Start", move |s| {
+            let content = |s: &mut Cursive, name: &str| {
+                s.call_on_name(name, |v: &mut EditView| v.get_content())
+                    .unwrap()
+                    .trim()
+                    .to_string()
+            };
+
+            let local_port = content(s, "tunnel_local_port");
+            let remote_host = content(s, "tunnel_remote_host");
+            let remote_port = content(s, "tunnel_remote_port");
+
+            let (local_port, remote_port) =
+                match (local_port.parse::<u16>(), remote_port.parse::<u16>()) {
+                    (Ok(local_port), Ok(remote_port)) => (local_port, remote_port),
+                    _ => {
+                        error_dialog(
+                            s,
+                            "Error",
+                            "The ports should be numbers between 1 and 65535.",
+                        );
+                        return;
+                    }
+                };
+
+            let remote_host = if remote_host.is_empty() {
+                None
+            } else {
+                Some(remote_host)
+            };
+
+            let ud = s.user_data::<ReturnValues>().unwrap();
+
+            let command = port_forwarding_command(
+                &ud.profile,
+                &ud.region,
+                &instance_id,
+                local_port,
+                remote_host.as_deref(),
+                remote_port,
+            );
+
+            match ud.tunnels.start(
+                &command,
+                &instance_id,
+                local_port,
+                remote_host.clone(),
+                remote_port,
+            ) {
+                Ok(pid) => {
+                    s.pop_layer();
+
+                    let message = format!(
+                        "Forwarding localhost:{} to {}:{} through {} (pid {}).",
+                        local_port,
+                        remote_host.unwrap_or_else(|| "localhost".to_string()),
+                        remote_port,
+                        instance_id,
+                        pid
+                    );
+
+                    // whether the session came up shows after a while, the
+                    // user interface keeps running meanwhile
+                    let cb_sink = s.cb_sink().clone();
+
+                    thread::spawn(move || {
+                        thread::sleep(TUNNEL_STARTUP_GRACE);
+
+                        let _ = cb_sink.send(Box::new(move |s: &mut Cursive| {
+                            let ud = s.user_data::<ReturnValues>().unwrap();
+
+                            match ud.tunnels.check_started(pid) {
+                                Ok(()) => {
+                                    let d = Dialog::around(TextView::new(message))
+                                        .title("Port forwarding")
+                                        .button("Ok", |s| {
+                                            s.pop_layer();
+                                        });
+
+                                    s.add_layer(event_view(d));
+                                }
+                                Err(err) => error_dialog(
+                                    s,
+                                    "Error",
+                                    &format!("Could not start port forwarding.\n\n{}", err),
+                                ),
+                            }
+
+                            refresh_tunnels(s);
+                        }));
+                    });
+                }
+                Err(err) => error_dialog(
+                    s,
+                    "Error",
+                    &format!("Could not start port forwarding.\n\n{}", err),
+                ),
+            }
+        })
+        .button("Cancel", |s| {
+            s.pop_layer();
+        });
+
+    s.add_layer(OnEventView::new(d).on_event(Key::Esc, |s| {
+        s.pop_layer();
+    }));
+}
+
+fn refresh_tunnels(s: &mut Cursive) {
+    let tunnels = s.user_data::<ReturnValues>().unwrap().tunnels.list();
+
+    s.call_on_name(
+        "tunnels",
+        |v: &mut InstancesView<TunnelInfo, TunnelColumn>| {
+            v.set_instances(tunnels);
+        },
+    );
+}
+
+fn tunnels(s: &mut Cursive) {
+    let tunnels = s.user_data::<ReturnValues>().unwrap().tunnels.list();
+
+    let table = InstancesView::<TunnelInfo, TunnelColumn>::scrollable(&tunnels)
+        .column(TunnelColumn::Pid)
+        .column(TunnelColumn::InstanceID)
+        .column(TunnelColumn::LocalPort)
+        .column(TunnelColumn::Remote)
+        .column(TunnelColumn::Uptime)
+        .column(TunnelColumn::Status);
+
+    let mut dl = LinearLayout::new(Orientation::Vertical);
+
+    dl.add_child(
+        TextView::new(format!(
+            "{} (tunnels)  [d: stop, r: refresh]",
+            built_info::PKG_NAME
+        ))
+        .h_align(HAlign::Center),
+    );
+
+    dl.add_child(ResizedView::new(
+        SizeConstraint::Full,
+        SizeConstraint::Full,
+        table.with_name("tunnels"),
+    ));
+
+    s.add_fullscreen_layer(
+        event_view(dl)
+            .on_event('d', |s| {
+                let pid = s
+                    .call_on_name(
+                        "tunnels",
+                        |v: &mut InstancesView<TunnelInfo, TunnelColumn>| {
+                            v.item().map(|tunnel| tunnel.pid)
+                        },
+                    )
+                    .flatten();
+
+                if let Some(pid) = pid {
+                    s.user_data::<ReturnValues>().unwrap().tunnels.stop(pid);
+                }
+
+                refresh_tunnels(s);
+            })
+            .on_event('r', refresh_tunnels),
+    );
+}
+
+/// Copies files from or to the selected instance with scp, directly or
+/// tunneled through ssm.
+fn copy_files(s: &mut Cursive) {
+    let table = s
+        .find_name::<InstancesView<InstanceRow, BasicColumn>>("instances")
+        .unwrap();
+
+    let instance = match table.item() {
+        Some(instance) => instance.clone(),
+        None => return,
+    };
+
+    // without a public address the instance is most likely only reachable
+    // through ssm
+    let via_ssm = instance.public_ip_address.is_none();
+
+    let list = ListView::new()
+        .child(
+            "Local path",
+            EditView::new()
+                .content(".")
+                .with_name("copy_local")
+                .fixed_width(50),
+        )
+        .child(
+            "Remote path",
+            EditView::new()
+                .content("~/")
+                .with_name("copy_remote")
+                .fixed_width(50),
+        )
+        .child(
+            "Through SSM",
+            Checkbox::new().with_checked(via_ssm).with_name("copy_ssm"),
+        );
+
+    let push_instance = instance.clone();
+    let d = Dialog::around(list)
+        .title(format!(
+            "Copy files ({})",
+            instance.instance_id.clone().unwrap_or_default()
+        ))
+        .button("Push", move |s| start_copy(s, &push_instance, true))
+        .button("Pull", move |s| start_copy(s, &instance, false))
+        .button("Cancel", |s| {
+            s.pop_layer();
+        });
+
+    s.add_layer(OnEventView::new(d).on_event(Key::Esc, |s| {
+        s.pop_layer();
+    }));
+}
+
+fn start_copy(s: &mut Cursive, instance: &Instance, push: bool) {
+    let local = s
+        .call_on_name("copy_local", |v: &mut EditView| v.get_content())
+        .unwrap();
+    let remote = s
+        .call_on_name("copy_remote", |v: &mut EditView| v.get_content())
+        .unwrap();
+    let via_ssm = s
+        .call_on_name("copy_ssm", |v: &mut Checkbox| v.is_checked())
+        .unwrap();
+
+    if local.is_empty() || remote.is_empty() {
+        return;
+    }
+
+    s.pop_layer();
+
+    let ssh_target = match resolve_ssh_target(s, instance, via_ssm) {
+        Ok(ssh_target) => ssh_target,
+        Err(err) => {
+            error_dialog(s, "Error", &format!("Could not copy the files.\n\n{}", err));
+            return;
+        }
+    };
+
+    let (source, destination) = if push {
+        (local.to_string(), ssh_target.remote(&remote))
+    } else {
+        (ssh_target.remote(&remote), local.to_string())
+    };
+
+    let command = ssh_target.copy_command(&source, &destination);
+
+    // scp draws its progress meter on stdout, errors are kept for the dialog
+    let output = terminal::suspend(s, || {
+        std::process::Command::new(&command[0])
+            .args(&command[1..])
+            .stdout(std::process::Stdio::inherit())
+            .stderr(std::process::Stdio::piped())
+            .output()
+    });
+
+    match output {
+        Ok(output) if output.status.success() => {
+            let d = Dialog::around(TextView::new(format!(
+                "Copied {} to {}.",
+                source, destination
+            )))
+            .title("Copy files")
+            .button("Ok", |s| {
+                s.pop_layer();
+            });
+
+            s.add_layer(event_view(d));
+        }
+        Ok(output) => error_dialog(
+            s,
+            "Error",
+            &format!(
+                "Could not copy the files, scp exited with {}.\n\n{}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        ),
+        Err(err) => error_dialog(s, "Error", &format!("Could not copy the files.\n\n{}", err)),
+    }
+}
+
+const RUN_COMMAND_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Runs a script on the marked instances, or the selected one, using
+/// `AWS-RunShellScript` or `AWS-RunPowerShellScript` depending on the platform.
+fn run_command(s: &mut Cursive) {
+    let table = s
+        .find_name::<InstancesView<InstanceRow, BasicColumn>>("instances")
+        .unwrap();
+
+    let instances: Vec<Instance> = table
+        .marked_or_selected()
+        .into_iter()
+        .map(|row| row.instance.clone())
+        .collect();
+
+    if instances.is_empty() {
+        return;
+    }
+
+    let ud = s.user_data::<ReturnValues>().unwrap();
+    let last = ud.command_history.first().cloned().unwrap_or_default();
+
+    let ids: Vec<String> = instances
+        .iter()
+        .map(|i| i.instance_id.clone().unwrap_or_default())
+        .collect();
+
+    let mut dl = LinearLayout::new(Orientation::Vertical);
+    dl.add_child(TextView::new(format!("Instances: {}", ids.join(", "))));
+    dl.add_child(TextView::new(" "));
+    dl.add_child(
+        TextArea::new()
+            .content(last)
+            .with_name("run_command_script")
+            .min_height(5),
+    );
+
+    let d = Dialog::around(dl.fixed_width(80))
+        .title("Run command")
+        .button("Run", move |s| {
+            let script = s
+                .call_on_name("run_command_script", |v: &mut TextArea| {
+                    v.get_content().to_string()
+                })
+                .unwrap();
+
+            if script.trim().is_empty() {
+                return;
+            }
+
+            let history = &mut s.user_data::<ReturnValues>().unwrap().command_history;
+            history.retain(|c| *c != script);
+            history.insert(0, script.clone());
+
+            s.pop_layer();
+            start_run_command(s, &instances, &script);
+        })
+        .button("History", command_history)
+        .button("Cancel", |s| {
+            s.pop_layer();
+        });
+
+    s.add_layer(OnEventView::new(d).on_event(Key::Esc, |s| {
+        s.pop_layer();
+    }));
+}
+
+fn command_history(s: &mut Cursive) {
+    let history = s
+        .user_data::<ReturnValues>()
+        .unwrap()
+        .command_history
+        .clone();
+
+    if history.is_empty() {
+        return;
+    }
+
+    let mut select = SelectView::<String>::new().autojump();
+    for command in history {
+        select.add_item(command.lines().collect::<Vec<_>>().join("; "), command);
+    }
+
+    select.set_on_submit(|s, command: &String| {
+        let command = command.clone();
+        s.pop_layer();
+        s.call_on_name("run_command_script", |v: &mut TextArea| {
+            v.set_content(command)
+        });
+    });
+
+    let d = Dialog::around(select.scrollable().max_height(20)).title("History");
+
+    s.add_layer(event_view(d));
+}
+
+fn start_run_command(s: &mut Cursive, instances: &[Instance], script: &str) {
+    let ud = s.user_data::<ReturnValues>().unwrap();
+    let (region, profile) = (ud.region.clone(), ud.profile.clone());
+
+    let batches = command_batches(instances);
+    let (mut invocations, failed) = send_batches(&batches, |document, ids| {
+        send_command(&region, &profile, document, ids, script)
+    });
+
+    if invocations.is_empty() {
+        error_dialog(
+            s,
+            "Error",
+            &format!(
+                "Could not send the command.\n\n{}",
+                failed.unwrap_or_default()
+            ),
+        );
+        return;
+    }
+
+    command_results(s, script, &invocations);
+
+    // the instances that got the command are still followed
+    if let Some(err) = failed {
+        error_dialog(
+            s,
+            "Error",
+            &format!(
+                "The command was only sent to {} of {} instances.\n\n{}",
+                invocations.len(),
+                instances.len(),
+                err
+            ),
+        );
+    }
+
+    let cb_sink = s.cb_sink().clone();
+
+    thread::spawn(move || {
+        let running = Arc::new(AtomicBool::new(true));
+
+        while running.load(atomic::Ordering::Relaxed) {
+            thread::sleep(RUN_COMMAND_POLL_INTERVAL);
+
+            let done = update_invocations(&mut invocations, |invocation| {
+                get_command_invocation(&region, &profile, invocation)
+            });
+
+            if done {
+                running.store(false, atomic::Ordering::Relaxed);
+            }
+
+            let updated = invocations.clone();
+            let open = running.clone();
+            let cb = Box::new(move |s: &mut Cursive| {
+                let found = s.call_on_name(
+                    "command_results",
+                    |v: &mut InstancesView<Invocation, InvocationColumn>| {
+                        let selected = v.selected_item().unwrap_or(0);
+                        v.set_instances(updated);
+                        v.set_selected_item(selected);
+                    },
+                );
+
+                // stop polling once the results are closed
+                if found.is_none() {
+                    open.store(false, atomic::Ordering::Relaxed);
+                }
+            });
+
+            if cb_sink.send(cb).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+fn command_results(s: &mut Cursive, script: &str, invocations: &[Invocation]) {
+    let table = InstancesView::<Invocation, InvocationColumn>::scrollable(invocations)
+        .column(InvocationColumn::InstanceID)
+        .column(InvocationColumn::Name)
+        .column(InvocationColumn::Status)
+        .column(InvocationColumn::ExitCode)
+        .column(InvocationColumn::Output)
+        .on_submit(|s, _| {
+            let invocation = s
+                .call_on_name(
+                    "command_results",
+                    |v: &mut InstancesView<Invocation, InvocationColumn>| v.item().cloned(),
+                )
+                .flatten();
+
+            if let Some(invocation) = invocation {
+                let mut dl = LinearLayout::new(Orientation::Vertical);
+
+                dl.add_child(
+                    TextView::new(format!(
+                        "{} (output of {})",
+                        built_info::PKG_NAME,
+                        invocation.instance_id
+                    ))
+                    .h_align(HAlign::Center),
+                );
+
+                dl.add_child(ResizedView::new(
+                    SizeConstraint::Full,
+                    SizeConstraint::Full,
+                    LogView::with_lines(invocation.output_lines()),
+                ));
+
+                s.add_fullscreen_layer(event_view(dl));
+            }
+        });
+
+    let mut dl = LinearLayout::new(Orientation::Vertical);
+
+    dl.add_child(
+        TextView::new(format!(
+            "{} (run command: {})  [enter: output]",
+            built_info::PKG_NAME,
+            script.lines().collect::<Vec<_>>().join("; ")
+        ))
+        .h_align(HAlign::Center),
+    );
+
+    dl.add_child(ResizedView::new(
+        SizeConstraint::Full,
+        SizeConstraint::Full,
+        table.with_name("command_results"),
+    ));
+
+    s.add_fullscreen_layer(event_view(dl));
+}
+
+fn event_view<V: View + 'static>(v: V) -> OnEventView<V> {
+    OnEventView::new(v)
+        .on_event(Key::Esc, |s| {
+            s.pop_layer();
+        })
+        .on_event('q', |s| {
+            s.pop_layer();
+        })
+}
+
+fn instance_details(siv: &mut Cursive, instance: &Instance) {
+    let mut dl = LinearLayout::new(Orientation::Vertical);
+
+    let dialog_title = TextView::new(format!(
+        "{} ({:})  [tab, 1-7: switch tab, r: raw, v: volumes, u: user data, e: edit tags]",
+        built_info::PKG_NAME,
+        instance.instance_id.clone().unwrap_or_default()
+    ))
+    .h_align(HAlign::Center)
+    .with_name("title");
+
+    dl.add_child(dialog_title);
+
+    let mut details = DetailsView::new();
+    for (name, lines) in instance_tabs(instance) {
+        details = details.tab(name, lines);
+    }
+
+    dl.add_child(ResizedView::new(
+        SizeConstraint::Full,
+        SizeConstraint::Full,
+        details.with_name("details"),
+    ));
+
+    let raw = instance.clone();
+    let volumes = instance.clone();
+    let user_data = instance.clone();
+    let tags = instance.clone();
+    let dl = event_view(dl)
+        .on_event('r', move |s| instance_document_view(s, &raw))
+        .on_event('v', move |s| instance_volumes(s, &volumes))
+        .on_event('u', move |s| instance_user_data(s, &user_data))
+        .on_event('e', move |s| edit_tags(s, vec![tags.clone()]));
+
+    siv.add_fullscreen_layer(dl);
+
+    load_instance_details(siv, instance);
+}
+
+/// Fills in the tabs of the details screen that need more than the instance.
+fn load_instance_details(s: &mut Cursive, instance: &Instance) {
+    let ud = s.user_data::<ReturnValues>().unwrap();
+    let (region, profile, range) = (ud.region.clone(), ud.profile.clone(), ud.metrics_range);
+
+    let cb_sink = s.cb_sink().clone();
+    let instance = instance.clone();
+
+    thread::spawn(move || {
+        let instance_id = instance.instance_id.clone().unwrap_or_default();
+
+        let update = |name: &'static str, lines: Vec<DetailLine>| {
+            let _ = cb_sink.send(Box::new(move |s: &mut Cursive| {
+                s.call_on_name("details", |v: &mut DetailsView| v.set_tab(name, lines));
+            }));
+        };
+
+        if let Ok(mut statuses) =
+            get_instance_statuses(&region, &profile, Some(vec![instance_id.clone()]))
+        {
+            if let Some(status) = statuses.remove(&instance_id) {
+                update(OVERVIEW, overview_with_status(&instance, &status));
+            }
+        }
+
+        match get_metrics(&region, &profile, std::slice::from_ref(&instance_id), range) {
+            Ok(mut metrics) => {
+                if let Some(metrics) = metrics.remove(&instance_id) {
+                    update(METRICS, metric_lines(&metrics));
+                }
+            }
+            Err(err) => update(METRICS, vec![DetailLine::field("", err.to_string())]),
+        }
+
+        // without access to the volumes the block device mappings remain
+        if let Ok(volumes) = get_instance_volumes(&region, &profile, &instance_id) {
+            update(STORAGE, storage_with_volumes(&instance, &volumes));
+        }
+
+        let group_ids: Vec<String> = instance
+            .security_groups
+            .iter()
+            .flatten()
+            .filter_map(|g| g.group_id.clone())
+            .collect();
+
+        if !group_ids.is_empty() {
+            if let Ok(groups) = get_security_groups(&region, &profile, Some(group_ids)) {
+                update(SECURITY, security_with_rules(&instance, &groups));
+            }
+        }
+    });
+}
+
+fn refresh_volumes(s: &mut Cursive, instance_id: &str) {
+    let ud = s.user_data::<ReturnValues>().unwrap();
+
+    match get_instance_volumes(&ud.region, &ud.profile, instance_id) {
+        Ok(volumes) => {
+            s.call_on_name("volumes", |v: &mut InstancesView<Volume, VolumeColumn>| {
+                v.set_instances(volumes);
+            });
+        }
+        Err(err) => error_dialog(
+            s,
+            "Error",
+            &format!("Could not retrieve the volumes.\n\n{}", err),
+        ),
+    }
+}
+
+fn selected_volume(s: &mut Cursive) -> Option<Volume> {
+    s.call_on_name("volumes", |v: &mut InstancesView<Volume, VolumeColumn>| {
+        v.item().cloned()
+    })
+    .flatten()
+}
+
+/// The EBS volumes of an instance, with actions to snapshot or modify them.
+fn instance_volumes(s: &mut Cursive, instance: &Instance) {
+    let instance_id = instance.instance_id.clone().unwrap_or_default();
+
+    let table = InstancesView::<Volume, VolumeColumn>::new()
+        .column(VolumeColumn::Device)
+        .column(VolumeColumn::VolumeID)
+        .column(VolumeColumn::Name)
+        .column(VolumeColumn::Size)
+        .column(VolumeColumn::Type)
+        .column(VolumeColumn::Iops)
+        .column(VolumeColumn::Encrypted)
+        .column(VolumeColumn::State)
+        .column(VolumeColumn::Attachment)
+        .column(VolumeColumn::DeleteOnTermination);
+
+    let mut dl = LinearLayout::new(Orientation::Vertical);
+
+    dl.add_child(
+        TextView::new(format!(
+            "{} (volumes of {})  [s: snapshot, m: modify, r: refresh]",
+            built_info::PKG_NAME,
+            instance_id
+        ))
+        .h_align(HAlign::Center),
+    );
+
+    dl.add_child(ResizedView::new(
+        SizeConstraint::Full,
+        SizeConstraint::Full,
+        table.with_name("volumes"),
+    ));
+
+    let refresh_id = instance_id.clone();
+
+    s.add_fullscreen_layer(
+        event_view(dl)
+            .on_event('s', |s| {
+                if let Some(volume) = selected_volume(s) {
+                    snapshot_volume(s, &volume);
+                }
+            })
+            .on_event('m', |s| {
+                if let Some(volume) = selected_volume(s) {
+                    modify_volume_dialog(s, &volume);
+                }
+            })
+            .on_event('r', move |s| refresh_volumes(s, &refresh_id)),
+    );
+
+    refresh_volumes(s, &instance_id);
+}
+
+fn snapshot_volume(s: &mut Cursive, volume: &Volume) {
+    let volume_id = volume.volume_id.clone().unwrap_or_default();
+
+    let description = format!(
+        "{} {}",
+        volume_id,
+        volume_tag(volume, "Name").unwrap_or_default()
+    );
+
+    let d = Dialog::around(
+        ListView::new().child(
+            "Description",
+            EditView::new()
+                .content(description.trim())
+                .with_name("snapshot_description")
+                .fixed_width(50),
+        ),
+    )
+    .title(format!("Snapshot {}", volume_id))
+    .button("Create", move |s| {
+        let description = s
+            .call_on_name("snapshot_description", |v: &mut EditView| v.get_content())
+            .unwrap();
+
+        s.pop_layer();
+
+        let ud = s.user_data::<ReturnValues>().unwrap();
+
+        match create_snapshot(
+            &ud.region,
+            &ud.profile,
+            &volume_id,
+            &description,
+            ud.dry_run,
+        ) {
+            Ok(snapshot_id) => {
+                let d = Dialog::around(TextView::new(format!(
+                    "Snapshot {} of {} is being created.",
+                    snapshot_id, volume_id
+                )))
+                .title("Snapshot volume")
+                .button("Ok", |s| {
+                    s.pop_layer();
+                });
+
+                s.add_layer(event_view(d));
+            }
+            Err(err) => error_dialog(
+                s,
+                "Error",
+                &format!("Could not snapshot the volume.\n\n{}", err),
+            ),
+        }
+    })
+    .button("Cancel", |s| {
+        s.pop_layer();
+    });
+
+    s.add_layer(OnEventView::new(d).on_event(Key::Esc, |s| {
+        s.pop_layer();
+    }));
+}
+
+fn modify_volume_dialog(s: &mut Cursive, volume: &Volume) {
+    let volume = volume.clone();
+    let volume_id = volume.volume_id.clone().unwrap_or_default();
+
+    let mut types = SelectView::<String>::new().popup();
+    types.add_all_str(VOLUME_TYPES.iter().cloned());
+    let current = volume.volume_type.clone().unwrap_or_default();
+    match VOLUME_TYPES.iter().position(|t| *t == current) {
+        Some(idx) => {
+            types.set_selection(idx);
+        }
+        // a gp3 volume keeps its type, only its size can be changed
+        None if !current.is_empty() => {
+            types.add_item_str(current.clone());
+            types.set_selection(VOLUME_TYPES.len());
+        }
+        None => {}
+    }
+
+    let d = Dialog::around(
+        ListView::new()
+            .child(
+                "Size (GiB)",
+                EditView::new()
+                    .content(volume.size.map(|s| s.to_string()).unwrap_or_default())
+                    .with_name("volume_size")
+                    .fixed_width(20),
+            )
+            .child("Type", types.with_name("volume_type"))
+            .child(
+                "IOPS (io1/io2)",
+                EditView::new()
+                    .content(volume.iops.map(|i| i.to_string()).unwrap_or_default())
+                    .with_name("volume_iops")
+                    .fixed_width(20),
+            ),
+    )
+    .title(format!("Modify {}", volume_id))
+    .button("Modify", move |s| {
+        let size = s
+            .call_on_name("volume_size", |v: &mut EditView| v.get_content())
+            .unwrap();
+        let volume_type = s
+            .call_on_name("volume_type", |v: &mut SelectView<String>| v.selection())
+            .unwrap()
+            .map(|t| t.to_string())
+            .unwrap_or_default();
+        let iops = s
+            .call_on_name("volume_iops", |v: &mut EditView| v.get_content())
+            .unwrap();
+
+        let size = match size.trim().parse::<i64>() {
+            Ok(size) => size,
+            Err(_) => {
+                error_dialog(s, "Error", "The size has to be a number of GiB.");
+                return;
+            }
+        };
+
+        // volumes can grow, but not shrink
+        if volume.size.is_some_and(|current| size < current) {
+            error_dialog(s, "Error", "Volumes can not be made smaller.");
+            return;
+        }
+
+        let iops = match iops.trim() {
+            "" => None,
+            iops => match iops.parse::<i64>() {
+                Ok(iops) => Some(iops),
+                Err(_) => {
+                    error_dialog(s, "Error", "The iops have to be a number.");
+                    return;
+                }
+            },
+        };
+
+        // only send what changed
+        let size = Some(size).filter(|size| Some(*size) != volume.size);
+        let iops = iops.filter(|iops| {
+            Some(*iops) != volume.iops && (volume_type == "io1" || volume_type == "io2")
+        });
+        let volume_type = Some(volume_type).filter(|t| Some(t) != volume.volume_type.as_ref());
+
+        if size.is_none() && iops.is_none() && volume_type.is_none() {
+            s.pop_layer();
+            return;
+        }
+
+        s.pop_layer();
+
+        let ud = s.user_data::<ReturnValues>().unwrap();
+
+        match modify_volume(
+            &ud.region,
+            &ud.profile,
+            &volume_id,
+            size,
+            volume_type,
+            iops,
+            ud.dry_run,
+        ) {
+            Ok(state) => {
+                let d = Dialog::around(TextView::new(format!(
+                    "The modification of {} is {}.",
+                    volume_id, state
+                )))
+                .title("Modify volume")
+                .button("Ok", |s| {
+                    s.pop_layer();
+                });
+
+                s.add_layer(event_view(d));
+            }
+            Err(err) => error_dialog(
+                s,
+                "Error",
+                &format!("Could not modify the volume.\n\n{}", err),
+            ),
+        }
+    })
+    .button("Cancel", |s| {
+        s.pop_layer();
+    });
+
+    s.add_layer(OnEventView::new(d).on_event(Key::Esc, |s| {
+        s.pop_layer();
+    }));
+}
+
+fn volumes_title(volumes: &[Volume]) -> String {
+    let (count, size) = unattached(volumes);
+
+    format!(
+        "{} (volumes: {}, {} unattached, {} GiB wasted)  [d: delete, a: attach, x: detach, s: snapshot, m: modify, r: refresh]",
+        built_info::PKG_NAME,
+        volumes.len(),
+        count,
+        size
+    )
+}
+
+fn refresh_all_volumes(s: &mut Cursive) {
+    let ud = s.user_data::<ReturnValues>().unwrap();
+
+    match get_volumes(&ud.region, &ud.profile, vec![]) {
+        Ok(volumes) => {
+            s.call_on_name("volumes_title", |v: &mut TextView| {
+                v.set_content(volumes_title(&volumes))
+            });
+
+            s.call_on_name("volumes", |v: &mut InstancesView<Volume, VolumeColumn>| {
+                v.set_instances(volumes);
+            });
+        }
+        Err(err) => error_dialog(
+            s,
+            "Error",
+            &format!("Could not retrieve the volumes.\n\n{}", err),
+        ),
+    }
+}
+
+/// All EBS volumes of the region, with the size of the unattached ones, and
+/// actions to delete, attach, detach, snapshot or modify them.
+fn volumes(s: &mut Cursive) {
+    let table = InstancesView::<Volume, VolumeColumn>::new()
+        .column(VolumeColumn::VolumeID)
+        .column(VolumeColumn::Name)
+        .column(VolumeColumn::Size)
+        .column(VolumeColumn::Type)
+        .column(VolumeColumn::Iops)
+        .column(VolumeColumn::Encrypted)
+        .column(VolumeColumn::State)
+        .column(VolumeColumn::Attachment)
+        .column(VolumeColumn::Device)
+        .column(VolumeColumn::Zone);
+
+    let mut dl = LinearLayout::new(Orientation::Vertical);
+
+    dl.add_child(
+        TextView::new(volumes_title(&[]))
+            .h_align(HAlign::Center)
+            .with_name("volumes_title"),
+    );
+
+    dl.add_child(ResizedView::new(
+        SizeConstraint::Full,
+        SizeConstraint::Full,
+        table.with_name("volumes"),
+    ));
+
+    s.add_fullscreen_layer(
+        event_view(dl)
+            .on_event('d', |s| {
+                if let Some(volume) = selected_volume(s) {
+                    delete_volume_dialog(s, volume);
+                }
+            })
+            .on_event('a', |s| {
+                if let Some(volume) = selected_volume(s) {
+                    attach_volume_dialog(s, volume);
+                }
+            })
+            .on_event('x', |s| {
+                if let Some(volume) = selected_volume(s) {
+                    detach_volume_dialog(s, volume);
+                }
+            })
+            .on_event('s', |s| {
+                if let Some(volume) = selected_volume(s) {
+                    snapshot_volume(s, &volume);
+                }
+            })
+            .on_event('m', |s| {
+                if let Some(volume) = selected_volume(s) {
+                    modify_volume_dialog(s, &volume);
+                }
+            })
+            .on_event('r', refresh_all_volumes),
+    );
+
+    refresh_all_volumes(s);
+}
+
+fn delete_volume_dialog(s: &mut Cursive, volume: Volume) {
+    if volume.state.as_deref() != Some("available") {
+        error_dialog(
+            s,
+            "Error",
+            "Only unattached volumes can be deleted, detach it first.",
+        );
+        return;
+    }
+
+    let volume_id = volume.volume_id.clone().unwrap_or_default();
+
+    let d = Dialog::around(TextView::new(format!(
+        "Delete volume {} ({} GiB)?\n\nIts data is lost, unless it has a snapshot.",
+        volume_id,
+        volume.size.unwrap_or_default()
+    )))
+    .title("Delete volume")
+    .button("Delete", move |s| {
+        let ud = s.user_data::<ReturnValues>().unwrap();
+
+        match delete_volume(&ud.region, &ud.profile, &volume_id, ud.dry_run) {
+            Ok(()) => {
+                s.pop_layer();
+                refresh_all_volumes(s);
+            }
+            Err(err) => error_dialog(
+                s,
+                "Error",
+                &format!("Could not delete the volume.\n\n{}", err),
+            ),
+        }
+    })
+    .button("Cancel", |s| {
+        s.pop_layer();
+    });
+
+    s.add_layer(event_view(d));
+}
+
+/// Attaches an unattached volume to an instance in its availability zone.
+fn attach_volume_dialog(s: &mut Cursive, volume: Volume) {
+    if volume.state.as_deref() != Some("available") {
+        error_dialog(s, "Error", "The volume is attached already.");
+        return;
+    }
+
+    let volume_id = volume.volume_id.clone().unwrap_or_default();
+    let zone = volume.availability_zone.clone().unwrap_or_default();
+
+    let ud = s.user_data::<ReturnValues>().unwrap();
+
+    let mut instance_select = SelectView::<String>::new().popup();
+    for row in ud.instances.iter() {
+        let state = row.state.as_ref().and_then(|state| state.name.as_deref());
+        let instance_zone = row
+            .placement
+            .as_ref()
+            .and_then(|placement| placement.availability_zone.as_deref());
+
+        if instance_zone != Some(zone.as_str())
+            || state == Some("shutting-down")
+            || state == Some("terminated")
+        {
+            continue;
+        }
+
+        let instance_id = row.instance_id.clone().unwrap_or_default();
+        let label = format!(
+            "{}  {}",
+            instance_id,
+            find_tag("Name".to_string(), row.tags.clone()).unwrap_or_default()
+        );
+
+        instance_select.add_item(label.trim(), instance_id);
+    }
+
+    if instance_select.is_empty() {
+        error_dialog(
+            s,
+            "Error",
+            &format!("There are no instances in {} to attach to.", zone),
+        );
+        return;
+    }
+
+    let d = Dialog::around(
+        ListView::new()
+            .child("Instance", instance_select.with_name("attach_instance"))
+            .child(
+                "Device",
+                EditView::new()
+                    .content("/dev/sdf")
+                    .with_name("attach_device")
+                    .fixed_width(20),
+            ),
+    )
+    .title(format!("Attach {}", volume_id))
+    .button("Attach", move |s| {
+        let instance_id = s
+            .call_on_name("attach_instance", |v: &mut SelectView<String>| {
+                v.selection()
+            })
+            .flatten();
+        let device = s
+            .call_on_name("attach_device", |v: &mut EditView| v.get_content())
+            .unwrap()
+            .trim()
+            .to_string();
+
+        let instance_id = match instance_id {
+            Some(instance_id) => instance_id,
+            None => return,
+        };
+
+        if device.is_empty() {
+            error_dialog(s, "Error", "The device name is required.");
+            return;
+        }
+
+        let ud = s.user_data::<ReturnValues>().unwrap();
+
+        match attach_volume(
+            &ud.region,
+            &ud.profile,
+            &volume_id,
+            &instance_id,
+            &device,
+            ud.dry_run,
+        ) {
+            Ok(()) => {
+                s.pop_layer();
+                refresh_all_volumes(s);
+            }
+            Err(err) => error_dialog(
+                s,
+                "Error",
+                &format!("Could not attach the volume.\n\n{}", err),
+            ),
+        }
+    })
+    .button("Cancel", |s| {
+        s.pop_layer();
+    });
+
+    s.add_layer(event_view(d));
+}
+
+fn detach_volume_dialog(s: &mut Cursive, volume: Volume) {
+    if volume.attachments.iter().flatten().next().is_none() {
+        error_dialog(s, "Error", "The volume is not attached.");
+        return;
+    }
+
+    let volume_id = volume.volume_id.clone().unwrap_or_default();
+
+    let d = Dialog::around(
+        LinearLayout::new(Orientation::Vertical)
+            .child(TextView::new(format!(
+                "Detach {} from {}?\n\nUnmount its file systems first.",
+                volume_id,
+                attachment(&volume)
+            )))
+            .child(TextView::new(" "))
+            .child(
+                LinearLayout::new(Orientation::Horizontal)
+                    .child(Checkbox::new().with_name("detach_force"))
+                    .child(TextView::new(
+                        " force, when the instance does not release it (data may be lost)",
+                    )),
+            ),
+    )
+    .title("Detach volume")
+    .button("Detach", move |s| {
+        let force = s
+            .call_on_name("detach_force", |v: &mut Checkbox| v.is_checked())
+            .unwrap_or(false);
+
+        let ud = s.user_data::<ReturnValues>().unwrap();
+
+        match detach_volume(&ud.region, &ud.profile, &volume_id, force, ud.dry_run) {
+            Ok(()) => {
+                s.pop_layer();
+                refresh_all_volumes(s);
+            }
+            Err(err) => error_dialog(
+                s,
+                "Error",
+                &format!("Could not detach the volume.\n\n{}", err),
+            ),
+        }
+    })
+    .button("Cancel", |s| {
+        s.pop_layer();
+    });
+
+    s.add_layer(event_view(d));
+}
+
+fn refresh_snapshots(s: &mut Cursive) {
+    let ud = s.user_data::<ReturnValues>().unwrap();
+
+    // the images tell which snapshots can't be deleted on their own
+    let rows = get_snapshots(&ud.region, &ud.profile).and_then(|snapshots| {
+        get_images(&ud.region, &ud.profile, ImageScope::Owned, "")
+            .map(|images| snapshot_rows(snapshots, &images))
+    });
+
+    match rows {
+        Ok(rows) => {
+            s.call_on_name(
+                "snapshots",
+                |v: &mut InstancesView<SnapshotRow, SnapshotColumn>| {
+                    v.set_instances(rows);
+                },
+            );
+        }
+        Err(err) => error_dialog(
+            s,
+            "Error",
+            &format!("Could not retrieve the snapshots.\n\n{}", err),
+        ),
+    }
+}
+
+fn selected_snapshot(s: &mut Cursive) -> Option<SnapshotRow> {
+    s.call_on_name(
+        "snapshots",
+        |v: &mut InstancesView<SnapshotRow, SnapshotColumn>| v.item().cloned(),
+    )
+    .flatten()
+}
+
+/// The EBS snapshots owned by the account, with their age, source volume and
+/// the image they belong to.
+fn snapshots(s: &mut Cursive) {
+    let table = InstancesView::<SnapshotRow, SnapshotColumn>::new()
+        .column(SnapshotColumn::SnapshotID)
+        .column(SnapshotColumn::Name)
+        .column(SnapshotColumn::Size)
+        .column(SnapshotColumn::Age)
+        .column(SnapshotColumn::Volume)
+        .column(SnapshotColumn::Image)
+        .column(SnapshotColumn::State)
+        .column(SnapshotColumn::Encrypted)
+        .column(SnapshotColumn::Description);
+
+    let mut dl = LinearLayout::new(Orientation::Vertical);
+
+    dl.add_child(
+        TextView::new(format!(
+            "{} (snapshots)  [d: delete, r: refresh]",
+            built_info::PKG_NAME
+        ))
+        .h_align(HAlign::Center),
+    );
+
+    dl.add_child(ResizedView::new(
+        SizeConstraint::Full,
+        SizeConstraint::Full,
+        table.with_name("snapshots"),
+    ));
+
+    s.add_fullscreen_layer(
+        event_view(dl)
+            .on_event('d', |s| {
+                if let Some(row) = selected_snapshot(s) {
+                    delete_snapshot_dialog(s, row);
+                }
+            })
+            .on_event('r', refresh_snapshots),
+    );
+
+    refresh_snapshots(s);
+}
+
+fn delete_snapshot_dialog(s: &mut Cursive, row: SnapshotRow) {
+    if let Some(image_id) = &row.image_id {
+        error_dialog(
+            s,
+            "Error",
+            &format!(
+                "The snapshot belongs to image {}, deregister the image first.",
+                image_id
+            ),
+        );
+        return;
+    }
+
+    let snapshot_id = row.snapshot_id();
+
+    let d = Dialog::around(TextView::new(format!(
+        "Delete snapshot {} of {}?",
+        snapshot_id,
+        row.snapshot.volume_id.clone().unwrap_or_default()
+    )))
+    .title("Delete snapshot")
+    .button("Delete", move |s| {
+        let ud = s.user_data::<ReturnValues>().unwrap();
+
+        match delete_snapshot(&ud.region, &ud.profile, &snapshot_id, ud.dry_run) {
+            Ok(()) => {
+                s.pop_layer();
+                refresh_snapshots(s);
+            }
+            Err(err) => error_dialog(
+                s,
+                "Error",
+                &format!("Could not delete the snapshot.\n\n{}", err),
+            ),
+        }
+    })
+    .button("Cancel", |s| {
+        s.pop_layer();
+    });
+
+    s.add_layer(event_view(d));
+}
+
+/// Shows the complete instance as json or yaml, so nothing returned by the
+/// api is hidden.
+fn instance_document_view(s: &mut Cursive, instance: &Instance) {
+    let lines = match instance_document(instance, false) {
+        Ok(lines) => lines,
+        Err(err) => {
+            error_dialog(
+                s,
+                "Error",
+                &format!("Could not serialize the instance.\n\n{}", err),
+            );
+            return;
+        }
+    };
+
+    let mut dl = LinearLayout::new(Orientation::Vertical);
+
+    dl.add_child(
+        TextView::new(format!(
+            "{} ({} json)  [enter: fold, -/+: fold all, /: search, m: json/yaml, y: copy block, Y: copy all]",
+            built_info::PKG_NAME,
+            instance.instance_id.clone().unwrap_or_default()
+        ))
+        .h_align(HAlign::Center)
+        .with_name("document_title"),
+    );
+
+    dl.add_child(ResizedView::new(
+        SizeConstraint::Full,
+        SizeConstraint::Full,
+        DocumentView::new(lines).with_name("document"),
+    ));
+
+    let yaml = Rc::new(Cell::new(false));
+    let instance = instance.clone();
+
+    let copy = |s: &mut Cursive, text: String| {
+        if let Err(err) = terminal::copy_to_clipboard(&text) {
+            error_dialog(
+                s,
+                "Error",
+                &format!("Could not copy to the clipboard.\n\n{}", err),
+            );
+        }
+    };
+
+    s.add_fullscreen_layer(
+        event_view(dl)
+            .on_event('m', move |s| {
+                yaml.set(!yaml.get());
+
+                if let Ok(lines) = instance_document(&instance, yaml.get()) {
+                    s.call_on_name("document", |v: &mut DocumentView| v.set_lines(lines));
+                }
+
+                let format = if yaml.get() { "yaml" } else { "json" };
+                let id = instance.instance_id.clone().unwrap_or_default();
+                s.call_on_name("document_title", |v: &mut TextView| {
+                    v.set_content(format!(
+                        "{} ({} {})  [enter: fold, -/+: fold all, /: search, m: json/yaml, y: copy block, Y: copy all]",
+                        built_info::PKG_NAME,
+                        id,
+                        format
+                    ))
+                });
+            })
+            .on_event('y', move |s| {
+                let text = s
+                    .call_on_name("document", |v: &mut DocumentView| v.current_block())
+                    .unwrap_or_default();
+                copy(s, text);
+            })
+            .on_event('Y', move |s| {
+                let text = s
+                    .call_on_name("document", |v: &mut DocumentView| v.content())
+                    .unwrap_or_default();
+                copy(s, text);
+            })
+            .on_event('/', |s| {
+                let search = |s: &mut Cursive, pattern: &str| {
+                    s.pop_layer();
+                    let found = s
+                        .call_on_name("document", |v: &mut DocumentView| v.search(pattern))
+                        .unwrap_or(false);
+
+                    if !found {
+                        error_dialog(s, "Search", &format!("\"{}\" not found.", pattern));
+                    }
+                };
+
+                let d = Dialog::around(
+                    EditView::new()
+                        .on_submit(search)
+                        .with_name("document_search")
+                        .fixed_width(40),
+                )
+                .title("Search (n/N: next/previous)")
+                .button("Ok", move |s| {
+                    let pattern = s
+                        .call_on_name("document_search", |v: &mut EditView| v.get_content())
+                        .unwrap();
+                    search(s, &pattern);
+                });
+
+                s.add_layer(OnEventView::new(d).on_event(Key::Esc, |s| {
+                    s.pop_layer();
+                }));
+            }),
+    );
+}
+
+fn user_data_lines(user_data: &str) -> Vec<String> {
+    user_data.lines().map(String::from).collect()
+}
+
+/// The decoded user data of an instance, editable while it is stopped.
+fn instance_user_data(s: &mut Cursive, instance: &Instance) {
+    let instance_id = instance.instance_id.clone().unwrap_or_default();
+
+    let ud = s.user_data::<ReturnValues>().unwrap();
+
+    let data = match get_user_data(&ud.region, &ud.profile, &instance_id) {
+        Ok(data) => data,
+        Err(err) => {
+            error_dialog(
+                s,
+                "Error",
+                &format!("Could not retrieve the user data.\n\n{}", err),
+            );
+            return;
+        }
+    };
+
+    if is_gzip(&data) {
+        error_dialog(
+            s,
+            "User data",
+            "The user data is gzip compressed and can not be shown.",
+        );
+        return;
+    }
+
+    let content = String::from_utf8_lossy(&data).to_string();
+
+    let mut dl = LinearLayout::new(Orientation::Vertical);
+
+    dl.add_child(
+        TextView::new(format!(
+            "{} ({} user data)  [enter: fold, -/+: fold all, e: edit]",
+            built_info::PKG_NAME,
+            instance_id,
+        ))
+        .h_align(HAlign::Center),
+    );
+
+    dl.add_child(ResizedView::new(
+        SizeConstraint::Full,
+        SizeConstraint::Full,
+        DocumentView::new(user_data_lines(&content))
+            .syntax(user_data_syntax(&content))
+            .with_name("user_data"),
+    ));
+
+    s.add_fullscreen_layer(event_view(dl).on_event('e', move |s| {
+        let ud = s.user_data::<ReturnValues>().unwrap();
+
+        // the instance may have been started or stopped since it was listed
+        match get_instance_state(&ud.region, &ud.profile, &instance_id) {
+            Ok(state) if state == "stopped" => edit_user_data(s, &instance_id),
+            Ok(_) => error_dialog(
+                s,
+                "Error",
+                "The user data can only be changed while the instance is stopped.",
+            ),
+            Err(err) => error_dialog(
+                s,
+                "Error",
+                &format!("Could not retrieve the state of the instance.\n\n{}", err),
+            ),
+        }
+    }));
+}
+
+/// Edits the user data in $EDITOR, and shows the changes before saving.
+fn edit_user_data(s: &mut Cursive, instance_id: &str) {
+    let ud = s.user_data::<ReturnValues>().unwrap();
+    let (region, profile, dry_run) = (ud.region.clone(), ud.profile.clone(), ud.dry_run);
+
+    // edit what is set now, not what was shown
+    let original = match get_user_data(&region, &profile, instance_id) {
+        Ok(data) if is_gzip(&data) => {
+            error_dialog(
+                s,
+                "Error",
+                "The user data is gzip compressed and can not be edited.",
+            );
+            return;
+        }
+        // saving would replace what isn't valid utf-8
+        Ok(data) => match String::from_utf8(data) {
+            Ok(data) => data,
+            Err(_) => {
+                error_dialog(
+                    s,
+                    "Error",
+                    "The user data is not text and can not be edited.",
+                );
+                return;
+            }
+        },
+        Err(err) => {
+            error_dialog(
+                s,
+                "Error",
+                &format!("Could not retrieve the user data.\n\n{}", err),
+            );
+            return;
+        }
+    };
+
+    let edited = match terminal::edit(s, "user-data", &original) {
+        Ok(edited) => edited,
+        Err(err) => {
+            error_dialog(
+                s,
+                "Error",
+                &format!("Could not edit the user data.\n\n{}", err),
+            );
+            return;
+        }
+    };
+
+    if edited == original {
+        return;
+    }
+
+    let preview = DocumentView::new(diff(&original, &edited)).syntax(Syntax::Diff);
+
+    let instance_id = instance_id.to_string();
+    let d = Dialog::around(preview)
+        .title(format!("Save the user data of {}?", instance_id))
+        .button("Save", move |s| {
+            match modify_user_data(&region, &profile, &instance_id, edited.as_bytes(), dry_run) {
+                Ok(()) => {
+                    s.pop_layer();
+
+                    s.call_on_name("user_data", |v: &mut DocumentView| {
+                        v.set_lines(user_data_lines(&edited));
+                        v.set_syntax(user_data_syntax(&edited));
+                    });
+                }
+                Err(err) => error_dialog(
+                    s,
+                    "Error",
+                    &format!("Could not save the user data.\n\n{}", err),
+                ),
+            }
+        })
+        .button("Cancel", |s| {
+            s.pop_layer();
+        });
+
+    s.add_layer(event_view(d.full_screen()));
+}
+
+/// Edits the tags the instances have in common, and shows the changes per
+/// instance before applying them.
+fn edit_tags(s: &mut Cursive, instances: Vec<Instance>) {
+    if instances.is_empty() {
+        return;
+    }
+
+    let before = common_tags(&instances);
+
+    let ids: Vec<String> = instances
+        .iter()
+        .map(|i| i.instance_id.clone().unwrap_or_default())
+        .collect();
+
+    let mut dl = LinearLayout::new(Orientation::Vertical);
+    dl.add_child(TextView::new(format!("Instances: {}", ids.join(", "))));
+    dl.add_child(TextView::new(" "));
+
+    if instances.len() > 1 {
+        dl.add_child(TextView::new(
+            "Lists the tags with the same value on all instances, other tags are kept.",
+        ));
+        dl.add_child(TextView::new(" "));
+    }
+
+    dl.add_child(
+        TextArea::new()
+            .content(format_tags(&before))
+            .with_name("tags_editor")
+            .min_height(10),
+    );
+
+    let d = Dialog::around(dl.fixed_width(80))
+        .title("Edit tags (key = value)")
+        .button("Preview", move |s| {
+            let text = s
+                .call_on_name("tags_editor", |v: &mut TextArea| {
+                    v.get_content().to_string()
+                })
+                .unwrap();
+
+            let after = match parse_tags(&text) {
+                Ok(after) => after,
+                Err(err) => {
+                    error_dialog(s, "Error", &err);
+                    return;
+                }
+            };
+
+            let changes = TagChanges::between(&before, &after);
+            if changes.is_empty() {
+                s.pop_layer();
+                return;
+            }
+
+            preview_tags(s, &instances, changes);
+        })
+        .button("Cancel", |s| {
+            s.pop_layer();
+        });
+
+    s.add_layer(OnEventView::new(d).on_event(Key::Esc, |s| {
+        s.pop_layer();
+    }));
+}
+
+fn preview_tags(s: &mut Cursive, instances: &[Instance], changes: TagChanges) {
+    let mut lines = vec![];
+    let mut ids = vec![];
+
+    for instance in instances {
+        let changed = preview_tag_changes(instance, &changes);
+        if changed.is_empty() {
+            continue;
+        }
+
+        let id = instance.instance_id.clone().unwrap_or_default();
+        let name = find_tag("Name".to_string(), instance.tags.clone()).unwrap_or_default();
+
+        if !lines.is_empty() {
+            lines.push("".to_string());
+        }
+        lines.push(format!("{} {}", id, name).trim_end().to_string());
+        lines.extend(changed);
+
+        ids.push(id);
+    }
+
+    if ids.is_empty() {
+        error_dialog(s, "Edit tags", "The tags of the instances are unchanged.");
+        return;
+    }
+
+    let ud = s.user_data::<ReturnValues>().unwrap();
+    let (region, profile, dry_run) = (ud.region.clone(), ud.profile.clone(), ud.dry_run);
+
+    let d = Dialog::around(DocumentView::new(lines).syntax(Syntax::Diff))
+        .title(format!("Change the tags of {} instance(s)?", ids.len()))
+        .button("Apply", move |s| {
+            match change_tags(&region, &profile, ids.clone(), &changes, dry_run) {
+                Ok(()) => {
+                    // the preview and the editor
+                    s.pop_layer();
+                    s.pop_layer();
+
+                    refresh(s);
+
+                    // the details screen shows a single instance
+                    if ids.len() == 1 {
+                        let ud = s.user_data::<ReturnValues>().unwrap();
+                        let lines = ud
+                            .instances
+                            .iter()
+                            .find(|row| row.instance_id.as_ref() == Some(&ids[0]))
+                            .map(|row| tag_lines(&row.instance));
+
+                        if let Some(lines) = lines {
+                            s.call_on_name("details", |v: &mut DetailsView| v.set_tab(TAGS, lines));
+                        }
+                    }
+                }
+                Err(err) => error_dialog(
+                    s,
+                    "Error",
+                    &format!("Could not change the tags.\n\n{}", err),
+                ),
+            }
+        })
+        .button("Cancel", |s| {
+            s.pop_layer();
+        });
+
+    s.add_layer(event_view(d.full_screen()));
+}
+
+const STOP_POLL_INTERVAL: Duration = Duration::from_secs(5);
+const STOP_POLL_ATTEMPTS: usize = 120;
+
+/// Changes the type of an instance, offering to stop it first when it runs.
+fn change_instance_type(s: &mut Cursive, instance: Instance) {
+    let instance_id = instance.instance_id.clone().unwrap_or_default();
+    let state = instance
+        .state
+        .as_ref()
+        .and_then(|state| state.name.clone())
+        .unwrap_or_default();
+
+    match state.as_str() {
+        "stopped" => choose_instance_type(s, instance, false),
+        "running" => {
+            let d = Dialog::around(TextView::new(format!(
+                "{} has to be stopped to change its type. Stop it now, and start it again afterwards?",
+                instance_id
+            )))
+            .title("Change instance type")
+            .button("Stop", move |s| {
+                s.pop_layer();
+                stop_for_type_change(s, instance.clone());
+            })
+            .button("Cancel", |s| {
+                s.pop_layer();
+            });
+
+            s.add_layer(event_view(d));
+        }
+        state => error_dialog(
+            s,
+            "Error",
+            &format!(
+                "The instance is {}, its type can be changed once it is stopped.",
+                state
+            ),
+        ),
+    }
+}
+
+/// Stops the instance, and continues with choosing the type once stopped.
+fn stop_for_type_change(s: &mut Cursive, instance: Instance) {
+    let instance_id = instance.instance_id.clone().unwrap_or_default();
+
+    let ud = s.user_data::<ReturnValues>().unwrap();
+    let (region, profile) = (ud.region.clone(), ud.profile.clone());
+
+    if let Err(err) = stop_instance(&region, &profile, &instance_id, ud.dry_run) {
+        error_dialog(
+            s,
+            "Error",
+            &format!("Could not stop the instance.\n\n{}", err),
+        );
+        return;
+    }
+
+    let cancelled = Arc::new(AtomicBool::new(false));
+
+    let cancel = cancelled.clone();
+    let d = Dialog::around(TextView::new("The instance is stopping.").with_name("stop_progress"))
+        .title(format!("Change type of {}", instance_id))
+        .button("Cancel", move |s| {
+            cancel.store(true, atomic::Ordering::Relaxed);
+            s.pop_layer();
+        });
+
+    s.add_layer(d);
+
+    let cb_sink = s.cb_sink().clone();
+
+    thread::spawn(move || {
+        for _ in 0..STOP_POLL_ATTEMPTS {
+            thread::sleep(STOP_POLL_INTERVAL);
+
+            if cancelled.load(atomic::Ordering::Relaxed) {
+                return;
+            }
+
+            // keep waiting when the state could not be retrieved
+            let state = get_instance_state(&region, &profile, &instance_id).unwrap_or_default();
+            let stopped = state == "stopped";
+
+            let cancelled = cancelled.clone();
+            let instance = instance.clone();
+            let _ = cb_sink.send(Box::new(move |s: &mut Cursive| {
+                if cancelled.load(atomic::Ordering::Relaxed) {
+                    return;
+                }
+
+                if stopped {
+                    s.pop_layer();
+                    choose_instance_type(s, instance, true);
+                } else if !state.is_empty() {
+                    s.call_on_name("stop_progress", |v: &mut TextView| {
+                        v.set_content(format!("The instance is {}.", state))
+                    });
+                }
+            }));
+
+            if stopped {
+                return;
+            }
+        }
+
+        let _ = cb_sink.send(Box::new(move |s: &mut Cursive| {
+            if cancelled.load(atomic::Ordering::Relaxed) {
+                return;
+            }
+
+            s.pop_layer();
+            error_dialog(s, "Error", "The instance did not stop in time.");
+        }));
+    });
+}
+
+/// Lists the instance types matching the architecture and virtualization
+/// type of the instance.
+fn choose_instance_type(s: &mut Cursive, instance: Instance, restart: bool) {
+    let ud = s.user_data::<ReturnValues>().unwrap();
+
+    let architecture = instance
+        .architecture
+        .clone()
+        .unwrap_or_else(|| "x86_64".to_string());
+    let virtualization = instance
+        .virtualization_type
+        .clone()
+        .unwrap_or_else(|| "hvm".to_string());
+
+    let types = match get_instance_types(&ud.region, &ud.profile, &architecture, &virtualization) {
+        Ok(types) => types,
+        Err(err) => {
+            error_dialog(
+                s,
+                "Error",
+                &format!("Could not retrieve the instance types.\n\n{}", err),
+            );
+            return;
+        }
+    };
+
+    let instance_id = instance.instance_id.clone().unwrap_or_default();
+    let current = instance.instance_type.clone().unwrap_or_default();
+
+    let mut table = InstancesView::<InstanceTypeInfo, InstanceTypeColumn>::scrollable(&types)
+        .column(InstanceTypeColumn::Type)
+        .column(InstanceTypeColumn::VCpus)
+        .column(InstanceTypeColumn::Memory)
+        .column(InstanceTypeColumn::Architecture)
+        .column(InstanceTypeColumn::Network)
+        .column(InstanceTypeColumn::Burstable)
+        .column(InstanceTypeColumn::CurrentGeneration);
+
+    if let Some(idx) = types
+        .iter()
+        .position(|t| t.instance_type.as_ref() == Some(&current))
+    {
+        table.set_selected_item(idx);
+    }
+
+    table.set_on_submit(move |s: &mut Cursive, _| {
+        let selected = s
+            .call_on_name(
+                "instance_types",
+                |v: &mut InstancesView<InstanceTypeInfo, InstanceTypeColumn>| v.item().cloned(),
+            )
+            .flatten()
+            .and_then(|t| t.instance_type);
+
+        if let Some(instance_type) = selected {
+            confirm_instance_type(s, &instance, instance_type, restart);
+        }
+    });
+
+    let mut dl = LinearLayout::new(Orientation::Vertical);
+
+    dl.add_child(
+        TextView::new(format!(
+            "{} ({} {}, {} {})  [enter: change type, </>: sort]",
+            built_info::PKG_NAME,
+            instance_id,
+            current,
+            architecture,
+            virtualization
+        ))
+        .h_align(HAlign::Center),
+    );
+
+    dl.add_child(ResizedView::new(
+        SizeConstraint::Full,
+        SizeConstraint::Full,
+        table.with_name("instance_types"),
+    ));
+
+    s.add_fullscreen_layer(event_view(dl));
+}
+
+fn confirm_instance_type(
+    s: &mut Cursive,
+    instance: &Instance,
+    instance_type: String,
+    restart: bool,
+) {
+    let instance_id = instance.instance_id.clone().unwrap_or_default();
+    let current = instance.instance_type.clone().unwrap_or_default();
+
+    if instance_type == current {
+        return;
+    }
+
+    let d = Dialog::around(
+        LinearLayout::new(Orientation::Vertical)
+            .child(TextView::new(format!(
+                "Change the type of {} from {} to {}?",
+                instance_id, current, instance_type
+            )))
+            .child(TextView::new(" "))
+            .child(
+                LinearLayout::new(Orientation::Horizontal)
+                    .child(
+                        Checkbox::new()
+                            .with_checked(restart)
+                            .with_name("type_change_start"),
+                    )
+                    .child(TextView::new(" start the instance afterwards")),
+            ),
+    )
+    .title("Change instance type")
+    .button("Change", move |s| {
+        let start = s
+            .call_on_name("type_change_start", |v: &mut Checkbox| v.is_checked())
+            .unwrap_or(false);
+
+        let ud = s.user_data::<ReturnValues>().unwrap();
+        let (region, profile, dry_run) = (ud.region.clone(), ud.profile.clone(), ud.dry_run);
+
+        if let Err(err) =
+            modify_instance_type(&region, &profile, &instance_id, &instance_type, dry_run)
+        {
+            error_dialog(
+                s,
+                "Error",
+                &format!("Could not change the instance type.\n\n{}", err),
+            );
+            return;
+        }
+
+        let mut message = format!("The type of {} is now {}.", instance_id, instance_type);
+
+        if start {
+            match start_instance(&region, &profile, &instance_id, dry_run) {
+                Ok(()) => message.push_str(" The instance will start."),
+                Err(err) => {
+                    message = format!("{}\n\nCould not start the instance.\n\n{}", message, err)
+                }
+            }
+        }
+
+        // the confirmation and the instance types
+        s.pop_layer();
+        s.pop_layer();
+
+        refresh(s);
+
+        let d = Dialog::around(TextView::new(message))
+            .title("Change instance type")
+            .button("Ok", |s| {
+                s.pop_layer();
+            });
+
+        s.add_layer(event_view(d));
+    })
+    .button("Cancel", |s| {
+        s.pop_layer();
+    });
+
+    s.add_layer(event_view(d));
+}
+
+fn notify(s: &mut Cursive, key: &str, level: NotificationLevel, message: &str) {
+    s.call_on_name("notifications", |v: &mut NotificationView| {
+        v.notify(key, level, message)
+    });
+}
+
+fn remove_notification(s: &mut Cursive, key: &str) {
+    s.call_on_name("notifications", |v: &mut NotificationView| v.remove(key));
+}
+
+const IMAGE_POLL_INTERVAL: Duration = Duration::from_secs(15);
+// images of large volumes can take hours
+const IMAGE_POLL_ATTEMPTS: usize = 960;
+const NOTIFICATION_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Returns the instance name, or its id, with a timestamp, leaving out the
+/// characters not allowed in image names.
+fn default_image_name(instance: &Instance) -> String {
+    let name = find_tag("Name".to_string(), instance.tags.clone())
+        .filter(|name| !name.is_empty())
+        .or_else(|| instance.instance_id.clone())
+        .unwrap_or_default();
+
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || "()[] ./-'@_".contains(c) {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+
+    format!("{}-{}", name.trim(), Utc::now().format("%Y%m%d-%H%M"))
+}
+
+fn create_image_dialog(s: &mut Cursive, instance: Instance) {
+    let instance_id = instance.instance_id.clone().unwrap_or_default();
+
+    let d = Dialog::around(
+        ListView::new()
+            .child(
+                "Name",
+                EditView::new()
+                    .content(default_image_name(&instance))
+                    .with_name("image_name")
+                    .fixed_width(50),
+            )
+            .child(
+                "Description",
+                EditView::new()
+                    .with_name("image_description")
+                    .fixed_width(50),
+            )
+            .child("No reboot", Checkbox::new().with_name("image_no_reboot"))
+            .child(
+                "Copy tags",
+                Checkbox::new().checked().with_name("image_copy_tags"),
+            ),
+    )
+    .title(format!("Create image of {}", instance_id))
+    .button("Create", move |s| {
+        let name = s
+            .call_on_name("image_name", |v: &mut EditView| v.get_content())
+            .unwrap()
+            .trim()
+            .to_string();
+        let description = s
+            .call_on_name("image_description", |v: &mut EditView| v.get_content())
+            .unwrap();
+        let no_reboot = s
+            .call_on_name("image_no_reboot", |v: &mut Checkbox| v.is_checked())
+            .unwrap_or(false);
+        let copy_tags = s
+            .call_on_name("image_copy_tags", |v: &mut Checkbox| v.is_checked())
+            .unwrap_or(false);
+
+        if name.len() < 3 || name.len() > 128 {
+            error_dialog(s, "Error", "The name has to be 3 to 128 characters long.");
+            return;
+        }
+
+        let ud = s.user_data::<ReturnValues>().unwrap();
+        let (region, profile, dry_run) = (ud.region.clone(), ud.profile.clone(), ud.dry_run);
+
+        let image_id = match create_image(
+            &region,
+            &profile,
+            &instance_id,
+            &name,
+            &description,
+            no_reboot,
+            dry_run,
+        ) {
+            Ok(image_id) => image_id,
+            Err(err) => {
+                error_dialog(
+                    s,
+                    "Error",
+                    &format!("Could not create the image.\n\n{}", err),
+                );
+                return;
+            }
+        };
+
+        s.pop_layer();
+
+        let tags = if copy_tags {
+            copyable_tags(&instance.tags)
+        } else {
+            vec![]
+        };
+
+        // the image can be tagged right away, its snapshots once available
+        if !tags.is_empty() {
+            let changes = TagChanges {
+                set: tags.clone(),
+                delete: vec![],
+            };
+
+            if let Err(err) =
+                change_tags(&region, &profile, vec![image_id.clone()], &changes, dry_run)
+            {
+                error_dialog(
+                    s,
+                    "Error",
+                    &format!(
+                        "The image {} is being created, but could not be tagged.\n\n{}",
+                        image_id, err
+                    ),
+                );
+            }
+        }
+
+        track_image(s, region, image_id, name, tags);
+    })
+    .button("Cancel", |s| {
+        s.pop_layer();
+    });
+
+    s.add_layer(event_view(d));
+}
+
+/// Follows an image until it is available, showing its state in the
+/// notification area. Once available its snapshots get the tags of the image.
+fn track_image(
+    s: &mut Cursive,
+    region: Region,
+    image_id: String,
+    name: String,
+    tags: Vec<(String, String)>,
+) {
+    let ud = s.user_data::<ReturnValues>().unwrap();
+    let (profile, dry_run) = (ud.profile.clone(), ud.dry_run);
+
+    let label = if region == ud.region {
+        format!("image {} ({})", image_id, name)
+    } else {
+        format!("image {} ({}) in {}", image_id, name, region.name())
+    };
+
+    notify(
+        s,
+        &image_id,
+        NotificationLevel::Info,
+        &format!("{} is pending", label),
+    );
+
+    let cb_sink = s.cb_sink().clone();
+
+    thread::spawn(move || {
+        let started = Instant::now();
+
+        let mut outcome = (
+            NotificationLevel::Error,
+            format!("{} is still not available, giving up", label),
+        );
+
+        for _ in 0..IMAGE_POLL_ATTEMPTS {
+            thread::sleep(IMAGE_POLL_INTERVAL);
+
+            // a new image is not always described right away
+            let image = match get_image(&region, &profile, &image_id) {
+                Ok(Some(image)) => image,
+                _ => continue,
+            };
+
+            match image.state.clone().unwrap_or_default().as_str() {
+                "pending" => {
+                    let message = format!(
+                        "{} is pending, {} min",
+                        label,
+                        started.elapsed().as_secs() / 60
+                    );
+                    let key = image_id.clone();
+
+                    if cb_sink
+                        .send(Box::new(move |s: &mut Cursive| {
+                            notify(s, &key, NotificationLevel::Info, &message)
+                        }))
+                        .is_err()
+                    {
+                        return;
+                    }
+                }
+                "available" => {
+                    let snapshot_ids = image_snapshot_ids(&image);
+                    let changes = TagChanges {
+                        set: tags.clone(),
+                        delete: vec![],
+                    };
+
+                    outcome = if tags.is_empty() || snapshot_ids.is_empty() {
+                        (
+                            NotificationLevel::Success,
+                            format!("{} is available", label),
+                        )
+                    } else {
+                        match change_tags(&region, &profile, snapshot_ids, &changes, dry_run) {
+                            Ok(()) => (
+                                NotificationLevel::Success,
+                                format!("{} is available, its snapshots are tagged", label),
+                            ),
+                            Err(err) => (
+                                NotificationLevel::Error,
+                                format!(
+                                    "{} is available, could not tag its snapshots: {}",
+                                    label, err
+                                ),
+                            ),
+                        }
+                    };
+                    break;
+                }
+                state => {
+                    let reason = image
+                        .state_reason
+                        .and_then(|reason| reason.message)
+                        .unwrap_or_default();
+
+                    outcome = (
+                        NotificationLevel::Error,
+                        format!("{} is {} {}", label, state, reason)
+                            .trim()
+                            .to_string(),
+                    );
+                    break;
+                }
+            }
+        }
+
+        let key = image_id.clone();
+        let (level, message) = outcome;
+        let _ = cb_sink.send(Box::new(move |s: &mut Cursive| {
+            notify(s, &key, level, &message)
+        }));
+
+        thread::sleep(NOTIFICATION_TIMEOUT);
+
+        let _ = cb_sink.send(Box::new(move |s: &mut Cursive| {
+            remove_notification(s, &image_id)
+        }));
+    });
+}
+
+/// Snapshots all volumes of an instance at once. Each snapshot gets the tags
+/// of the instance and its id, so the set can be found back together.
+fn snapshot_instance_dialog(s: &mut Cursive, instance: Instance) {
+    let instance_id = instance.instance_id.clone().unwrap_or_default();
+
+    let description = format!(
+        "{} {}",
+        find_tag("Name".to_string(), instance.tags.clone()).unwrap_or_else(|| instance_id.clone()),
+        Utc::now().format("%Y-%m-%d %H:%M")
+    );
+
+    let d = Dialog::around(
+        ListView::new()
+            .child(
+                "Description",
+                EditView::new()
+                    .content(description)
+                    .with_name("snapshots_description")
+                    .fixed_width(50),
+            )
+            .child(
+                "Exclude boot volume",
+                Checkbox::new().with_name("snapshots_exclude_boot"),
+            ),
+    )
+    .title(format!("Snapshot all volumes of {}", instance_id))
+    .button("Create", move |s| {
+        let description = s
+            .call_on_name("snapshots_description", |v: &mut EditView| v.get_content())
+            .unwrap();
+        let exclude_boot = s
+            .call_on_name("snapshots_exclude_boot", |v: &mut Checkbox| v.is_checked())
+            .unwrap_or(false);
+
+        let mut tags = copyable_tags(&instance.tags);
+        tags.retain(|(key, _)| key != "InstanceId");
+        tags.push(("InstanceId".to_string(), instance_id.clone()));
+
+        let ud = s.user_data::<ReturnValues>().unwrap();
+
+        match create_instance_snapshots(
+            &ud.region,
+            &ud.profile,
+            &instance_id,
+            &description,
+            exclude_boot,
+            &tags,
+            ud.dry_run,
+        ) {
+            Ok(snapshots) => {
+                s.pop_layer();
+
+                let lines: Vec<String> = snapshots
+                    .iter()
+                    .map(|snapshot| {
+                        format!(
+                            "{} of {}",
+                            snapshot.snapshot_id.clone().unwrap_or_default(),
+                            snapshot.volume_id.clone().unwrap_or_default()
+                        )
+                    })
+                    .collect();
+
+                let d = Dialog::around(TextView::new(format!(
+                    "{} snapshots are being created.\n\n{}",
+                    snapshots.len(),
+                    lines.join("\n")
+                )))
+                .title("Snapshot volumes")
+                .button("Ok", |s| {
+                    s.pop_layer();
+                });
+
+                s.add_layer(event_view(d));
+            }
+            Err(err) => error_dialog(
+                s,
+                "Error",
+                &format!("Could not snapshot the volumes.\n\n{}", err),
+            ),
+        }
+    })
+    .button("Cancel", |s| {
+        s.pop_layer();
+    });
+
+    s.add_layer(event_view(d));
+}
+
+fn images_title(scope: ImageScope, pattern: &str) -> String {
+    let what = if !pattern.is_empty() {
+        format!("{} matching {}", scope.name(), pattern)
+    } else if scope == ImageScope::Owned {
+        scope.name().to_string()
+    } else {
+        format!("{}, press f to search by name", scope.name())
+    };
+
+    format!(
+        "{} (images: {})  [o: owned/public/marketplace, f: name, d: deregister, c: copy to region, s: share, r: refresh]",
+        built_info::PKG_NAME,
+        what
+    )
+}
+
+fn refresh_images(s: &mut Cursive) {
+    let ud = s.user_data::<ReturnValues>().unwrap();
+    let (scope, pattern) = (ud.image_scope, ud.image_pattern.clone());
+
+    // there are far too many public and marketplace images to list them all
+    let rows = if scope != ImageScope::Owned && pattern.is_empty() {
+        Ok(vec![])
+    } else {
+        let instances: Vec<Instance> = ud
+            .instances
+            .iter()
+            .map(|row| row.instance.clone())
+            .collect();
+
+        get_images(&ud.region, &ud.profile, scope, &pattern)
+            .map(|images| image_rows(images, &instances))
+    };
+
+    s.call_on_name("images_title", |v: &mut TextView| {
+        v.set_content(images_title(scope, &pattern))
+    });
+
+    match rows {
+        Ok(rows) => {
+            s.call_on_name("images", |v: &mut InstancesView<ImageRow, ImageColumn>| {
+                v.set_instances(rows);
+            });
+
+            load_launch_permissions(s);
+        }
+        Err(err) => error_dialog(
+            s,
+            "Error",
+            &format!("Could not retrieve the images.\n\n{}", err),
+        ),
+    }
+}
+
+fn selected_image(s: &mut Cursive) -> Option<ImageRow> {
+    s.call_on_name("images", |v: &mut InstancesView<ImageRow, ImageColumn>| {
+        v.item().cloned()
+    })
+    .flatten()
+}
+
+/// Looks up the accounts the owned private images are shared with, one image
+/// at a time in the background as it takes a request per image.
+fn load_launch_permissions(s: &mut Cursive) {
+    let ud = s.user_data::<ReturnValues>().unwrap();
+    if ud.image_scope != ImageScope::Owned {
+        return;
+    }
+
+    let (region, profile) = (ud.region.clone(), ud.profile.clone());
+
+    let image_ids: Vec<String> = s
+        .call_on_name("images", |v: &mut InstancesView<ImageRow, ImageColumn>| {
+            v.items()
+                .iter()
+                .filter(|row| !row.is_public() && row.shared_with.is_none())
+                .map(|row| row.image_id())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    if image_ids.is_empty() {
+        return;
+    }
+
+    let cb_sink = s.cb_sink().clone();
+
+    thread::spawn(move || {
+        for image_id in image_ids {
+            // the sharing of an image that can't be described stays unknown
+            let accounts = match get_launch_permissions(&region, &profile, &image_id) {
+                Ok(accounts) => accounts,
+                Err(_) => continue,
+            };
+
+            if cb_sink
+                .send(Box::new(move |s: &mut Cursive| {
+                    s.call_on_name("images", |v: &mut InstancesView<ImageRow, ImageColumn>| {
+                        v.update_items(|row| {
+                            if row.image_id() == image_id {
+                                row.shared_with = Some(accounts.clone());
+                            }
+                        });
+                    });
+                }))
+                .is_err()
+            {
+                return;
+            }
+        }
+    });
+}
+
+/// The images owned by the account, or the public and marketplace images
+/// matching a name, with the running instances using them.
+fn images(s: &mut Cursive) {
+    let table = InstancesView::<ImageRow, ImageColumn>::new()
+        .column(ImageColumn::ImageID)
+        .column(ImageColumn::Name)
+        .column(ImageColumn::Created)
+        .column(ImageColumn::Architecture)
+        .column(ImageColumn::RootDevice)
+        .column(ImageColumn::Visibility)
+        .column(ImageColumn::State)
+        .column(ImageColumn::UsedBy);
+
+    let ud = s.user_data::<ReturnValues>().unwrap();
+
+    let mut dl = LinearLayout::new(Orientation::Vertical);
+
+    dl.add_child(
+        TextView::new(images_title(ud.image_scope, &ud.image_pattern))
+            .h_align(HAlign::Center)
+            .with_name("images_title"),
+    );
+
+    dl.add_child(ResizedView::new(
+        SizeConstraint::Full,
+        SizeConstraint::Full,
+        table.with_name("images"),
+    ));
+
+    s.add_fullscreen_layer(
+        event_view(dl)
+            .on_event('o', |s| {
+                s.with_user_data(|v: &mut ReturnValues| {
+                    v.image_scope = v.image_scope.next();
+                });
+
+                refresh_images(s);
+            })
+            .on_event('f', image_pattern_dialog)
+            .on_event('d', |s| {
+                if let Some(row) = selected_image(s) {
+                    deregister_image_dialog(s, row);
+                }
+            })
+            .on_event('c', |s| {
+                if let Some(row) = selected_image(s) {
+                    copy_image_dialog(s, row);
+                }
+            })
+            .on_event('s', |s| {
+                if let Some(row) = selected_image(s) {
+                    share_image_dialog(s, row);
+                }
+            })
+            .on_event('r', refresh_images),
+    );
+
+    refresh_images(s);
+}
+
+fn image_pattern_dialog(s: &mut Cursive) {
+    fn ok(s: &mut Cursive) {
+        let pattern = s
+            .call_on_name("image_pattern", |v: &mut EditView| v.get_content())
+            .unwrap()
+            .trim()
+            .to_string();
+
+        s.with_user_data(|v: &mut ReturnValues| {
+            v.image_pattern = pattern;
+        });
+
+        s.pop_layer();
+
+        refresh_images(s);
+    }
+
+    let ud = s.user_data::<ReturnValues>().unwrap();
+
+    let d = Dialog::around(
+        LinearLayout::new(Orientation::Vertical)
+            .child(
+                EditView::new()
+                    .content(ud.image_pattern.clone())
+                    .on_submit(|s, _| ok(s))
+                    .with_name("image_pattern")
+                    .fixed_width(50),
+            )
+            .child(TextView::new(" "))
+            .child(TextView::new(
+                "* and ? are wildcards, without them any name\ncontaining the text matches.",
+            )),
+    )
+    .title("Filter images by name")
+    .button("Ok", ok)
+    .button("Cancel", |s| {
+        s.pop_layer();
+    });
+
+    s.add_layer(event_view(d));
+}
+
+fn deregister_image_dialog(s: &mut Cursive, row: ImageRow) {
+    let image_id = row.image_id();
+    let snapshot_ids = image_snapshot_ids(&row.image);
+
+    let mut layout = LinearLayout::new(Orientation::Vertical).child(TextView::new(format!(
+        "Deregister image {} ({})?",
+        image_id,
+        row.name()
+    )));
+
+    if !row.used_by.is_empty() {
+        layout.add_child(TextView::new(" "));
+        layout.add_child(TextView::new(format!(
+            "It is used by {}.\nThese keep running, but no more instances can be launched from it.",
+            row.used_by.join(", ")
+        )));
+    }
+
+    if !snapshot_ids.is_empty() {
+        layout.add_child(TextView::new(" "));
+        layout.add_child(
+            LinearLayout::new(Orientation::Horizontal)
+                .child(
+                    Checkbox::new()
+                        .checked()
+                        .with_name("image_delete_snapshots"),
+                )
+                .child(TextView::new(format!(
+                    " delete its snapshots too: {}",
+                    snapshot_ids.join(", ")
+                ))),
+        );
+    }
+
+    let d = Dialog::around(layout)
+        .title("Deregister image")
+        .button("Deregister", move |s| {
+            let delete_snapshots = s
+                .call_on_name("image_delete_snapshots", |v: &mut Checkbox| v.is_checked())
+                .unwrap_or(false);
+
+            let ud = s.user_data::<ReturnValues>().unwrap();
+            let (region, profile, dry_run) = (ud.region.clone(), ud.profile.clone(), ud.dry_run);
+
+            if let Err(err) = deregister_image(&region, &profile, &image_id, dry_run) {
+                error_dialog(
+                    s,
+                    "Error",
+                    &format!("Could not deregister the image.\n\n{}", err),
+                );
+                return;
+            }
+
+            // the snapshots are only released once the image is deregistered
+            let errors: Vec<String> = if delete_snapshots {
+                snapshot_ids
+                    .iter()
+                    .filter_map(|snapshot_id| {
+                        delete_snapshot(&region, &profile, snapshot_id, dry_run)
+                            .err()
+                            .map(|err| format!("{}: {}", snapshot_id, err))
+                    })
+                    .collect()
+            } else {
+                vec![]
+            };
+
+            s.pop_layer();
+
+            refresh_images(s);
+
+            if !errors.is_empty() {
+                error_dialog(
+                    s,
+                    "Error",
+                    &format!(
+                        "The image is deregistered, but not all of its snapshots could be deleted.\n\n{}",
+                        errors.join("\n")
+                    ),
+                );
+            }
+        })
+        .button("Cancel", |s| {
+            s.pop_layer();
+        });
+
+    s.add_layer(event_view(d));
+}
+
+/// Copies an image to another region, with its tags, following the copy in
+/// the notification area until it is available.
+fn copy_image_dialog(s: &mut Cursive, row: ImageRow) {
+    let ud = s.user_data::<ReturnValues>().unwrap();
+
+    let mut region_select = SelectView::<String>::new().popup();
+    region_select.add_all_str(
+        regions()
+            .iter()
+            .filter(|region| **region != ud.region)
+            .map(|region| region.name()),
+    );
+
+    let image_id = row.image_id();
+
+    let d = Dialog::around(
+        ListView::new()
+            .child("Region", region_select.with_name("copy_region"))
+            .child(
+                "Name",
+                EditView::new()
+                    .content(row.name())
+                    .with_name("copy_name")
+                    .fixed_width(50),
+            )
+            .child(
+                "Description",
+                EditView::new()
+                    .content(row.image.description.clone().unwrap_or_default())
+                    .with_name("copy_description")
+                    .fixed_width(50),
+            )
+            .child(
+                "Copy tags",
+                Checkbox::new().checked().with_name("copy_tags"),
+            ),
+    )
+    .title(format!("Copy image {}", image_id))
+    .button("Copy", move |s| {
+        let destination = s
+            .call_on_name("copy_region", |v: &mut SelectView<String>| v.selection())
+            .flatten()
+            .and_then(|name| Region::from_str(&name).ok());
+        let name = s
+            .call_on_name("copy_name", |v: &mut EditView| v.get_content())
+            .unwrap()
+            .trim()
+            .to_string();
+        let description = s
+            .call_on_name("copy_description", |v: &mut EditView| v.get_content())
+            .unwrap();
+        let copy_tags = s
+            .call_on_name("copy_tags", |v: &mut Checkbox| v.is_checked())
+            .unwrap_or(false);
+
+        let destination = match destination {
+            Some(destination) => destination,
+            None => return,
+        };
+
+        if name.len() < 3 || name.len() > 128 {
+            error_dialog(s, "Error", "The name has to be 3 to 128 characters long.");
+            return;
+        }
+
+        let ud = s.user_data::<ReturnValues>().unwrap();
+        let (region, profile, dry_run) = (ud.region.clone(), ud.profile.clone(), ud.dry_run);
+
+        let copy_id = match copy_image(
+            &destination,
+            &profile,
+            &region,
+            &image_id,
+            &name,
+            &description,
+            dry_run,
+        ) {
+            Ok(copy_id) => copy_id,
+            Err(err) => {
+                error_dialog(s, "Error", &format!("Could not copy the image.\n\n{}", err));
+                return;
+            }
+        };
+
+        s.pop_layer();
+
+        let tags = if copy_tags {
+            copyable_tags(&row.image.tags)
+        } else {
+            vec![]
+        };
+
+        if !tags.is_empty() {
+            let changes = TagChanges {
+                set: tags.clone(),
+                delete: vec![],
+            };
+
+            if let Err(err) = change_tags(
+                &destination,
+                &profile,
+                vec![copy_id.clone()],
+                &changes,
+                dry_run,
+            ) {
+                error_dialog(
+                    s,
+                    "Error",
+                    &format!(
+                        "The image {} is being copied, but could not be tagged.\n\n{}",
+                        copy_id, err
+                    ),
+                );
+            }
+        }
+
+        track_image(s, destination, copy_id, name, tags);
+    })
+    .button("Cancel", |s| {
+        s.pop_layer();
+    });
+
+    s.add_layer(event_view(d));
+}
+
+/// Edits the accounts an image is shared with.
+fn share_image_dialog(s: &mut Cursive, row: ImageRow) {
+    let image_id = row.image_id();
+
+    let ud = s.user_data::<ReturnValues>().unwrap();
+
+    let current = match get_launch_permissions(&ud.region, &ud.profile, &image_id) {
+        Ok(accounts) => accounts,
+        Err(err) => {
+            error_dialog(
+                s,
+                "Error",
+                &format!("Could not retrieve the sharing of the image.\n\n{}", err),
+            );
+            return;
+        }
+    };
+
+    let mut layout = LinearLayout::new(Orientation::Vertical);
+
+    if row.is_public() {
+        layout.add_child(TextView::new(
+            "The image is public, it can be launched by any account.",
+        ));
+        layout.add_child(TextView::new(" "));
+    }
+
+    layout.add_child(TextView::new(
+        "The accounts that can launch the image, separated by commas or new lines.",
+    ));
+    layout.add_child(
+        TextArea::new()
+            .content(current.join("\n"))
+            .with_name("image_accounts")
+            .fixed_size((50, 8)),
+    );
+
+    let d = Dialog::around(layout)
+        .title(format!("Share image {}", image_id))
+        .button("Save", move |s| {
+            let text = s
+                .call_on_name("image_accounts", |v: &mut TextArea| {
+                    v.get_content().to_string()
+                })
+                .unwrap();
+
+            let accounts = match parse_accounts(&text) {
+                Ok(accounts) => accounts,
+                Err(err) => {
+                    error_dialog(s, "Error", &err);
+                    return;
+                }
+            };
+
+            let add: Vec<String> = accounts
+                .iter()
+                .filter(|account| !current.contains(account))
+                .cloned()
+                .collect();
+            let remove: Vec<String> = current
+                .iter()
+                .filter(|account| !accounts.contains(account))
+                .cloned()
+                .collect();
+
+            if add.is_empty() && remove.is_empty() {
+                s.pop_layer();
+                return;
+            }
+
+            let ud = s.user_data::<ReturnValues>().unwrap();
+
+            match modify_launch_permissions(
+                &ud.region,
+                &ud.profile,
+                &image_id,
+                &add,
+                &remove,
+                ud.dry_run,
+            ) {
+                Ok(()) => {
+                    s.pop_layer();
+
+                    s.call_on_name("images", |v: &mut InstancesView<ImageRow, ImageColumn>| {
+                        v.update_items(|row| {
+                            if row.image_id() == image_id {
+                                row.shared_with = Some(accounts.clone());
+                            }
+                        });
+                    });
+                }
+                Err(err) => error_dialog(
+                    s,
+                    "Error",
+                    &format!("Could not change the sharing of the image.\n\n{}", err),
+                ),
+            }
+        })
+        .button("Cancel", |s| {
+            s.pop_layer();
+        });
+
+    s.add_layer(event_view(d));
+}
+
+const LAUNCH_HIGHLIGHT: Duration = Duration::from_secs(10 * 60);
+const LAUNCH_REFRESH_DELAY: Duration = Duration::from_secs(15);
+
+/// Launches instances from an image or a launch template, starting with the
+/// choice between them. `spec` holds what is decided already.
+fn launch_wizard(s: &mut Cursive, spec: LaunchSpec) {
+    let ud = s.user_data::<ReturnValues>().unwrap();
+
+    // without permission to describe launch templates only images remain
+    let templates = get_launch_templates(&ud.region, &ud.profile).unwrap_or_default();
+
+    let mut select = SelectView::<Option<LaunchTemplate>>::new().item("image (AMI)", None);
+
+    for template in templates {
+        let label = format!(
+            "template {} ({})",
+            template.launch_template_name.clone().unwrap_or_default(),
+            template.launch_template_id.clone().unwrap_or_default()
         );
-        y += 1;
+        select.add_item(label, Some(template));
+    }
 
-        let security_groups = instance.security_groups.clone();
+    let title = launch_title(&spec);
 
-        for sg in security_groups.unwrap().iter() {
-            printer.print(
-                (0, y),
-                &format!(
-                    "{:>20}: {:}",
-                    &sg.group_id.clone().unwrap(),
-                    &sg.group_name.clone().unwrap(),
-                ),
+    select.set_on_submit(move |s, template: &Option<LaunchTemplate>| {
+        s.pop_layer();
+
+        match template {
+            Some(template) => launch_template_version(s, template.clone(), spec.clone()),
+            None => launch_details(s, spec.clone()),
+        }
+    });
+
+    s.add_layer(event_view(
+        Dialog::around(select.scrollable())
+            .title(title)
+            .button("Cancel", |s| {
+                s.pop_layer();
+            }),
+    ));
+}
+
+fn launch_title(spec: &LaunchSpec) -> &'static str {
+    if spec.spot_price.is_some() {
+        "Request spot instances"
+    } else {
+        "Launch instances"
+    }
+}
+
+fn launch_template_version(s: &mut Cursive, template: LaunchTemplate, spec: LaunchSpec) {
+    let template_id = template.launch_template_id.clone().unwrap_or_default();
+    let name = template.launch_template_name.clone().unwrap_or_default();
+
+    let ud = s.user_data::<ReturnValues>().unwrap();
+
+    let versions = match get_launch_template_versions(&ud.region, &ud.profile, &template_id) {
+        Ok(versions) => versions,
+        Err(err) => {
+            error_dialog(
+                s,
+                "Error",
+                &format!("Could not retrieve the template versions.\n\n{}", err),
             );
+            return;
+        }
+    };
+
+    let mut select = SelectView::<String>::new()
+        .item(
+            format!(
+                "$Default (version {})",
+                template.default_version_number.unwrap_or_default()
+            ),
+            "$Default".to_string(),
+        )
+        .item(
+            format!(
+                "$Latest (version {})",
+                template.latest_version_number.unwrap_or_default()
+            ),
+            "$Latest".to_string(),
+        );
+
+    for version in versions.iter() {
+        select.add_item(
+            version_label(version),
+            version.version_number.unwrap_or_default().to_string(),
+        );
+    }
+
+    let title = format!("Launch from {}", name);
+
+    select.set_on_submit(move |s, version: &String| {
+        s.pop_layer();
+
+        let spec = LaunchSpec {
+            template: Some(TemplateSource {
+                id: template_id.clone(),
+                name: name.clone(),
+                version: version.clone(),
+            }),
+            ..spec.clone()
+        };
+
+        launch_details(s, spec);
+    });
+
+    s.add_layer(event_view(
+        Dialog::around(select.scrollable())
+            .title(title)
+            .button("Cancel", |s| {
+                s.pop_layer();
+            }),
+    ));
+}
+
+/// The settings of the instances to launch, prefilled from `spec`.
+fn launch_details(s: &mut Cursive, spec: LaunchSpec) {
+    let ud = s.user_data::<ReturnValues>().unwrap();
+    let (region, profile) = (ud.region.clone(), ud.profile.clone());
+
+    // what cannot be described is left to the defaults or the template
+    let subnets = get_subnets(&region, &profile).unwrap_or_default();
+    let key_pairs = get_key_pairs(&region, &profile).unwrap_or_default();
+    let groups = get_security_groups(&region, &profile, None).unwrap_or_default();
+
+    let unset = if spec.template.is_some() {
+        "from template"
+    } else {
+        "default"
+    };
+
+    let mut subnet_select = SelectView::<String>::new()
+        .popup()
+        .item(unset, "".to_string());
+    for subnet in subnets.iter() {
+        subnet_select.add_item(
+            subnet_label(subnet),
+            subnet.subnet_id.clone().unwrap_or_default(),
+        );
+    }
+    if !spec.subnet_id.is_empty()
+        && !subnets
+            .iter()
+            .any(|subnet| subnet.subnet_id.as_ref() == Some(&spec.subnet_id))
+    {
+        subnet_select.add_item(spec.subnet_id.clone(), spec.subnet_id.clone());
+    }
+    let idx = subnet_select
+        .iter()
+        .position(|(_, id)| *id == spec.subnet_id)
+        .unwrap_or(0);
+    subnet_select.set_selection(idx);
+
+    let mut key_select = SelectView::<String>::new()
+        .popup()
+        .item(unset, "".to_string());
+    for key_name in key_pairs.iter() {
+        key_select.add_item(key_name.clone(), key_name.clone());
+    }
+    if !spec.key_name.is_empty() && !key_pairs.contains(&spec.key_name) {
+        key_select.add_item(spec.key_name.clone(), spec.key_name.clone());
+    }
+    let idx = key_select
+        .iter()
+        .position(|(_, name)| *name == spec.key_name)
+        .unwrap_or(0);
+    key_select.set_selection(idx);
+
+    let mut group_ids: Vec<String> = vec![];
+    let mut group_list = ListView::new();
+    for group in groups.iter() {
+        let group_id = group.group_id.clone().unwrap_or_default();
+        let label = format!(
+            "{} {} ({})",
+            group_id,
+            group.group_name.clone().unwrap_or_default(),
+            group.vpc_id.clone().unwrap_or_default()
+        );
 
-            y += 1;
+        group_list.add_child(
+            &label,
+            Checkbox::new()
+                .with_checked(spec.security_group_ids.contains(&group_id))
+                .with_name(format!("launch_sg_{}", group_id)),
+        );
+        group_ids.push(group_id);
+    }
+    for group_id in spec.security_group_ids.iter() {
+        if !group_ids.contains(group_id) {
+            group_list.add_child(
+                group_id,
+                Checkbox::new()
+                    .checked()
+                    .with_name(format!("launch_sg_{}", group_id)),
+            );
+            group_ids.push(group_id.clone());
         }
+    }
+
+    let mut form = ListView::new()
+        .child(
+            "Image",
+            EditView::new()
+                .content(spec.image_id.clone())
+                .with_name("launch_image")
+                .fixed_width(50),
+        )
+        .child(
+            "Instance type",
+            EditView::new()
+                .content(spec.instance_type.clone())
+                .with_name("launch_type")
+                .fixed_width(50),
+        )
+        .child("Subnet", subnet_select.with_name("launch_subnet"))
+        .child("Key pair", key_select.with_name("launch_key"))
+        .child(
+            "Count",
+            EditView::new()
+                .content(spec.count.to_string())
+                .with_name("launch_count")
+                .fixed_width(6),
+        );
 
-        y += 1;
+    if let Some(price) = &spec.spot_price {
+        form.add_child(
+            "Max price",
+            EditView::new()
+                .content(price.clone())
+                .with_name("launch_max_price")
+                .fixed_width(12),
+        );
+    }
 
-        printer.with_color(
-            ColorStyle::new(PaletteColor::TitleSecondary, PaletteColor::Background),
-            |p| p.print((0, y), "network interfaces"),
+    let layout = LinearLayout::new(Orientation::Vertical)
+        .child(form)
+        .child(TextView::new(" "))
+        .child(TextView::new(format!(
+            "Security groups ({} when none)",
+            unset
+        )))
+        .child(group_list.scrollable().max_height(8))
+        .child(TextView::new(" "))
+        .child(TextView::new("Tags (key = value)"))
+        .child(
+            TextArea::new()
+                .content(format_tags(&spec.tags))
+                .with_name("launch_tags")
+                .min_height(4),
         );
-        y += 1;
-
-        let network_interfaces = instance.network_interfaces.clone();
-        for sg in network_interfaces.unwrap().iter() {
-            printer.print(
-                (0, y),
-                &format!(
-                    "{:>20}: {:}",
-                    &sg.network_interface_id.clone().unwrap(),
-                    &sg.description.clone().unwrap(),
+
+    let template = spec.template.clone();
+    let spot = spec.spot_price.is_some();
+
+    let d = Dialog::around(layout)
+        .title(launch_title(&spec))
+        .button("Next", move |s| {
+            let text = |s: &mut Cursive, name: &str| {
+                s.call_on_name(name, |v: &mut EditView| v.get_content())
+                    .map(|content| content.trim().to_string())
+                    .unwrap_or_default()
+            };
+            let selection = |s: &mut Cursive, name: &str| {
+                s.call_on_name(name, |v: &mut SelectView<String>| v.selection())
+                    .flatten()
+                    .map(|value| (*value).clone())
+                    .unwrap_or_default()
+            };
+
+            let tags = s
+                .call_on_name("launch_tags", |v: &mut TextArea| {
+                    parse_tags(v.get_content())
+                })
+                .unwrap_or_else(|| Ok(vec![]));
+            let tags = match tags {
+                Ok(tags) => tags,
+                Err(err) => {
+                    error_dialog(s, "Error", &err);
+                    return;
+                }
+            };
+
+            let count = match text(s, "launch_count").parse::<i64>() {
+                Ok(count) => count,
+                Err(_) => {
+                    error_dialog(s, "Error", "The count has to be a number.");
+                    return;
+                }
+            };
+
+            let mut security_group_ids = vec![];
+            for group_id in group_ids.iter() {
+                if s.call_on_name(&format!("launch_sg_{}", group_id), |v: &mut Checkbox| {
+                    v.is_checked()
+                })
+                .unwrap_or(false)
+                {
+                    security_group_ids.push(group_id.clone());
+                }
+            }
+
+            let spec = LaunchSpec {
+                template: template.clone(),
+                image_id: text(s, "launch_image"),
+                instance_type: text(s, "launch_type"),
+                subnet_id: selection(s, "launch_subnet"),
+                security_group_ids,
+                key_name: selection(s, "launch_key"),
+                tags,
+                count,
+                spot_price: if spot {
+                    Some(text(s, "launch_max_price"))
+                } else {
+                    None
+                },
+            };
+
+            if let Err(err) = spec.validate() {
+                error_dialog(s, "Error", &err);
+                return;
+            }
+
+            launch_review(s, spec);
+        })
+        .button("Cancel", |s| {
+            s.pop_layer();
+        });
+
+    s.add_layer(event_view(d));
+}
+
+fn launch_review(s: &mut Cursive, spec: LaunchSpec) {
+    let (title, button) = if spec.spot_price.is_some() {
+        ("Request spot instances", "Request")
+    } else {
+        ("Launch instances", "Launch")
+    };
+
+    let d = Dialog::around(TextView::new(spec.summary().join("\n")))
+        .title(title)
+        .button(button, move |s| {
+            let ud = s.user_data::<ReturnValues>().unwrap();
+
+            match run_instances(&ud.region, &ud.profile, &spec, ud.dry_run) {
+                Ok(instance_ids) => {
+                    // the review and the settings
+                    s.pop_layer();
+                    s.pop_layer();
+
+                    show_launched(s, instance_ids);
+
+                    if s.find_name::<InstancesView<SpotRequest, SpotColumn>>("spot_requests")
+                        .is_some()
+                    {
+                        refresh_spot_requests(s);
+                    }
+                }
+                Err(err) => error_dialog(
+                    s,
+                    "Error",
+                    &format!("Could not launch the instances.\n\n{}", err),
                 ),
-            );
+            }
+        })
+        .button("Back", |s| {
+            s.pop_layer();
+        });
 
-            y += 1;
+    s.add_layer(event_view(d));
+}
+
+/// Highlights the launched instances in the table for a while, selecting the
+/// first one.
+fn show_launched(s: &mut Cursive, instance_ids: Vec<String>) {
+    let now = Instant::now();
+    s.with_user_data(|v: &mut ReturnValues| {
+        for instance_id in instance_ids.iter() {
+            v.launched.insert(instance_id.clone(), now);
+        }
+    });
+
+    refresh(s);
+
+    if let Some(first) = instance_ids.first() {
+        let mut table = s
+            .find_name::<InstancesView<InstanceRow, BasicColumn>>("instances")
+            .unwrap();
+
+        if let Some(idx) = table
+            .items()
+            .iter()
+            .position(|row| row.instance_id.as_ref() == Some(first))
+        {
+            table.set_selected_item(idx);
+        }
+    }
+
+    let key = format!("launch {}", instance_ids.join(" "));
+
+    notify(
+        s,
+        &key,
+        NotificationLevel::Success,
+        &format!("launched {}", instance_ids.join(", ")),
+    );
+
+    let cb_sink = s.cb_sink().clone();
+
+    thread::spawn(move || {
+        // the addresses are known once the instances run
+        thread::sleep(LAUNCH_REFRESH_DELAY);
+
+        if cb_sink.send(Box::new(refresh)).is_err() {
+            return;
         }
+
+        thread::sleep(NOTIFICATION_TIMEOUT);
+
+        let _ = cb_sink.send(Box::new(move |s: &mut Cursive| {
+            remove_notification(s, &key)
+        }));
     });
+}
+
+fn refresh_spot_requests(s: &mut Cursive) {
+    let ud = s.user_data::<ReturnValues>().unwrap();
+
+    match get_spot_requests(&ud.region, &ud.profile) {
+        Ok(requests) => {
+            s.call_on_name(
+                "spot_requests",
+                |v: &mut InstancesView<SpotRequest, SpotColumn>| {
+                    v.set_instances(requests);
+                },
+            );
+        }
+        Err(err) => error_dialog(
+            s,
+            "Error",
+            &format!("Could not retrieve the spot requests.\n\n{}", err),
+        ),
+    }
+}
+
+fn selected_spot_request(s: &mut Cursive) -> Option<SpotRequest> {
+    s.call_on_name(
+        "spot_requests",
+        |v: &mut InstancesView<SpotRequest, SpotColumn>| v.item().cloned(),
+    )
+    .flatten()
+}
+
+/// The spot instance requests and spot fleets, with actions to request spot
+/// instances, cancel requests and show the price history.
+fn spot_requests(s: &mut Cursive) {
+    let table = InstancesView::<SpotRequest, SpotColumn>::new()
+        .column(SpotColumn::RequestID)
+        .column(SpotColumn::Kind)
+        .column(SpotColumn::State)
+        .column(SpotColumn::Status)
+        .column(SpotColumn::InstanceType)
+        .column(SpotColumn::MaxPrice)
+        .column(SpotColumn::Instances)
+        .column(SpotColumn::Zone)
+        .column(SpotColumn::Created)
+        // the table handles enter itself
+        .on_submit(|s, _| {
+            if let Some(request) = selected_spot_request(s) {
+                spot_request_status(s, &request);
+            }
+        });
+
+    let mut dl = LinearLayout::new(Orientation::Vertical);
+
+    dl.add_child(
+        TextView::new(format!(
+            "{} (spot requests)  [enter: status, n: request, c: cancel, h: price history, r: refresh]",
+            built_info::PKG_NAME
+        ))
+        .h_align(HAlign::Center),
+    );
 
     dl.add_child(ResizedView::new(
         SizeConstraint::Full,
         SizeConstraint::Full,
-        canvas,
+        table.with_name("spot_requests"),
     ));
 
-    let dl = event_view(dl);
+    s.add_fullscreen_layer(
+        event_view(dl)
+            .on_event('n', |s| {
+                let spec = LaunchSpec {
+                    spot_price: Some("".to_string()),
+                    ..LaunchSpec::new()
+                };
 
-    siv.add_fullscreen_layer(dl);
+                launch_wizard(s, spec);
+            })
+            .on_event('c', |s| {
+                if let Some(request) = selected_spot_request(s) {
+                    cancel_spot_request_dialog(s, request);
+                }
+            })
+            .on_event('h', |s| {
+                if let Some(request) = selected_spot_request(s) {
+                    spot_price_history(s, &request);
+                }
+            })
+            .on_event('r', refresh_spot_requests),
+    );
+
+    refresh_spot_requests(s);
+}
+
+fn spot_request_status(s: &mut Cursive, request: &SpotRequest) {
+    let mut lines = vec![
+        format!("state    {}", request.state),
+        format!("status   {}", request.status),
+    ];
+
+    if !request.message.is_empty() {
+        lines.push("".to_string());
+        lines.push(request.message.clone());
+    }
+
+    let d = Dialog::around(TextView::new(lines.join("\n")))
+        .title(request.id.clone())
+        .button("Ok", |s| {
+            s.pop_layer();
+        });
+
+    s.add_layer(event_view(d));
+}
+
+fn cancel_spot_request_dialog(s: &mut Cursive, request: SpotRequest) {
+    if !request.is_active() {
+        error_dialog(
+            s,
+            "Error",
+            &format!("The request is {} already.", request.state),
+        );
+        return;
+    }
+
+    let what = match request.kind {
+        SpotKind::Request => "spot instance request",
+        SpotKind::Fleet => "spot fleet request",
+    };
+
+    let d = Dialog::around(
+        LinearLayout::new(Orientation::Vertical)
+            .child(TextView::new(format!("Cancel {} {}?", what, request.id)))
+            .child(TextView::new(" "))
+            .child(
+                LinearLayout::new(Orientation::Horizontal)
+                    .child(Checkbox::new().with_name("spot_terminate"))
+                    .child(TextView::new(" terminate its instances too")),
+            ),
+    )
+    .title("Cancel spot request")
+    .button("Cancel request", move |s| {
+        let terminate = s
+            .call_on_name("spot_terminate", |v: &mut Checkbox| v.is_checked())
+            .unwrap_or(false);
+
+        let ud = s.user_data::<ReturnValues>().unwrap();
+
+        match cancel_spot_request(&ud.region, &ud.profile, &request, terminate, ud.dry_run) {
+            Ok(()) => {
+                s.pop_layer();
+                refresh_spot_requests(s);
+            }
+            Err(err) => error_dialog(
+                s,
+                "Error",
+                &format!("Could not cancel the request.\n\n{}", err),
+            ),
+        }
+    })
+    .button("Close", |s| {
+        s.pop_layer();
+    });
+
+    s.add_layer(event_view(d));
+}
+
+/// Shows the spot prices per availability zone of the instance type of the
+/// request, the first one of a fleet with several types.
+fn spot_price_history(s: &mut Cursive, request: &SpotRequest) {
+    let instance_type = match request.instance_types.first() {
+        Some(instance_type) => instance_type.clone(),
+        None => {
+            error_dialog(s, "Error", "The instance type of the request is unknown.");
+            return;
+        }
+    };
+
+    let product = if request.product.is_empty() {
+        "Linux/UNIX".to_string()
+    } else {
+        request.product.clone()
+    };
+
+    let ud = s.user_data::<ReturnValues>().unwrap();
+
+    let prices = match get_spot_price_history(&ud.region, &ud.profile, &instance_type, &product) {
+        Ok(prices) => prices,
+        Err(err) => {
+            error_dialog(
+                s,
+                "Error",
+                &format!("Could not retrieve the spot prices.\n\n{}", err),
+            );
+            return;
+        }
+    };
+
+    let mut dl = LinearLayout::new(Orientation::Vertical);
+
+    dl.add_child(
+        TextView::new(format!(
+            "{} (spot prices of {})",
+            built_info::PKG_NAME,
+            instance_type
+        ))
+        .h_align(HAlign::Center),
+    );
+
+    dl.add_child(ResizedView::new(
+        SizeConstraint::Full,
+        SizeConstraint::Full,
+        DetailsView::new().tab("prices", price_lines(&instance_type, &product, &prices)),
+    ));
+
+    s.add_fullscreen_layer(event_view(dl));
 }
 
 fn help(siv: &mut Cursive) {
@@ -961,11 +5595,13 @@ fn action(s: &mut Cursive) {
         .autojump()
         .item("start", Actions::Start)
         .item("stop", Actions::Stop)
-        .item("reboot", Actions::Reboot);
+        .item("reboot", Actions::Reboot)
+        .item("modify type", Actions::ModifyType)
+        .item("launch more like this", Actions::LaunchMore);
 
     fn ok(s: &mut Cursive, action: &Actions) {
         let table = &s
-            .find_name::<InstancesView<Instance, BasicColumn>>("instances")
+            .find_name::<InstancesView<InstanceRow, BasicColumn>>("instances")
             .unwrap();
 
         let instance = table.item();
@@ -983,18 +5619,20 @@ fn action(s: &mut Cursive) {
 
         let client = client.unwrap();
         match action {
+            Actions::ModifyType => {
+                let instance = instance.unwrap().instance.clone();
+                s.pop_layer();
+                change_instance_type(s, instance);
+            }
+            Actions::LaunchMore => {
+                let spec = LaunchSpec::like(instance.unwrap());
+                s.pop_layer();
+                launch_details(s, spec);
+            }
             Actions::Start => {
-                let req = StartInstancesRequest {
-                    dry_run: Some(ud.dry_run),
-                    instance_ids: [instance.unwrap().instance_id.clone().unwrap()].to_vec(),
-                    ..Default::default()
-                };
+                let instance_id = instance.unwrap().instance_id.clone().unwrap();
 
-                let ft = client.start_instances(req);
-
-                let mut runtime = tokio::runtime::Runtime::new().unwrap();
-
-                match runtime.block_on(ft) {
+                match start_instance(&ud.region, &ud.profile, &instance_id, ud.dry_run) {
                     Ok(_) => {
                         match s.cb_sink().send(Box::new(|s| {
                             let d = Dialog::around(TextView::new("The instance will start."))
@@ -1021,17 +5659,9 @@ fn action(s: &mut Cursive) {
                 };
             }
             Actions::Stop => {
-                let req = StopInstancesRequest {
-                    dry_run: Some(ud.dry_run),
-                    instance_ids: [instance.unwrap().instance_id.clone().unwrap()].to_vec(),
-                    ..Default::default()
-                };
-
-                let ft = client.stop_instances(req);
+                let instance_id = instance.unwrap().instance_id.clone().unwrap();
 
-                let mut runtime = tokio::runtime::Runtime::new().unwrap();
-
-                match runtime.block_on(ft) {
+                match stop_instance(&ud.region, &ud.profile, &instance_id, ud.dry_run) {
                     Ok(_) => {
                         match s.cb_sink().send(Box::new(|s| {
                             let d = Dialog::around(TextView::new("The instance will be stopped."))
@@ -1099,7 +5729,7 @@ fn action(s: &mut Cursive) {
     select.set_on_submit(ok);
 
     let table = &s
-        .find_name::<InstancesView<Instance, BasicColumn>>("instances")
+        .find_name::<InstancesView<InstanceRow, BasicColumn>>("instances")
         .unwrap();
 
     let instance = table.item();
@@ -1124,8 +5754,8 @@ fn action(s: &mut Cursive) {
     ));
 }
 
-fn change_region(s: &mut Cursive) {
-    let regions = vec![
+fn regions() -> Vec<Region> {
+    vec![
         ApEast1,
         AfSouth1,
         ApNortheast1,
@@ -1149,7 +5779,11 @@ fn change_region(s: &mut Cursive) {
         UsGovWest1,
         CnNorth1,
         CnNorthwest1,
-    ];
+    ]
+}
+
+fn change_region(s: &mut Cursive) {
+    let regions = regions();
 
     let mut select = SelectView::<String>::new()
         // Center the text horizontally
@@ -1181,7 +5815,7 @@ fn change_region(s: &mut Cursive) {
         match get_instances_with_region(&ud.profile, &region) {
             Ok(instances) => {
                 let mut iv = s
-                    .find_name::<InstancesView<Instance, BasicColumn>>("instances")
+                    .find_name::<InstancesView<InstanceRow, BasicColumn>>("instances")
                     .unwrap();
                 iv.set_instances(instances.clone());
 
@@ -0,0 +1,118 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+/// Where interactive sessions (SSM, SSH) are started.
+#[derive(Deserialize, Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Terminal {
+    /// Use the multiplexer cloudman runs in, or the foreground otherwise.
+    #[default]
+    Auto,
+    /// Suspend cloudman and run the session on the same terminal.
+    Foreground,
+    Tmux,
+    Screen,
+    Zellij,
+    Kitty,
+    Wezterm,
+}
+
+/// Options for `aws ssm start-session`.
+#[derive(Deserialize, Clone, Default, Debug)]
+#[serde(default)]
+pub struct SsmConfig {
+    /// Session document, e.g. `AWS-StartInteractiveCommand`.
+    pub document: Option<String>,
+    /// Command started after connecting, e.g. `bash -l`.
+    pub shell: Option<String>,
+}
+
+/// Which address of the instance ssh connects to.
+#[derive(Deserialize, Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Address {
+    /// The public ip, or the private ip when there is none or when connecting
+    /// through a bastion.
+    #[default]
+    Auto,
+    Public,
+    Private,
+    /// The public dns name, or the private one when there is none.
+    Dns,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct SshConfig {
+    pub address: Address,
+    /// Login user when neither the tag nor the AMI determine one.
+    pub user: Option<String>,
+    /// Instance tag holding the login user.
+    pub user_tag: String,
+    /// Directory searched for `<key name>.pem` and `<key name>`.
+    pub key_dir: String,
+    /// Private key per key pair name.
+    pub keys: HashMap<String, String>,
+    /// Login user per AMI id or part of the AMI name.
+    pub users: HashMap<String, String>,
+    /// Jump host (`user@host[:port]`) per vpc id.
+    pub bastions: HashMap<String, String>,
+    /// Push an ephemeral key with EC2 Instance Connect when no key file for
+    /// the key pair of the instance is found.
+    pub instance_connect: bool,
+}
+
+impl Default for SshConfig {
+    fn default() -> Self {
+        SshConfig {
+            address: Address::default(),
+            user: None,
+            user_tag: "ssh-user".to_string(),
+            key_dir: "~/.ssh".to_string(),
+            keys: HashMap::new(),
+            users: HashMap::new(),
+            bastions: HashMap::new(),
+            instance_connect: true,
+        }
+    }
+}
+
+/// Settings read from ~/.config/cloudman/config.toml, all of them optional.
+///
+/// ```toml
+/// terminal = "tmux"
+///
+/// [ssm]
+/// shell = "bash -l"
+///
+/// [ssh.bastions]
+/// "vpc-0123456789abcdef0" = "ec2-user@bastion.example.com"
+/// ```
+#[derive(Deserialize, Clone, Default, Debug)]
+#[serde(default)]
+pub struct Config {
+    pub terminal: Terminal,
+    pub ssm: SsmConfig,
+    pub ssh: SshConfig,
+}
+
+impl Config {
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("cloudman").join("config.toml"))
+    }
+
+    /// Loads the configuration, falling back to the defaults when there is
+    /// no configuration file.
+    pub fn load() -> Result<Self, Box<dyn Error>> {
+        match Config::path() {
+            Some(path) if path.exists() => {
+                let content = fs::read_to_string(&path)?;
+                Ok(toml::from_str(&content)?)
+            }
+            _ => Ok(Config::default()),
+        }
+    }
+}
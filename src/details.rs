@@ -0,0 +1,299 @@
+use rusoto_ec2::{Instance, SecurityGroup, Volume};
+use std::error::Error;
+
+use crate::instance_status::{status_lines, Status};
+use crate::security_groups::security_lines;
+use crate::views::DetailLine;
+use crate::volumes::volume_lines;
+
+pub const OVERVIEW: &str = "Overview";
+pub const NETWORK: &str = "Network";
+pub const STORAGE: &str = "Storage";
+pub const SECURITY: &str = "Security";
+pub const TAGS: &str = "Tags";
+pub const METADATA: &str = "Metadata";
+pub const METRICS: &str = "Metrics";
+
+fn text(value: &Option<String>) -> String {
+    value.clone().unwrap_or_default()
+}
+
+fn number(value: Option<i64>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+fn flag(value: Option<bool>) -> String {
+    match value {
+        Some(true) => "yes".to_string(),
+        Some(false) => "no".to_string(),
+        None => "".to_string(),
+    }
+}
+
+/// Returns the details of an instance per tab, missing fields are blank.
+pub fn instance_tabs(instance: &Instance) -> Vec<(&'static str, Vec<DetailLine>)> {
+    vec![
+        (OVERVIEW, overview(instance)),
+        (NETWORK, network(instance)),
+        (STORAGE, storage(instance)),
+        (SECURITY, security(instance)),
+        (TAGS, tags(instance)),
+        (METADATA, metadata(instance)),
+        (METRICS, vec![DetailLine::field("", "loading")]),
+    ]
+}
+
+pub fn overview(instance: &Instance) -> Vec<DetailLine> {
+    let state = instance.state.clone().unwrap_or_default();
+    let state_reason = instance.state_reason.clone().unwrap_or_default();
+    let placement = instance.placement.clone().unwrap_or_default();
+    let cpu = instance.cpu_options.clone().unwrap_or_default();
+
+    let name = instance
+        .tags
+        .iter()
+        .flatten()
+        .find(|t| {
+            t.key
+                .as_deref()
+                .is_some_and(|k| k.eq_ignore_ascii_case("name"))
+        })
+        .and_then(|t| t.value.clone());
+
+    vec![
+        DetailLine::field("instance-id", text(&instance.instance_id)),
+        DetailLine::field("name", text(&name)),
+        DetailLine::field("state", text(&state.name)),
+        DetailLine::field("state reason", text(&state_reason.message)),
+        DetailLine::field("state transition", text(&instance.state_transition_reason)),
+        DetailLine::field("launch time", text(&instance.launch_time)),
+        DetailLine::Blank,
+        DetailLine::field("instance type", text(&instance.instance_type)),
+        DetailLine::field("architecture", text(&instance.architecture)),
+        DetailLine::field("platform", text(&instance.platform)),
+        DetailLine::field("cpu cores", number(cpu.core_count)),
+        DetailLine::field("threads per core", number(cpu.threads_per_core)),
+        DetailLine::field("lifecycle", text(&instance.instance_lifecycle)),
+        DetailLine::field("spot request", text(&instance.spot_instance_request_id)),
+        DetailLine::Blank,
+        DetailLine::field("image-id", text(&instance.image_id)),
+        DetailLine::field("kernel-id", text(&instance.kernel_id)),
+        DetailLine::field("ramdisk-id", text(&instance.ramdisk_id)),
+        DetailLine::field("key name", text(&instance.key_name)),
+        DetailLine::Blank,
+        DetailLine::field("availability zone", text(&placement.availability_zone)),
+        DetailLine::field("placement group", text(&placement.group_name)),
+        DetailLine::field("tenancy", text(&placement.tenancy)),
+        DetailLine::field("host-id", text(&placement.host_id)),
+        DetailLine::field(
+            "monitoring",
+            text(&instance.monitoring.clone().and_then(|m| m.state)),
+        ),
+    ]
+}
+
+/// The overview tab followed by the status checks and scheduled events.
+pub fn overview_with_status(instance: &Instance, status: &Status) -> Vec<DetailLine> {
+    let mut lines = overview(instance);
+
+    lines.push(DetailLine::Blank);
+    lines.extend(status_lines(status));
+
+    lines
+}
+
+pub fn network(instance: &Instance) -> Vec<DetailLine> {
+    let mut lines = vec![
+        DetailLine::field("vpc-id", text(&instance.vpc_id)),
+        DetailLine::field("subnet-id", text(&instance.subnet_id)),
+        DetailLine::field("public ip", text(&instance.public_ip_address)),
+        DetailLine::field("public dns", text(&instance.public_dns_name)),
+        DetailLine::field("private ip", text(&instance.private_ip_address)),
+        DetailLine::field("private dns", text(&instance.private_dns_name)),
+        DetailLine::field("source/dest check", flag(instance.source_dest_check)),
+        DetailLine::field("ena support", flag(instance.ena_support)),
+        DetailLine::field("ebs optimized", flag(instance.ebs_optimized)),
+    ];
+
+    for interface in instance.network_interfaces.iter().flatten() {
+        lines.push(DetailLine::Blank);
+        lines.push(DetailLine::section(&format!(
+            "network interface {}",
+            text(&interface.network_interface_id)
+        )));
+        lines.push(DetailLine::field(
+            "description",
+            text(&interface.description),
+        ));
+        lines.push(DetailLine::field("status", text(&interface.status)));
+        lines.push(DetailLine::field("subnet-id", text(&interface.subnet_id)));
+        lines.push(DetailLine::field(
+            "mac address",
+            text(&interface.mac_address),
+        ));
+
+        for address in interface.private_ip_addresses.iter().flatten() {
+            let public = address
+                .association
+                .as_ref()
+                .and_then(|a| a.public_ip.clone())
+                .map(|ip| format!(" ({})", ip))
+                .unwrap_or_default();
+
+            lines.push(DetailLine::field(
+                if address.primary == Some(true) {
+                    "primary ip"
+                } else {
+                    "secondary ip"
+                },
+                format!("{}{}", text(&address.private_ip_address), public),
+            ));
+        }
+
+        for address in interface.ipv_6_addresses.iter().flatten() {
+            lines.push(DetailLine::field("ipv6", text(&address.ipv_6_address)));
+        }
+    }
+
+    lines
+}
+
+pub fn storage(instance: &Instance) -> Vec<DetailLine> {
+    let mut lines = vec![
+        DetailLine::field("root device", text(&instance.root_device_name)),
+        DetailLine::field("root device type", text(&instance.root_device_type)),
+    ];
+
+    for mapping in instance.block_device_mappings.iter().flatten() {
+        let ebs = mapping.ebs.clone().unwrap_or_default();
+
+        lines.push(DetailLine::Blank);
+        lines.push(DetailLine::section(&text(&mapping.device_name)));
+        lines.push(DetailLine::field("volume-id", text(&ebs.volume_id)));
+        lines.push(DetailLine::field("status", text(&ebs.status)));
+        lines.push(DetailLine::field("attach time", text(&ebs.attach_time)));
+        lines.push(DetailLine::field(
+            "delete on termination",
+            flag(ebs.delete_on_termination),
+        ));
+    }
+
+    lines
+}
+
+/// The storage tab with the block device mappings replaced by the details
+/// of the attached volumes.
+pub fn storage_with_volumes(instance: &Instance, volumes: &[Volume]) -> Vec<DetailLine> {
+    let mut lines = vec![
+        DetailLine::field("root device", text(&instance.root_device_name)),
+        DetailLine::field("root device type", text(&instance.root_device_type)),
+    ];
+
+    lines.extend(volume_lines(volumes));
+
+    lines
+}
+
+pub fn security(instance: &Instance) -> Vec<DetailLine> {
+    let mut lines = vec![DetailLine::field(
+        "iam instance profile",
+        text(&instance.iam_instance_profile.clone().and_then(|p| p.arn)),
+    )];
+
+    lines.push(DetailLine::Blank);
+    lines.push(DetailLine::section("security groups"));
+
+    for group in instance.security_groups.iter().flatten() {
+        lines.push(DetailLine::field(
+            &text(&group.group_id),
+            text(&group.group_name),
+        ));
+    }
+
+    lines
+}
+
+/// The security tab with the effective rules of the security groups.
+pub fn security_with_rules(instance: &Instance, groups: &[SecurityGroup]) -> Vec<DetailLine> {
+    let mut lines = vec![DetailLine::field(
+        "iam instance profile",
+        text(&instance.iam_instance_profile.clone().and_then(|p| p.arn)),
+    )];
+
+    lines.extend(security_lines(instance, groups));
+
+    lines
+}
+
+pub fn tags(instance: &Instance) -> Vec<DetailLine> {
+    let mut tags: Vec<(String, String)> = instance
+        .tags
+        .iter()
+        .flatten()
+        .map(|t| (text(&t.key), text(&t.value)))
+        .collect();
+
+    tags.sort();
+
+    tags.iter()
+        .map(|(key, value)| DetailLine::field(key, value.clone()))
+        .collect()
+}
+
+pub fn metadata(instance: &Instance) -> Vec<DetailLine> {
+    let options = instance.metadata_options.clone().unwrap_or_default();
+
+    vec![
+        DetailLine::field("state", text(&options.state)),
+        DetailLine::field("http endpoint", text(&options.http_endpoint)),
+        DetailLine::field("http tokens", text(&options.http_tokens)),
+        DetailLine::field("hop limit", number(options.http_put_response_hop_limit)),
+        DetailLine::Blank,
+        DetailLine::field("hypervisor", text(&instance.hypervisor)),
+        DetailLine::field("virtualization", text(&instance.virtualization_type)),
+        DetailLine::field("ami launch index", number(instance.ami_launch_index)),
+        DetailLine::field(
+            "hibernation",
+            flag(
+                instance
+                    .hibernation_options
+                    .clone()
+                    .and_then(|h| h.configured),
+            ),
+        ),
+        DetailLine::field("client token", text(&instance.client_token)),
+    ]
+}
+
+fn strip_nulls(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => serde_json::Value::Object(
+            map.into_iter()
+                .filter(|(_, v)| !v.is_null())
+                .map(|(k, v)| (k, strip_nulls(v)))
+                .collect(),
+        ),
+        serde_json::Value::Array(values) => {
+            serde_json::Value::Array(values.into_iter().map(strip_nulls).collect())
+        }
+        value => value,
+    }
+}
+
+/// Returns the complete instance as returned by the api, as pretty-printed
+/// json or yaml lines. Fields that are not set are left out.
+pub fn instance_document(instance: &Instance, yaml: bool) -> Result<Vec<String>, Box<dyn Error>> {
+    let value = strip_nulls(serde_json::to_value(instance)?);
+
+    let document = if yaml {
+        serde_yaml::to_string(&value)?
+    } else {
+        serde_json::to_string_pretty(&value)?
+    };
+
+    Ok(document
+        .lines()
+        .filter(|line| *line != "---")
+        .map(String::from)
+        .collect())
+}
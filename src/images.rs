@@ -0,0 +1,569 @@
+use chrono::DateTime;
+use cursive::theme::{BaseColor, Color, ColorStyle};
+use rusoto_core::Region;
+use rusoto_ec2::{
+    CopyImageRequest, CreateImageRequest, CreateSnapshotsRequest, DeregisterImageRequest,
+    DescribeImageAttributeRequest, DescribeImagesRequest, Ec2, Filter, Image, Instance,
+    InstanceSpecification, LaunchPermission, LaunchPermissionModifications,
+    ModifyImageAttributeRequest, SnapshotInfo, Tag, TagSpecification,
+};
+use std::cmp::Ordering;
+use std::error::Error;
+
+use crate::aws::new_ec2client;
+use crate::views::{Header, TableViewItem};
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ImageColumn {
+    ImageID,
+    Name,
+    Created,
+    Architecture,
+    RootDevice,
+    Visibility,
+    State,
+    UsedBy,
+}
+
+impl Header for ImageColumn {
+    fn to_header(&self) -> String {
+        match self {
+            ImageColumn::ImageID => "image-id".to_string(),
+            ImageColumn::Name => "name".to_string(),
+            ImageColumn::Created => "created".to_string(),
+            ImageColumn::Architecture => "arch".to_string(),
+            ImageColumn::RootDevice => "root".to_string(),
+            ImageColumn::Visibility => "visibility".to_string(),
+            ImageColumn::State => "state".to_string(),
+            ImageColumn::UsedBy => "used-by".to_string(),
+        }
+    }
+
+    fn to_header_size(&self, w: usize) -> usize {
+        match self {
+            ImageColumn::ImageID => 22,
+            ImageColumn::Name => (50 * w) / 160,
+            ImageColumn::Created => 17,
+            ImageColumn::Architecture => 8,
+            ImageColumn::RootDevice => 15,
+            ImageColumn::Visibility => 12,
+            ImageColumn::State => 10,
+            ImageColumn::UsedBy => 24,
+        }
+    }
+}
+
+/// Which images to list.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ImageScope {
+    Owned,
+    Public,
+    Marketplace,
+}
+
+impl ImageScope {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ImageScope::Owned => "owned",
+            ImageScope::Public => "public",
+            ImageScope::Marketplace => "marketplace",
+        }
+    }
+
+    pub fn next(&self) -> ImageScope {
+        match self {
+            ImageScope::Owned => ImageScope::Public,
+            ImageScope::Public => ImageScope::Marketplace,
+            ImageScope::Marketplace => ImageScope::Owned,
+        }
+    }
+}
+
+/// An image, with the running instances launched from it and the accounts
+/// it is shared with, `None` until known.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct ImageRow {
+    pub image: Image,
+    pub used_by: Vec<String>,
+    pub shared_with: Option<Vec<String>>,
+}
+
+impl ImageRow {
+    pub fn image_id(&self) -> String {
+        self.image.image_id.clone().unwrap_or_default()
+    }
+
+    pub fn name(&self) -> String {
+        self.image.name.clone().unwrap_or_default()
+    }
+
+    pub fn is_public(&self) -> bool {
+        self.image.public == Some(true)
+    }
+}
+
+/// Pairs the images with the running instances launched from them.
+pub fn image_rows(images: Vec<Image>, instances: &[Instance]) -> Vec<ImageRow> {
+    images
+        .into_iter()
+        .map(|image| {
+            let used_by = instances
+                .iter()
+                .filter(|i| i.image_id.is_some() && i.image_id == image.image_id)
+                .filter(|i| i.state.as_ref().and_then(|s| s.name.as_deref()) == Some("running"))
+                .filter_map(|i| i.instance_id.clone())
+                .collect();
+
+            ImageRow {
+                image,
+                used_by,
+                shared_with: None,
+            }
+        })
+        .collect()
+}
+
+fn format_date(date: &str) -> String {
+    DateTime::parse_from_rfc3339(date)
+        .map(|d| d.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|_| date.to_string())
+}
+
+impl TableViewItem<ImageColumn> for ImageRow {
+    fn to_column(&self, column: ImageColumn) -> String {
+        match column {
+            ImageColumn::ImageID => self.image_id(),
+            ImageColumn::Name => self.name(),
+            ImageColumn::Created => {
+                format_date(&self.image.creation_date.clone().unwrap_or_default())
+            }
+            ImageColumn::Architecture => self.image.architecture.clone().unwrap_or_default(),
+            ImageColumn::RootDevice => self.image.root_device_type.clone().unwrap_or_default(),
+            ImageColumn::Visibility => {
+                if self.is_public() {
+                    "public".to_string()
+                } else {
+                    match &self.shared_with {
+                        Some(accounts) if !accounts.is_empty() => {
+                            format!("shared ({})", accounts.len())
+                        }
+                        Some(_) => "private".to_string(),
+                        None => "".to_string(),
+                    }
+                }
+            }
+            ImageColumn::State => self.image.state.clone().unwrap_or_default(),
+            ImageColumn::UsedBy => match self.used_by.len() {
+                0 => "".to_string(),
+                1 => self.used_by[0].clone(),
+                n => format!("{} +{}", self.used_by[0], n - 1),
+            },
+        }
+    }
+
+    fn to_column_color(&self, column: ImageColumn) -> ColorStyle {
+        match column {
+            ImageColumn::Name => {
+                ColorStyle::new(Color::Dark(BaseColor::Green), Color::TerminalDefault)
+            }
+            ImageColumn::Visibility if self.is_public() => {
+                ColorStyle::new(Color::Light(BaseColor::Yellow), Color::TerminalDefault)
+            }
+            ImageColumn::State if self.image.state.as_deref() != Some("available") => {
+                ColorStyle::new(Color::Light(BaseColor::Red), Color::TerminalDefault)
+            }
+            _ => ColorStyle::primary(),
+        }
+    }
+
+    fn cmp(&self, other: &Self, column: ImageColumn) -> Ordering {
+        match column {
+            ImageColumn::Created => self.image.creation_date.cmp(&other.image.creation_date),
+            ImageColumn::UsedBy => self.used_by.len().cmp(&other.used_by.len()),
+            _ => self
+                .to_column(column)
+                .to_lowercase()
+                .cmp(&other.to_column(column).to_lowercase()),
+        }
+    }
+}
+
+/// Returns the images of `scope` whose name matches `pattern`, the newest
+/// first. `*` and `?` are wildcards, without them any name containing the
+/// pattern matches.
+pub fn get_images(
+    region: &Region,
+    profile: &str,
+    scope: ImageScope,
+    pattern: &str,
+) -> Result<Vec<Image>, Box<dyn Error>> {
+    let client = new_ec2client(region, profile)?;
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let mut filters = vec![];
+
+    if !pattern.is_empty() {
+        let pattern = if pattern.contains('*') || pattern.contains('?') {
+            pattern.to_string()
+        } else {
+            format!("*{}*", pattern)
+        };
+
+        filters.push(Filter {
+            name: Some("name".to_string()),
+            values: Some(vec![pattern]),
+        });
+    }
+
+    let owners = match scope {
+        ImageScope::Owned => Some(vec!["self".to_string()]),
+        ImageScope::Public => {
+            filters.push(Filter {
+                name: Some("is-public".to_string()),
+                values: Some(vec!["true".to_string()]),
+            });
+            None
+        }
+        ImageScope::Marketplace => Some(vec!["aws-marketplace".to_string()]),
+    };
+
+    let req = DescribeImagesRequest {
+        owners,
+        filters: if filters.is_empty() {
+            None
+        } else {
+            Some(filters)
+        },
+        ..Default::default()
+    };
+
+    let response = runtime.block_on(client.describe_images(req))?;
+
+    let mut images = response.images.unwrap_or_default();
+    images.sort_by(|a, b| b.creation_date.cmp(&a.creation_date));
+
+    Ok(images)
+}
+
+/// Creates an image of an instance, returning the id of the image. Unless
+/// `no_reboot` is set the instance is shut down for a consistent image.
+pub fn create_image(
+    region: &Region,
+    profile: &str,
+    instance_id: &str,
+    name: &str,
+    description: &str,
+    no_reboot: bool,
+    dry_run: bool,
+) -> Result<String, Box<dyn Error>> {
+    let client = new_ec2client(region, profile)?;
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let req = CreateImageRequest {
+        instance_id: instance_id.to_string(),
+        name: name.to_string(),
+        description: if description.is_empty() {
+            None
+        } else {
+            Some(description.to_string())
+        },
+        no_reboot: Some(no_reboot),
+        dry_run: Some(dry_run),
+        ..Default::default()
+    };
+
+    let response = runtime.block_on(client.create_image(req))?;
+
+    Ok(response.image_id.unwrap_or_default())
+}
+
+/// Returns the image with the given id, `None` when it does not exist (any
+/// more).
+pub fn get_image(
+    region: &Region,
+    profile: &str,
+    image_id: &str,
+) -> Result<Option<Image>, Box<dyn Error>> {
+    let client = new_ec2client(region, profile)?;
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let req = DescribeImagesRequest {
+        image_ids: Some(vec![image_id.to_string()]),
+        ..Default::default()
+    };
+
+    let response = runtime.block_on(client.describe_images(req))?;
+
+    Ok(response.images.unwrap_or_default().into_iter().next())
+}
+
+/// Returns the ids of the snapshots backing the volumes of an image.
+pub fn image_snapshot_ids(image: &Image) -> Vec<String> {
+    image
+        .block_device_mappings
+        .iter()
+        .flatten()
+        .filter_map(|m| m.ebs.as_ref().and_then(|ebs| ebs.snapshot_id.clone()))
+        .collect()
+}
+
+/// Snapshots all EBS volumes attached to an instance at the same point in
+/// time, giving each snapshot the same tags.
+pub fn create_instance_snapshots(
+    region: &Region,
+    profile: &str,
+    instance_id: &str,
+    description: &str,
+    exclude_boot_volume: bool,
+    tags: &[(String, String)],
+    dry_run: bool,
+) -> Result<Vec<SnapshotInfo>, Box<dyn Error>> {
+    let client = new_ec2client(region, profile)?;
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let tag_specifications = if tags.is_empty() {
+        None
+    } else {
+        Some(vec![TagSpecification {
+            resource_type: Some("snapshot".to_string()),
+            tags: Some(
+                tags.iter()
+                    .map(|(key, value)| Tag {
+                        key: Some(key.clone()),
+                        value: Some(value.clone()),
+                    })
+                    .collect(),
+            ),
+        }])
+    };
+
+    let req = CreateSnapshotsRequest {
+        description: Some(description.to_string()),
+        instance_specification: InstanceSpecification {
+            instance_id: Some(instance_id.to_string()),
+            exclude_boot_volume: Some(exclude_boot_volume),
+        },
+        tag_specifications,
+        dry_run: Some(dry_run),
+        ..Default::default()
+    };
+
+    let response = runtime.block_on(client.create_snapshots(req))?;
+
+    Ok(response.snapshots.unwrap_or_default())
+}
+
+/// Returns the accounts an image is shared with.
+pub fn get_launch_permissions(
+    region: &Region,
+    profile: &str,
+    image_id: &str,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let client = new_ec2client(region, profile)?;
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let req = DescribeImageAttributeRequest {
+        attribute: "launchPermission".to_string(),
+        image_id: image_id.to_string(),
+        ..Default::default()
+    };
+
+    let response = runtime.block_on(client.describe_image_attribute(req))?;
+
+    Ok(response
+        .launch_permissions
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|permission| permission.user_id)
+        .collect())
+}
+
+fn launch_permissions(accounts: &[String]) -> Option<Vec<LaunchPermission>> {
+    if accounts.is_empty() {
+        return None;
+    }
+
+    Some(
+        accounts
+            .iter()
+            .map(|account| LaunchPermission {
+                user_id: Some(account.clone()),
+                group: None,
+            })
+            .collect(),
+    )
+}
+
+/// Shares an image with the accounts in `add`, and stops sharing it with
+/// those in `remove`.
+pub fn modify_launch_permissions(
+    region: &Region,
+    profile: &str,
+    image_id: &str,
+    add: &[String],
+    remove: &[String],
+    dry_run: bool,
+) -> Result<(), Box<dyn Error>> {
+    let client = new_ec2client(region, profile)?;
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let req = ModifyImageAttributeRequest {
+        image_id: image_id.to_string(),
+        launch_permission: Some(LaunchPermissionModifications {
+            add: launch_permissions(add),
+            remove: launch_permissions(remove),
+        }),
+        dry_run: Some(dry_run),
+        ..Default::default()
+    };
+
+    runtime.block_on(client.modify_image_attribute(req))?;
+
+    Ok(())
+}
+
+/// Parses account ids separated by commas or whitespace.
+pub fn parse_accounts(text: &str) -> Result<Vec<String>, String> {
+    let mut accounts: Vec<String> = vec![];
+
+    for account in text.split(|c: char| c == ',' || c.is_whitespace()) {
+        if account.is_empty() {
+            continue;
+        }
+
+        if account.len() != 12 || !account.chars().all(|c| c.is_ascii_digit()) {
+            return Err(format!("{} is not an account id of 12 digits.", account));
+        }
+
+        if !accounts.iter().any(|a| a == account) {
+            accounts.push(account.to_string());
+        }
+    }
+
+    Ok(accounts)
+}
+
+/// Deregisters an image. Its snapshots are left behind.
+pub fn deregister_image(
+    region: &Region,
+    profile: &str,
+    image_id: &str,
+    dry_run: bool,
+) -> Result<(), Box<dyn Error>> {
+    let client = new_ec2client(region, profile)?;
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let req = DeregisterImageRequest {
+        image_id: image_id.to_string(),
+        dry_run: Some(dry_run),
+    };
+
+    runtime.block_on(client.deregister_image(req))?;
+
+    Ok(())
+}
+
+/// Copies an image from `source_region` into `region`, returning the id of
+/// the copy. Tags are not copied.
+pub fn copy_image(
+    region: &Region,
+    profile: &str,
+    source_region: &Region,
+    image_id: &str,
+    name: &str,
+    description: &str,
+    dry_run: bool,
+) -> Result<String, Box<dyn Error>> {
+    let client = new_ec2client(region, profile)?;
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let req = CopyImageRequest {
+        source_region: source_region.name().to_string(),
+        source_image_id: image_id.to_string(),
+        name: name.to_string(),
+        description: if description.is_empty() {
+            None
+        } else {
+            Some(description.to_string())
+        },
+        dry_run: Some(dry_run),
+        ..Default::default()
+    };
+
+    let response = runtime.block_on(client.copy_image(req))?;
+
+    Ok(response.image_id.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusoto_ec2::InstanceState;
+
+    fn image(id: &str) -> Image {
+        Image {
+            image_id: Some(id.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn instance(id: &str, image_id: Option<&str>, state: &str) -> Instance {
+        Instance {
+            instance_id: Some(id.to_string()),
+            image_id: image_id.map(String::from),
+            state: Some(InstanceState {
+                name: Some(state.to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn accounts() {
+        assert_eq!(
+            parse_accounts("123456789012, 210987654321\n123456789012"),
+            Ok(vec!["123456789012".to_string(), "210987654321".to_string()])
+        );
+        assert_eq!(parse_accounts(" \n,"), Ok(vec![]));
+        assert_eq!(
+            parse_accounts("123456789012 12345"),
+            Err("12345 is not an account id of 12 digits.".to_string())
+        );
+        assert_eq!(
+            parse_accounts("12345678901a"),
+            Err("12345678901a is not an account id of 12 digits.".to_string())
+        );
+    }
+
+    #[test]
+    fn rows() {
+        let instances = vec![
+            instance("i-1", Some("ami-1"), "running"),
+            instance("i-2", Some("ami-1"), "stopped"),
+            instance("i-3", Some("ami-1"), "running"),
+            instance("i-4", Some("ami-2"), "running"),
+            instance("i-5", None, "running"),
+        ];
+
+        let rows = image_rows(
+            vec![image("ami-1"), image("ami-3"), Image::default()],
+            &instances,
+        );
+
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].used_by, vec!["i-1", "i-3"]);
+        assert!(rows[1].used_by.is_empty());
+        // instances without an image don't match images without an id
+        assert!(rows[2].used_by.is_empty());
+        assert!(rows.iter().all(|row| row.shared_with.is_none()));
+    }
+}
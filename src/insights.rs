@@ -0,0 +1,330 @@
+use cursive::theme::ColorStyle;
+use rusoto_core::Region;
+use rusoto_logs::{
+    CloudWatchLogs, GetQueryResultsRequest, ResultField, StartQueryRequest, StopQueryRequest,
+};
+use std::cmp::Ordering;
+use std::error::Error;
+
+use crate::logs::{new_logs_client, now_millis, TimeRange};
+use crate::views::{Header, TableViewItem};
+
+/// Widest a result column is drawn, longer values are truncated.
+const MAX_COLUMN_WIDTH: usize = 60;
+
+pub const DEFAULT_QUERY: &str = "fields @timestamp, @message\n| sort @timestamp desc\n| limit 100";
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct QueryColumn {
+    pub index: usize,
+    name: String,
+    width: usize,
+}
+
+impl Header for QueryColumn {
+    fn to_header(&self) -> String {
+        self.name.clone()
+    }
+
+    fn to_header_size(&self, _: usize) -> usize {
+        self.width
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct QueryRow {
+    pub values: Vec<String>,
+}
+
+impl TableViewItem<QueryColumn> for QueryRow {
+    fn to_column(&self, column: QueryColumn) -> String {
+        self.values.get(column.index).cloned().unwrap_or_default()
+    }
+
+    fn to_column_color(&self, _: QueryColumn) -> ColorStyle {
+        ColorStyle::primary()
+    }
+
+    fn cmp(&self, other: &Self, column: QueryColumn) -> Ordering {
+        let (a, b) = (self.to_column(column.clone()), other.to_column(column));
+
+        // numeric fields (counts, durations) sort by value
+        match (a.parse::<f64>(), b.parse::<f64>()) {
+            (Ok(a), Ok(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+            _ => a.cmp(&b),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct QueryResults {
+    pub status: String,
+    pub records_matched: f64,
+    pub records_scanned: f64,
+    pub bytes_scanned: f64,
+    pub fields: Vec<String>,
+    pub rows: Vec<QueryRow>,
+}
+
+impl QueryResults {
+    pub fn is_done(&self) -> bool {
+        !matches!(self.status.as_str(), "Scheduled" | "Running")
+    }
+
+    /// Returns the table columns, sized after their contents.
+    pub fn columns(&self) -> Vec<QueryColumn> {
+        self.fields
+            .iter()
+            .enumerate()
+            .map(|(index, name)| {
+                let width = self
+                    .rows
+                    .iter()
+                    .map(|row| row.values[index].chars().count())
+                    .chain(std::iter::once(name.chars().count() + 1))
+                    .max()
+                    .unwrap_or(0)
+                    .min(MAX_COLUMN_WIDTH);
+
+                QueryColumn {
+                    index,
+                    name: name.clone(),
+                    width,
+                }
+            })
+            .collect()
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut out = String::new();
+
+        let escape = |value: &str| {
+            if value.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", value.replace('"', "\"\""))
+            } else {
+                value.to_string()
+            }
+        };
+
+        let header: Vec<String> = self.fields.iter().map(|f| escape(f)).collect();
+        out.push_str(&header.join(","));
+        out.push('\n');
+
+        for row in &self.rows {
+            let values: Vec<String> = row.values.iter().map(|v| escape(v)).collect();
+            out.push_str(&values.join(","));
+            out.push('\n');
+        }
+
+        out
+    }
+
+    pub fn to_json(&self) -> String {
+        let rows: Vec<serde_json::Value> = self
+            .rows
+            .iter()
+            .map(|row| {
+                let object: serde_json::Map<String, serde_json::Value> = self
+                    .fields
+                    .iter()
+                    .cloned()
+                    .zip(row.values.iter().cloned().map(serde_json::Value::String))
+                    .collect();
+
+                serde_json::Value::Object(object)
+            })
+            .collect();
+
+        serde_json::to_string_pretty(&rows).unwrap_or_default()
+    }
+}
+
+/// Starts a Logs Insights query over `groups`, returning the query id.
+pub fn start_query(
+    region: &Region,
+    profile: &str,
+    groups: &[String],
+    query: &str,
+    range: TimeRange,
+) -> Result<String, Box<dyn Error>> {
+    let client = new_logs_client(region, profile)?;
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let req = StartQueryRequest {
+        log_group_names: Some(groups.to_vec()),
+        query_string: query.to_string(),
+        start_time: range.start() / 1000,
+        end_time: now_millis() / 1000,
+        ..Default::default()
+    };
+
+    let response = runtime.block_on(client.start_query(req))?;
+
+    Ok(response.query_id.unwrap_or_default())
+}
+
+pub fn get_query_results(
+    region: &Region,
+    profile: &str,
+    query_id: &str,
+) -> Result<QueryResults, Box<dyn Error>> {
+    let client = new_logs_client(region, profile)?;
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let req = GetQueryResultsRequest {
+        query_id: query_id.to_string(),
+    };
+
+    let response = runtime.block_on(client.get_query_results(req))?;
+
+    let statistics = response.statistics.unwrap_or_default();
+
+    let results: Vec<Vec<ResultField>> = response.results.unwrap_or_default();
+
+    // @ptr is an opaque reference to the log record, not worth a column
+    let mut fields: Vec<String> = vec![];
+    for result in &results {
+        for field in result.iter().filter_map(|f| f.field.as_ref()) {
+            if field != "@ptr" && !fields.contains(field) {
+                fields.push(field.clone());
+            }
+        }
+    }
+
+    let rows = results
+        .iter()
+        .map(|result| QueryRow {
+            values: fields
+                .iter()
+                .map(|name| {
+                    result
+                        .iter()
+                        .find(|f| f.field.as_ref() == Some(name))
+                        .and_then(|f| f.value.clone())
+                        .unwrap_or_default()
+                })
+                .collect(),
+        })
+        .collect();
+
+    Ok(QueryResults {
+        status: response.status.unwrap_or_default(),
+        records_matched: statistics.records_matched.unwrap_or(0.0),
+        records_scanned: statistics.records_scanned.unwrap_or(0.0),
+        bytes_scanned: statistics.bytes_scanned.unwrap_or(0.0),
+        fields,
+        rows,
+    })
+}
+
+pub fn stop_query(region: &Region, profile: &str, query_id: &str) -> Result<(), Box<dyn Error>> {
+    let client = new_logs_client(region, profile)?;
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let req = StopQueryRequest {
+        query_id: query_id.to_string(),
+    };
+
+    runtime.block_on(client.stop_query(req))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn results(fields: &[&str], rows: &[&[&str]]) -> QueryResults {
+        QueryResults {
+            status: "Complete".to_string(),
+            fields: fields.iter().map(|f| f.to_string()).collect(),
+            rows: rows
+                .iter()
+                .map(|values| QueryRow {
+                    values: values.iter().map(|v| v.to_string()).collect(),
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn done() {
+        let mut results = results(&[], &[]);
+        assert!(results.is_done());
+
+        for status in &["Scheduled", "Running"] {
+            results.status = status.to_string();
+            assert!(!results.is_done());
+        }
+
+        for status in &["Failed", "Cancelled", "Timeout"] {
+            results.status = status.to_string();
+            assert!(results.is_done());
+        }
+    }
+
+    #[test]
+    fn columns() {
+        let long = "x".repeat(100);
+        let results = results(&["@message", "n"], &[&["short", "12345"], &[&long, "1"]]);
+
+        let columns = results.columns();
+
+        assert_eq!(columns.len(), 2);
+        assert_eq!(columns[0].index, 0);
+        assert_eq!(columns[0].to_header(), "@message");
+        assert_eq!(columns[0].to_header_size(0), MAX_COLUMN_WIDTH);
+        // wide enough for the header and a sort marker
+        assert_eq!(columns[1].to_header(), "n");
+        assert_eq!(columns[1].to_header_size(0), 5);
+    }
+
+    #[test]
+    fn csv() {
+        let results = results(
+            &["@timestamp", "@message"],
+            &[
+                &["2020-06-01 12:00:00", "plain"],
+                &["2020-06-01 12:00:01", "a, \"quoted\"\nvalue"],
+            ],
+        );
+
+        assert_eq!(
+            results.to_csv(),
+            "@timestamp,@message\n\
+             2020-06-01 12:00:00,plain\n\
+             2020-06-01 12:00:01,\"a, \"\"quoted\"\"\nvalue\"\n"
+        );
+    }
+
+    #[test]
+    fn json() {
+        let results = results(&["level", "count"], &[&["error", "3"], &["warn", "10"]]);
+
+        let json: serde_json::Value = serde_json::from_str(&results.to_json()).unwrap();
+
+        assert_eq!(
+            json,
+            serde_json::json!([
+                {"level": "error", "count": "3"},
+                {"level": "warn", "count": "10"},
+            ])
+        );
+    }
+
+    #[test]
+    fn numeric_sort() {
+        let results = results(&["level", "count"], &[&["warn", "10"], &["error", "9"]]);
+        let columns = results.columns();
+        let (a, b) = (&results.rows[0], &results.rows[1]);
+
+        // by value, where "10" < "9" as strings
+        assert_eq!(a.cmp(b, columns[1].clone()), Ordering::Greater);
+        assert_eq!(a.cmp(b, columns[0].clone()), Ordering::Greater);
+        assert_eq!(b.cmp(a, columns[0].clone()), Ordering::Less);
+    }
+}
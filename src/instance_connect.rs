@@ -0,0 +1,149 @@
+use rusoto_core::request::HttpClient;
+use rusoto_core::Region;
+use rusoto_ec2_instance_connect::{
+    Ec2InstanceConnect, Ec2InstanceConnectClient, SendSSHPublicKeyRequest,
+};
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use crate::aws::profile_provider;
+use crate::temp::PrivateDir;
+
+/// A key pair generated for this cloudman run, in a private directory
+/// removed when dropped.
+///
+/// Keys pushed with EC2 Instance Connect are only accepted for 60 seconds,
+/// so one key is good enough for all sessions.
+pub struct EphemeralKey {
+    _dir: PrivateDir,
+    pub private_key: PathBuf,
+    pub public_key: String,
+}
+
+impl EphemeralKey {
+    /// Generates an ed25519 key pair using `ssh-keygen`.
+    pub fn generate() -> io::Result<Self> {
+        let dir = PrivateDir::new("key")?;
+        let private_key = dir.path().join("id_ed25519");
+
+        let output = Command::new("ssh-keygen")
+            .args(["-q", "-t", "ed25519", "-N", "", "-C", "cloudman", "-f"])
+            .arg(&private_key)
+            .stdin(Stdio::null())
+            .output()?;
+
+        if !output.status.success() {
+            return Err(io::Error::other(format!(
+                "ssh-keygen exited with {}.\n\n{}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        let public_key = fs::read_to_string(private_key.with_extension("pub"))?
+            .trim()
+            .to_string();
+
+        Ok(EphemeralKey {
+            _dir: dir,
+            private_key,
+            public_key,
+        })
+    }
+}
+
+/// Host name of the serial console of an instance, ssh connects as
+/// `<instance-id>.port0`.
+pub fn serial_console_host(region: &Region) -> String {
+    format!("serial-console.ec2-instance-connect.{}.aws", region.name())
+}
+
+pub fn new_instance_connect_client(
+    region: &Region,
+    profile: &str,
+) -> Result<Ec2InstanceConnectClient, rusoto_core::request::TlsError> {
+    let http_client = HttpClient::new()?;
+
+    let client =
+        Ec2InstanceConnectClient::new_with(http_client, profile_provider(profile), region.clone());
+
+    Ok(client)
+}
+
+/// Authorizes `public_key` for `user` on the instance for the next 60
+/// seconds.
+pub fn send_ssh_public_key(
+    region: &Region,
+    profile: &str,
+    instance_id: &str,
+    availability_zone: &str,
+    user: &str,
+    public_key: &str,
+) -> Result<(), Box<dyn Error>> {
+    let client = new_instance_connect_client(region, profile)?;
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let req = SendSSHPublicKeyRequest {
+        instance_id: instance_id.to_string(),
+        availability_zone: availability_zone.to_string(),
+        instance_os_user: user.to_string(),
+        ssh_public_key: public_key.to_string(),
+    };
+
+    let response = runtime.block_on(client.send_ssh_public_key(req))?;
+
+    if response.success == Some(false) {
+        return Err(format!(
+            "The key was not accepted (request {}).",
+            response.request_id.unwrap_or_default()
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
+/// Authorizes `public_key` for the serial console of the instance for the
+/// next 60 seconds.
+///
+/// `SendSerialConsoleSSHPublicKey` is not available in rusoto, so the aws cli
+/// is used, like for ssm sessions.
+pub fn send_serial_console_ssh_public_key(
+    region: &Region,
+    profile: &str,
+    instance_id: &str,
+    public_key: &str,
+) -> Result<(), Box<dyn Error>> {
+    let output = Command::new("aws")
+        .args([
+            "ec2-instance-connect",
+            "send-serial-console-ssh-public-key",
+            "--profile",
+            profile,
+            "--region",
+            region.name(),
+            "--instance-id",
+            instance_id,
+            "--serial-port",
+            "0",
+            "--ssh-public-key",
+            public_key,
+        ])
+        .stdin(Stdio::null())
+        .output()?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "aws exited with {}.\n\n{}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,326 @@
+use chrono::DateTime;
+use rusoto_core::Region;
+use rusoto_ec2::{DescribeInstanceStatusRequest, Ec2, InstanceStatusEvent, InstanceStatusSummary};
+use std::collections::HashMap;
+use std::error::Error;
+
+use crate::aws::new_ec2client;
+use crate::views::DetailLine;
+
+/// The result of a reachability check: ok, impaired, initializing,
+/// insufficient-data or not-applicable.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Check {
+    pub status: String,
+    pub impaired_since: Option<String>,
+}
+
+impl Check {
+    fn from_summary(summary: Option<InstanceStatusSummary>) -> Self {
+        let summary = summary.unwrap_or_default();
+
+        Check {
+            status: summary.status.unwrap_or_default(),
+            impaired_since: summary
+                .details
+                .iter()
+                .flatten()
+                .find_map(|d| d.impaired_since.clone()),
+        }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.status == "ok"
+    }
+
+    pub fn is_impaired(&self) -> bool {
+        self.status == "impaired"
+    }
+}
+
+/// A scheduled reboot, stop, retirement or maintenance of an instance.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct ScheduledEvent {
+    pub code: String,
+    pub description: String,
+    pub not_before: Option<String>,
+    pub not_after: Option<String>,
+    pub deadline: Option<String>,
+}
+
+impl ScheduledEvent {
+    fn from_event(event: InstanceStatusEvent) -> Self {
+        ScheduledEvent {
+            code: event.code.unwrap_or_default(),
+            description: event.description.unwrap_or_default(),
+            not_before: event.not_before,
+            not_after: event.not_after,
+            deadline: event.not_before_deadline,
+        }
+    }
+
+    /// Events stay listed for a while after they completed or were canceled,
+    /// their description then starts with `[Completed]` or `[Canceled]`.
+    pub fn is_pending(&self) -> bool {
+        !self.description.starts_with("[Completed]") && !self.description.starts_with("[Canceled]")
+    }
+
+    /// Returns the window in which the event takes place.
+    pub fn window(&self) -> String {
+        match (&self.not_before, &self.not_after) {
+            (Some(before), Some(after)) => {
+                format!("{} - {}", format_date(before), format_date(after))
+            }
+            (Some(before), None) => format!("from {}", format_date(before)),
+            (None, Some(after)) => format!("until {}", format_date(after)),
+            (None, None) => "".to_string(),
+        }
+    }
+}
+
+/// The status checks and scheduled events of an instance.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Status {
+    pub system: Check,
+    pub instance: Check,
+    pub events: Vec<ScheduledEvent>,
+}
+
+impl Status {
+    pub fn is_impaired(&self) -> bool {
+        self.system.is_impaired() || self.instance.is_impaired()
+    }
+
+    pub fn pending_events(&self) -> Vec<&ScheduledEvent> {
+        self.events.iter().filter(|e| e.is_pending()).collect()
+    }
+
+    /// Whether an instance has failing checks or events coming up.
+    pub fn needs_attention(&self) -> bool {
+        self.is_impaired() || !self.pending_events().is_empty()
+    }
+
+    /// Returns the passed checks as `2/2`, or the state of the checks while
+    /// they are not conclusive, followed by the first upcoming event.
+    pub fn summary(&self) -> String {
+        let checks = if self.is_impaired() || (self.system.is_ok() && self.instance.is_ok()) {
+            let passed = [&self.system, &self.instance]
+                .iter()
+                .filter(|c| c.is_ok())
+                .count();
+            format!("{}/2", passed)
+        } else if self.system.status == "not-applicable" {
+            "".to_string()
+        } else if self.system.status == self.instance.status {
+            self.system.status.clone()
+        } else {
+            format!("{}/{}", self.system.status, self.instance.status)
+        };
+
+        match self.pending_events().first() {
+            Some(event) => format!(
+                "{} {} {}",
+                checks,
+                event.code,
+                event
+                    .not_before
+                    .as_ref()
+                    .map(|d| format_day(d))
+                    .unwrap_or_default()
+            )
+            .trim()
+            .to_string(),
+            None => checks,
+        }
+    }
+}
+
+fn parse_date(date: &str) -> Option<DateTime<chrono::FixedOffset>> {
+    DateTime::parse_from_rfc3339(date).ok()
+}
+
+fn format_date(date: &str) -> String {
+    parse_date(date)
+        .map(|d| d.format("%Y-%m-%d %H:%M UTC").to_string())
+        .unwrap_or_else(|| date.to_string())
+}
+
+fn format_day(date: &str) -> String {
+    parse_date(date)
+        .map(|d| d.format("%Y-%m-%d").to_string())
+        .unwrap_or_else(|| date.to_string())
+}
+
+/// Returns the status per instance id of the given instances, all instances
+/// of the region when `None`. Stopped instances are included.
+pub fn get_instance_statuses(
+    region: &Region,
+    profile: &str,
+    instance_ids: Option<Vec<String>>,
+) -> Result<HashMap<String, Status>, Box<dyn Error>> {
+    let client = new_ec2client(region, profile)?;
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let mut statuses = HashMap::new();
+    let mut next_token = None;
+
+    loop {
+        let req = DescribeInstanceStatusRequest {
+            include_all_instances: Some(true),
+            instance_ids: instance_ids.clone(),
+            next_token: next_token.clone(),
+            ..Default::default()
+        };
+
+        let response = runtime.block_on(client.describe_instance_status(req))?;
+
+        for status in response.instance_statuses.unwrap_or_default() {
+            if let Some(instance_id) = status.instance_id {
+                statuses.insert(
+                    instance_id,
+                    Status {
+                        system: Check::from_summary(status.system_status),
+                        instance: Check::from_summary(status.instance_status),
+                        events: status
+                            .events
+                            .unwrap_or_default()
+                            .into_iter()
+                            .map(ScheduledEvent::from_event)
+                            .collect(),
+                    },
+                );
+            }
+        }
+
+        next_token = response.next_token;
+        if next_token.is_none() {
+            break;
+        }
+    }
+
+    Ok(statuses)
+}
+
+fn check_line(label: &str, check: &Check) -> DetailLine {
+    let value = match &check.impaired_since {
+        Some(since) => format!("{} since {}", check.status, format_date(since)),
+        None => check.status.clone(),
+    };
+
+    if check.is_impaired() {
+        DetailLine::Alert(label.to_string(), value)
+    } else {
+        DetailLine::field(label, value)
+    }
+}
+
+/// Returns the status checks and scheduled events as lines for the details
+/// screen.
+pub fn status_lines(status: &Status) -> Vec<DetailLine> {
+    let mut lines = vec![
+        DetailLine::section("status checks"),
+        check_line("system reachability", &status.system),
+        check_line("instance reachability", &status.instance),
+    ];
+
+    lines.push(DetailLine::Blank);
+    lines.push(DetailLine::section("scheduled events"));
+
+    if status.events.is_empty() {
+        lines.push(DetailLine::field("", "none"));
+    }
+
+    for event in status.events.iter() {
+        let mut value = format!("{}  {}", event.window(), event.description);
+        if let Some(deadline) = &event.deadline {
+            value = format!("{}  (deadline {})", value, format_date(deadline));
+        }
+
+        if event.is_pending() {
+            lines.push(DetailLine::Warning(event.code.clone(), value));
+        } else {
+            lines.push(DetailLine::field(&event.code, value));
+        }
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(status: &str) -> Check {
+        Check {
+            status: status.to_string(),
+            impaired_since: None,
+        }
+    }
+
+    fn event(description: &str) -> ScheduledEvent {
+        ScheduledEvent {
+            code: "system-reboot".to_string(),
+            description: description.to_string(),
+            not_before: Some("2026-11-02T10:00:00.000Z".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn summary() {
+        let cases = vec![
+            ("ok", "ok", vec![], "2/2"),
+            ("impaired", "ok", vec![], "1/2"),
+            ("ok", "impaired", vec![], "1/2"),
+            ("impaired", "impaired", vec![], "0/2"),
+            ("impaired", "initializing", vec![], "0/2"),
+            ("not-applicable", "not-applicable", vec![], ""),
+            ("initializing", "initializing", vec![], "initializing"),
+            ("ok", "insufficient-data", vec![], "ok/insufficient-data"),
+            (
+                "ok",
+                "ok",
+                vec![event("The instance is scheduled for a reboot")],
+                "2/2 system-reboot 2026-11-02",
+            ),
+            (
+                "not-applicable",
+                "not-applicable",
+                vec![event("The instance is scheduled for a reboot")],
+                "system-reboot 2026-11-02",
+            ),
+            (
+                "ok",
+                "ok",
+                vec![event("[Completed] The instance is scheduled for a reboot")],
+                "2/2",
+            ),
+        ];
+
+        for (system, instance, events, expected) in cases {
+            let status = Status {
+                system: check(system),
+                instance: check(instance),
+                events,
+            };
+
+            assert_eq!(status.summary(), expected, "{} {}", system, instance);
+        }
+    }
+
+    #[test]
+    fn pending() {
+        let cases = vec![
+            ("The instance is scheduled for a reboot", true),
+            ("[Completed] The instance is scheduled for a reboot", false),
+            ("[Canceled] The instance is scheduled for a reboot", false),
+            ("Completed [Canceled]", true),
+            ("", true),
+        ];
+
+        for (description, expected) in cases {
+            assert_eq!(event(description).is_pending(), expected, "{}", description);
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use cursive::theme::{BaseColor, Color, ColorStyle};
+use rusoto_core::Region;
+use rusoto_ec2::{
+    AttributeValue, DescribeInstanceTypesRequest, Ec2, Filter, InstanceTypeInfo,
+    ModifyInstanceAttributeRequest,
+};
+use std::cmp::Ordering;
+use std::error::Error;
+
+use crate::aws::new_ec2client;
+use crate::views::{Header, TableViewItem};
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub enum InstanceTypeColumn {
+    Type,
+    VCpus,
+    Memory,
+    Architecture,
+    Network,
+    Burstable,
+    CurrentGeneration,
+}
+
+impl Header for InstanceTypeColumn {
+    fn to_header(&self) -> String {
+        match self {
+            InstanceTypeColumn::Type => "type".to_string(),
+            InstanceTypeColumn::VCpus => "vcpus".to_string(),
+            InstanceTypeColumn::Memory => "memory".to_string(),
+            InstanceTypeColumn::Architecture => "arch".to_string(),
+            InstanceTypeColumn::Network => "network".to_string(),
+            InstanceTypeColumn::Burstable => "burstable".to_string(),
+            InstanceTypeColumn::CurrentGeneration => "current".to_string(),
+        }
+    }
+
+    fn to_header_size(&self, _: usize) -> usize {
+        match self {
+            InstanceTypeColumn::Type => 20,
+            InstanceTypeColumn::VCpus => 6,
+            InstanceTypeColumn::Memory => 10,
+            InstanceTypeColumn::Architecture => 14,
+            InstanceTypeColumn::Network => 20,
+            InstanceTypeColumn::Burstable => 9,
+            InstanceTypeColumn::CurrentGeneration => 7,
+        }
+    }
+}
+
+fn text(value: &Option<String>) -> String {
+    value.clone().unwrap_or_default()
+}
+
+fn flag(value: Option<bool>) -> String {
+    match value {
+        Some(true) => "yes".to_string(),
+        Some(false) => "no".to_string(),
+        None => "".to_string(),
+    }
+}
+
+fn vcpus(info: &InstanceTypeInfo) -> Option<i64> {
+    info.v_cpu_info.as_ref().and_then(|v| v.default_v_cpus)
+}
+
+fn memory(info: &InstanceTypeInfo) -> Option<i64> {
+    info.memory_info.as_ref().and_then(|m| m.size_in_mi_b)
+}
+
+impl TableViewItem<InstanceTypeColumn> for InstanceTypeInfo {
+    fn to_column(&self, column: InstanceTypeColumn) -> String {
+        match column {
+            InstanceTypeColumn::Type => text(&self.instance_type),
+            InstanceTypeColumn::VCpus => vcpus(self).map(|v| v.to_string()).unwrap_or_default(),
+            InstanceTypeColumn::Memory => memory(self)
+                .map(|m| format!("{:.1} GiB", m as f64 / 1024.0))
+                .unwrap_or_default(),
+            InstanceTypeColumn::Architecture => self
+                .processor_info
+                .as_ref()
+                .and_then(|p| p.supported_architectures.clone())
+                .unwrap_or_default()
+                .join(","),
+            InstanceTypeColumn::Network => text(
+                &self
+                    .network_info
+                    .as_ref()
+                    .and_then(|n| n.network_performance.clone()),
+            ),
+            InstanceTypeColumn::Burstable => flag(self.burstable_performance_supported),
+            InstanceTypeColumn::CurrentGeneration => flag(self.current_generation),
+        }
+    }
+
+    fn to_column_color(&self, column: InstanceTypeColumn) -> ColorStyle {
+        match column {
+            InstanceTypeColumn::Type => {
+                ColorStyle::new(Color::Dark(BaseColor::Green), Color::TerminalDefault)
+            }
+            InstanceTypeColumn::CurrentGeneration if self.current_generation == Some(false) => {
+                ColorStyle::new(Color::Light(BaseColor::Yellow), Color::TerminalDefault)
+            }
+            _ => ColorStyle::primary(),
+        }
+    }
+
+    fn cmp(&self, other: &Self, column: InstanceTypeColumn) -> Ordering {
+        match column {
+            InstanceTypeColumn::VCpus => vcpus(self).cmp(&vcpus(other)),
+            InstanceTypeColumn::Memory => memory(self).cmp(&memory(other)),
+            _ => self
+                .to_column(column)
+                .to_lowercase()
+                .cmp(&other.to_column(column).to_lowercase()),
+        }
+    }
+}
+
+/// Returns the instance types available in the region for the given
+/// architecture and virtualization type, sorted by vcpus and memory.
+pub fn get_instance_types(
+    region: &Region,
+    profile: &str,
+    architecture: &str,
+    virtualization: &str,
+) -> Result<Vec<InstanceTypeInfo>, Box<dyn Error>> {
+    let client = new_ec2client(region, profile)?;
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let filters = vec![
+        Filter {
+            name: Some("processor-info.supported-architecture".to_string()),
+            values: Some(vec![architecture.to_string()]),
+        },
+        Filter {
+            name: Some("supported-virtualization-type".to_string()),
+            values: Some(vec![virtualization.to_string()]),
+        },
+    ];
+
+    let mut types = vec![];
+    let mut next_token = None;
+
+    loop {
+        let req = DescribeInstanceTypesRequest {
+            filters: Some(filters.clone()),
+            next_token: next_token.clone(),
+            ..Default::default()
+        };
+
+        let response = runtime.block_on(client.describe_instance_types(req))?;
+
+        types.extend(response.instance_types.unwrap_or_default());
+
+        next_token = response.next_token;
+        if next_token.is_none() {
+            break;
+        }
+    }
+
+    types.sort_by(|a, b| {
+        vcpus(a)
+            .cmp(&vcpus(b))
+            .then(memory(a).cmp(&memory(b)))
+            .then(a.instance_type.cmp(&b.instance_type))
+    });
+
+    Ok(types)
+}
+
+/// Changes the type of a stopped instance.
+pub fn modify_instance_type(
+    region: &Region,
+    profile: &str,
+    instance_id: &str,
+    instance_type: &str,
+    dry_run: bool,
+) -> Result<(), Box<dyn Error>> {
+    let client = new_ec2client(region, profile)?;
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let req = ModifyInstanceAttributeRequest {
+        instance_id: instance_id.to_string(),
+        instance_type: Some(AttributeValue {
+            value: Some(instance_type.to_string()),
+        }),
+        dry_run: Some(dry_run),
+        ..Default::default()
+    };
+
+    runtime.block_on(client.modify_instance_attribute(req))?;
+
+    Ok(())
+}
@@ -0,0 +1,75 @@
+use rusoto_core::Region;
+use rusoto_ec2::{DescribeInstancesRequest, Ec2, StartInstancesRequest, StopInstancesRequest};
+use std::error::Error;
+
+use crate::aws::new_ec2client;
+
+pub fn stop_instance(
+    region: &Region,
+    profile: &str,
+    instance_id: &str,
+    dry_run: bool,
+) -> Result<(), Box<dyn Error>> {
+    let client = new_ec2client(region, profile)?;
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let req = StopInstancesRequest {
+        instance_ids: vec![instance_id.to_string()],
+        dry_run: Some(dry_run),
+        ..Default::default()
+    };
+
+    runtime.block_on(client.stop_instances(req))?;
+
+    Ok(())
+}
+
+pub fn start_instance(
+    region: &Region,
+    profile: &str,
+    instance_id: &str,
+    dry_run: bool,
+) -> Result<(), Box<dyn Error>> {
+    let client = new_ec2client(region, profile)?;
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let req = StartInstancesRequest {
+        instance_ids: vec![instance_id.to_string()],
+        dry_run: Some(dry_run),
+        ..Default::default()
+    };
+
+    runtime.block_on(client.start_instances(req))?;
+
+    Ok(())
+}
+
+/// Returns the name of the state of an instance, e.g. `stopping`.
+pub fn get_instance_state(
+    region: &Region,
+    profile: &str,
+    instance_id: &str,
+) -> Result<String, Box<dyn Error>> {
+    let client = new_ec2client(region, profile)?;
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let req = DescribeInstancesRequest {
+        instance_ids: Some(vec![instance_id.to_string()]),
+        ..Default::default()
+    };
+
+    let response = runtime.block_on(client.describe_instances(req))?;
+
+    Ok(response
+        .reservations
+        .into_iter()
+        .flatten()
+        .flat_map(|r| r.instances.unwrap_or_default())
+        .next()
+        .and_then(|i| i.state)
+        .and_then(|s| s.name)
+        .unwrap_or_default())
+}
@@ -0,0 +1,572 @@
+use rusoto_core::Region;
+use rusoto_ec2::{
+    DescribeKeyPairsRequest, DescribeLaunchTemplateVersionsRequest, DescribeLaunchTemplatesRequest,
+    DescribeSubnetsRequest, Ec2, Instance, InstanceMarketOptionsRequest, LaunchTemplate,
+    LaunchTemplateSpecification, LaunchTemplateVersion, RunInstancesRequest, SpotMarketOptions,
+    Subnet, Tag, TagSpecification,
+};
+use std::cmp::Reverse;
+use std::error::Error;
+
+use crate::aws::new_ec2client;
+use crate::tags::copyable_tags;
+
+/// The most instances launched at once.
+pub const MAX_COUNT: i64 = 100;
+
+/// A version of a launch template, `$Default`, `$Latest` or a number.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct TemplateSource {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+}
+
+/// What to launch. Empty values are taken from the launch template, or left
+/// to the defaults of the account without one.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct LaunchSpec {
+    pub template: Option<TemplateSource>,
+    pub image_id: String,
+    pub instance_type: String,
+    pub subnet_id: String,
+    pub security_group_ids: Vec<String>,
+    pub key_name: String,
+    pub tags: Vec<(String, String)>,
+    pub count: i64,
+    /// The maximum hourly price of spot instances, empty for at most the
+    /// on-demand price. On-demand instances when `None`.
+    pub spot_price: Option<String>,
+}
+
+fn opt(value: &str) -> Option<String> {
+    if value.is_empty() {
+        None
+    } else {
+        Some(value.to_string())
+    }
+}
+
+impl LaunchSpec {
+    pub fn new() -> Self {
+        LaunchSpec {
+            count: 1,
+            ..Default::default()
+        }
+    }
+
+    /// Returns a spec to launch another instance like `instance`: the same
+    /// image, type, subnet, security groups, key pair and tags.
+    pub fn like(instance: &Instance) -> Self {
+        LaunchSpec {
+            template: None,
+            image_id: instance.image_id.clone().unwrap_or_default(),
+            instance_type: instance.instance_type.clone().unwrap_or_default(),
+            subnet_id: instance.subnet_id.clone().unwrap_or_default(),
+            security_group_ids: instance
+                .security_groups
+                .iter()
+                .flatten()
+                .filter_map(|group| group.group_id.clone())
+                .collect(),
+            key_name: instance.key_name.clone().unwrap_or_default(),
+            tags: copyable_tags(&instance.tags),
+            count: 1,
+            spot_price: None,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.template.is_none() && self.image_id.is_empty() {
+            return Err("An image is required without a launch template.".to_string());
+        }
+        if self.count < 1 || self.count > MAX_COUNT {
+            return Err(format!("The count has to be between 1 and {}.", MAX_COUNT));
+        }
+        match &self.spot_price {
+            Some(price) if !price.is_empty() => match price.parse::<f64>() {
+                Ok(price) if price > 0.0 => {}
+                _ => return Err("The max price has to be a positive number.".to_string()),
+            },
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Returns a line per setting, for the review before launching.
+    pub fn summary(&self) -> Vec<String> {
+        let unset = if self.template.is_some() {
+            "from template"
+        } else {
+            "default"
+        };
+        let value = |value: &str| {
+            if value.is_empty() {
+                unset.to_string()
+            } else {
+                value.to_string()
+            }
+        };
+
+        let mut lines = vec![];
+
+        if let Some(template) = &self.template {
+            lines.push(format!(
+                "template         {} ({}) version {}",
+                template.name, template.id, template.version
+            ));
+        }
+
+        lines.push(format!("image            {}", value(&self.image_id)));
+        lines.push(format!("instance type    {}", value(&self.instance_type)));
+        lines.push(format!("subnet           {}", value(&self.subnet_id)));
+        lines.push(format!(
+            "security groups  {}",
+            value(&self.security_group_ids.join(", "))
+        ));
+        lines.push(format!("key pair         {}", value(&self.key_name)));
+
+        let tags: Vec<String> = self
+            .tags
+            .iter()
+            .map(|(key, value)| format!("{} = {}", key, value))
+            .collect();
+        lines.push(format!("tags             {}", value(&tags.join(", "))));
+
+        lines.push(format!("count            {}", self.count));
+
+        lines.push(match &self.spot_price {
+            Some(price) if !price.is_empty() => {
+                format!("market           spot, at most {} per hour", price)
+            }
+            Some(_) => "market           spot, at most the on-demand price".to_string(),
+            None => "market           on-demand".to_string(),
+        });
+
+        lines
+    }
+}
+
+pub(crate) fn name_tag(tags: &Option<Vec<Tag>>) -> Option<String> {
+    tags.iter()
+        .flatten()
+        .find(|t| t.key.as_deref() == Some("Name"))
+        .and_then(|t| t.value.clone())
+}
+
+/// Describes a subnet by its id, availability zone, cidr and name.
+pub fn subnet_label(subnet: &Subnet) -> String {
+    format!(
+        "{}  {}  {}  {}",
+        subnet.subnet_id.clone().unwrap_or_default(),
+        subnet.availability_zone.clone().unwrap_or_default(),
+        subnet.cidr_block.clone().unwrap_or_default(),
+        name_tag(&subnet.tags).unwrap_or_default()
+    )
+    .trim()
+    .to_string()
+}
+
+/// Describes a template version, e.g. `3  web servers (default)`.
+pub fn version_label(version: &LaunchTemplateVersion) -> String {
+    let mut label = format!(
+        "{}  {}",
+        version.version_number.unwrap_or_default(),
+        version.version_description.clone().unwrap_or_default()
+    )
+    .trim()
+    .to_string();
+
+    if version.default_version == Some(true) {
+        label.push_str(" (default)");
+    }
+
+    label
+}
+
+pub fn get_launch_templates(
+    region: &Region,
+    profile: &str,
+) -> Result<Vec<LaunchTemplate>, Box<dyn Error>> {
+    let client = new_ec2client(region, profile)?;
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let mut templates = vec![];
+    let mut next_token = None;
+
+    loop {
+        let req = DescribeLaunchTemplatesRequest {
+            next_token: next_token.clone(),
+            ..Default::default()
+        };
+
+        let response = runtime.block_on(client.describe_launch_templates(req))?;
+
+        templates.extend(response.launch_templates.unwrap_or_default());
+
+        next_token = response.next_token;
+        if next_token.is_none() {
+            break;
+        }
+    }
+
+    templates.sort_by(|a, b| a.launch_template_name.cmp(&b.launch_template_name));
+
+    Ok(templates)
+}
+
+/// Returns the versions of a launch template, the newest first.
+pub fn get_launch_template_versions(
+    region: &Region,
+    profile: &str,
+    template_id: &str,
+) -> Result<Vec<LaunchTemplateVersion>, Box<dyn Error>> {
+    let client = new_ec2client(region, profile)?;
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let mut versions = vec![];
+    let mut next_token = None;
+
+    loop {
+        let req = DescribeLaunchTemplateVersionsRequest {
+            launch_template_id: Some(template_id.to_string()),
+            next_token: next_token.clone(),
+            ..Default::default()
+        };
+
+        let response = runtime.block_on(client.describe_launch_template_versions(req))?;
+
+        versions.extend(response.launch_template_versions.unwrap_or_default());
+
+        next_token = response.next_token;
+        if next_token.is_none() {
+            break;
+        }
+    }
+
+    versions.sort_by_key(|v| Reverse(v.version_number));
+
+    Ok(versions)
+}
+
+/// Returns the subnets of the region, sorted by vpc and availability zone.
+pub fn get_subnets(region: &Region, profile: &str) -> Result<Vec<Subnet>, Box<dyn Error>> {
+    let client = new_ec2client(region, profile)?;
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let mut subnets = vec![];
+    let mut next_token = None;
+
+    loop {
+        let req = DescribeSubnetsRequest {
+            next_token: next_token.clone(),
+            ..Default::default()
+        };
+
+        let response = runtime.block_on(client.describe_subnets(req))?;
+
+        subnets.extend(response.subnets.unwrap_or_default());
+
+        next_token = response.next_token;
+        if next_token.is_none() {
+            break;
+        }
+    }
+
+    subnets.sort_by(|a, b| {
+        a.vpc_id
+            .cmp(&b.vpc_id)
+            .then(a.availability_zone.cmp(&b.availability_zone))
+            .then(a.cidr_block.cmp(&b.cidr_block))
+    });
+
+    Ok(subnets)
+}
+
+/// Returns the names of the key pairs of the region.
+pub fn get_key_pairs(region: &Region, profile: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let client = new_ec2client(region, profile)?;
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let response =
+        runtime.block_on(client.describe_key_pairs(DescribeKeyPairsRequest::default()))?;
+
+    let mut names: Vec<String> = response
+        .key_pairs
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|key| key.key_name)
+        .collect();
+
+    names.sort();
+
+    Ok(names)
+}
+
+/// Launches the instances, returning their ids. Either all `count`
+/// instances are launched or none.
+pub fn run_instances(
+    region: &Region,
+    profile: &str,
+    spec: &LaunchSpec,
+    dry_run: bool,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let client = new_ec2client(region, profile)?;
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let tags: Vec<Tag> = spec
+        .tags
+        .iter()
+        .map(|(key, value)| Tag {
+            key: Some(key.clone()),
+            value: Some(value.clone()),
+        })
+        .collect();
+
+    let tag_specifications = if tags.is_empty() {
+        None
+    } else {
+        Some(
+            ["instance", "volume"]
+                .iter()
+                .map(|resource_type| TagSpecification {
+                    resource_type: Some(resource_type.to_string()),
+                    tags: Some(tags.clone()),
+                })
+                .collect(),
+        )
+    };
+
+    let req = RunInstancesRequest {
+        launch_template: spec
+            .template
+            .as_ref()
+            .map(|template| LaunchTemplateSpecification {
+                launch_template_id: Some(template.id.clone()),
+                version: Some(template.version.clone()),
+                ..Default::default()
+            }),
+        image_id: opt(&spec.image_id),
+        instance_type: opt(&spec.instance_type),
+        subnet_id: opt(&spec.subnet_id),
+        security_group_ids: if spec.security_group_ids.is_empty() {
+            None
+        } else {
+            Some(spec.security_group_ids.clone())
+        },
+        key_name: opt(&spec.key_name),
+        tag_specifications,
+        // a one-time spot request per instance
+        instance_market_options: spec.spot_price.as_ref().map(|price| {
+            InstanceMarketOptionsRequest {
+                market_type: Some("spot".to_string()),
+                spot_options: Some(SpotMarketOptions {
+                    max_price: opt(price),
+                    ..Default::default()
+                }),
+            }
+        }),
+        min_count: spec.count,
+        max_count: spec.count,
+        dry_run: Some(dry_run),
+        ..Default::default()
+    };
+
+    let reservation = runtime.block_on(client.run_instances(req))?;
+
+    Ok(reservation
+        .instances
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|instance| instance.instance_id)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusoto_ec2::GroupIdentifier;
+
+    fn spec() -> LaunchSpec {
+        LaunchSpec {
+            image_id: "ami-1".to_string(),
+            ..LaunchSpec::new()
+        }
+    }
+
+    fn template() -> TemplateSource {
+        TemplateSource {
+            id: "lt-1".to_string(),
+            name: "web".to_string(),
+            version: "$Default".to_string(),
+        }
+    }
+
+    #[test]
+    fn validate() {
+        assert_eq!(spec().validate(), Ok(()));
+        assert_eq!(
+            LaunchSpec::new().validate(),
+            Err("An image is required without a launch template.".to_string())
+        );
+        assert_eq!(
+            LaunchSpec {
+                template: Some(template()),
+                ..LaunchSpec::new()
+            }
+            .validate(),
+            Ok(())
+        );
+
+        for count in &[0, MAX_COUNT + 1] {
+            assert_eq!(
+                LaunchSpec {
+                    count: *count,
+                    ..spec()
+                }
+                .validate(),
+                Err("The count has to be between 1 and 100.".to_string())
+            );
+        }
+        assert_eq!(
+            LaunchSpec {
+                count: MAX_COUNT,
+                ..spec()
+            }
+            .validate(),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn validate_spot_price() {
+        for price in &["", "0.05"] {
+            let spot = LaunchSpec {
+                spot_price: Some(price.to_string()),
+                ..spec()
+            };
+            assert_eq!(spot.validate(), Ok(()), "{}", price);
+        }
+
+        for price in &["0", "-1", "cheap"] {
+            let spot = LaunchSpec {
+                spot_price: Some(price.to_string()),
+                ..spec()
+            };
+            assert_eq!(
+                spot.validate(),
+                Err("The max price has to be a positive number.".to_string()),
+                "{}",
+                price
+            );
+        }
+    }
+
+    #[test]
+    fn like() {
+        let instance = Instance {
+            image_id: Some("ami-1".to_string()),
+            instance_type: Some("t3.micro".to_string()),
+            subnet_id: Some("subnet-1".to_string()),
+            security_groups: Some(vec![
+                GroupIdentifier {
+                    group_id: Some("sg-1".to_string()),
+                    group_name: Some("web".to_string()),
+                },
+                GroupIdentifier {
+                    group_id: Some("sg-2".to_string()),
+                    group_name: Some("ssh".to_string()),
+                },
+            ]),
+            key_name: Some("ops".to_string()),
+            tags: Some(vec![
+                Tag {
+                    key: Some("Name".to_string()),
+                    value: Some("web".to_string()),
+                },
+                Tag {
+                    key: Some("aws:cloudformation:stack-name".to_string()),
+                    value: Some("web-stack".to_string()),
+                },
+            ]),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            LaunchSpec::like(&instance),
+            LaunchSpec {
+                template: None,
+                image_id: "ami-1".to_string(),
+                instance_type: "t3.micro".to_string(),
+                subnet_id: "subnet-1".to_string(),
+                security_group_ids: vec!["sg-1".to_string(), "sg-2".to_string()],
+                key_name: "ops".to_string(),
+                tags: vec![("Name".to_string(), "web".to_string())],
+                count: 1,
+                spot_price: None,
+            }
+        );
+    }
+
+    #[test]
+    fn summary() {
+        let spot = LaunchSpec {
+            instance_type: "t3.micro".to_string(),
+            tags: vec![
+                ("Name".to_string(), "web".to_string()),
+                ("env".to_string(), "prod".to_string()),
+            ],
+            count: 2,
+            spot_price: Some("0.05".to_string()),
+            ..spec()
+        };
+
+        assert_eq!(
+            spot.summary(),
+            vec![
+                "image            ami-1",
+                "instance type    t3.micro",
+                "subnet           default",
+                "security groups  default",
+                "key pair         default",
+                "tags             Name = web, env = prod",
+                "count            2",
+                "market           spot, at most 0.05 per hour",
+            ]
+        );
+    }
+
+    #[test]
+    fn summary_of_template() {
+        let spec = LaunchSpec {
+            template: Some(template()),
+            spot_price: Some("".to_string()),
+            ..LaunchSpec::new()
+        };
+
+        assert_eq!(
+            spec.summary(),
+            vec![
+                "template         web (lt-1) version $Default",
+                "image            from template",
+                "instance type    from template",
+                "subnet           from template",
+                "security groups  from template",
+                "key pair         from template",
+                "tags             from template",
+                "count            1",
+                "market           spot, at most the on-demand price",
+            ]
+        );
+        assert_eq!(
+            LaunchSpec::new().summary().last().unwrap(),
+            "market           on-demand"
+        );
+    }
+}
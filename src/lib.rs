@@ -1 +1,24 @@
+pub mod aws;
+pub mod config;
+pub mod details;
+pub mod images;
+pub mod insights;
+pub mod instance_connect;
+pub mod instance_status;
+pub mod instance_types;
+pub mod instances;
+pub mod launch;
+pub mod logs;
+pub mod metrics;
+pub mod run_command;
+pub mod security_groups;
+pub mod ssh;
+pub mod spot;
+pub mod ssm;
+pub mod tags;
+pub mod temp;
+pub mod terminal;
+pub mod tunnels;
+pub mod user_data;
 pub mod views;
+pub mod volumes;
@@ -0,0 +1,376 @@
+use chrono::{Local, TimeZone, Utc};
+use rusoto_core::request::HttpClient;
+use rusoto_core::Region;
+use rusoto_logs::{
+    CloudWatchLogs, CloudWatchLogsClient, DescribeLogGroupsRequest, DescribeLogStreamsRequest,
+    FilterLogEventsRequest, FilteredLogEvent, LogGroup, LogStream,
+};
+use std::error::Error;
+
+use crate::aws::profile_provider;
+
+/// Upper bound of events retrieved for a single query, to keep the view
+/// responsive on chatty log groups.
+const MAX_EVENTS: usize = 10_000;
+
+/// Times the range of a query is narrowed down to its newest part when it
+/// holds more than `MAX_EVENTS` events.
+const NARROWING_STEPS: usize = 4;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TimeRange {
+    Minutes15,
+    Hour1,
+    Hours6,
+    Hours24,
+    Days7,
+}
+
+impl TimeRange {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TimeRange::Minutes15 => "15m",
+            TimeRange::Hour1 => "1h",
+            TimeRange::Hours6 => "6h",
+            TimeRange::Hours24 => "24h",
+            TimeRange::Days7 => "7d",
+        }
+    }
+
+    pub fn millis(&self) -> i64 {
+        let minutes = match self {
+            TimeRange::Minutes15 => 15,
+            TimeRange::Hour1 => 60,
+            TimeRange::Hours6 => 6 * 60,
+            TimeRange::Hours24 => 24 * 60,
+            TimeRange::Days7 => 7 * 24 * 60,
+        };
+
+        minutes * 60 * 1000
+    }
+
+    pub fn all() -> Vec<TimeRange> {
+        vec![
+            TimeRange::Minutes15,
+            TimeRange::Hour1,
+            TimeRange::Hours6,
+            TimeRange::Hours24,
+            TimeRange::Days7,
+        ]
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            TimeRange::Minutes15 => TimeRange::Hour1,
+            TimeRange::Hour1 => TimeRange::Hours6,
+            TimeRange::Hours6 => TimeRange::Hours24,
+            TimeRange::Hours24 => TimeRange::Days7,
+            TimeRange::Days7 => TimeRange::Minutes15,
+        }
+    }
+
+    /// Start of the range in milliseconds since epoch, relative to now.
+    pub fn start(&self) -> i64 {
+        now_millis() - self.millis()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct EventQuery {
+    pub group: String,
+    pub stream: Option<String>,
+    pub pattern: String,
+    pub range: TimeRange,
+}
+
+impl EventQuery {
+    pub fn new(group: &str, stream: Option<String>) -> Self {
+        EventQuery {
+            group: group.to_string(),
+            stream,
+            pattern: "".to_string(),
+            range: TimeRange::Hour1,
+        }
+    }
+}
+
+pub fn now_millis() -> i64 {
+    Utc::now().timestamp_millis()
+}
+
+pub fn new_logs_client(
+    region: &Region,
+    profile: &str,
+) -> Result<CloudWatchLogsClient, rusoto_core::request::TlsError> {
+    let http_client = HttpClient::new()?;
+
+    let client =
+        CloudWatchLogsClient::new_with(http_client, profile_provider(profile), region.clone());
+
+    Ok(client)
+}
+
+pub fn get_log_groups(region: &Region, profile: &str) -> Result<Vec<LogGroup>, Box<dyn Error>> {
+    let client = new_logs_client(region, profile)?;
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let mut groups = vec![];
+    let mut next_token = None;
+
+    loop {
+        let req = DescribeLogGroupsRequest {
+            next_token: next_token.clone(),
+            ..Default::default()
+        };
+
+        let response = runtime.block_on(client.describe_log_groups(req))?;
+
+        groups.extend(response.log_groups.unwrap_or_default());
+
+        next_token = response.next_token;
+        if next_token.is_none() {
+            break;
+        }
+    }
+
+    Ok(groups)
+}
+
+/// Returns the streams of a log group, most recently written first.
+pub fn get_log_streams(
+    region: &Region,
+    profile: &str,
+    group: &str,
+) -> Result<Vec<LogStream>, Box<dyn Error>> {
+    let client = new_logs_client(region, profile)?;
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let req = DescribeLogStreamsRequest {
+        log_group_name: group.to_string(),
+        order_by: Some("LastEventTime".to_string()),
+        descending: Some(true),
+        ..Default::default()
+    };
+
+    let response = runtime.block_on(client.describe_log_streams(req))?;
+
+    Ok(response.log_streams.unwrap_or_default())
+}
+
+/// Runs `FilterLogEvents` for the query, from `start` (milliseconds since
+/// epoch) until now. When there are more than `MAX_EVENTS` events, the range
+/// is narrowed down to the newest ones, leaving out older events.
+pub fn filter_log_events(
+    region: &Region,
+    profile: &str,
+    query: &EventQuery,
+    start: i64,
+) -> Result<Vec<FilteredLogEvent>, Box<dyn Error>> {
+    let client = new_logs_client(region, profile)?;
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let now = Utc::now().timestamp_millis();
+    let mut start = start;
+    let mut steps = 0;
+
+    loop {
+        let (events, complete) = filter_log_events_from(&mut runtime, &client, query, start)?;
+
+        if complete || steps == NARROWING_STEPS {
+            return Ok(events);
+        }
+
+        start = narrowed_start(start, now);
+        steps += 1;
+    }
+}
+
+/// Returns the start of the newest quarter of `start..now`.
+fn narrowed_start(start: i64, now: i64) -> i64 {
+    now - (now - start) / 4
+}
+
+/// Returns the events from `start`, at most `MAX_EVENTS` of them, and
+/// whether these are all events.
+fn filter_log_events_from(
+    runtime: &mut tokio::runtime::Runtime,
+    client: &CloudWatchLogsClient,
+    query: &EventQuery,
+    start: i64,
+) -> Result<(Vec<FilteredLogEvent>, bool), Box<dyn Error>> {
+    let mut events = vec![];
+    let mut next_token = None;
+
+    loop {
+        let req = FilterLogEventsRequest {
+            log_group_name: query.group.clone(),
+            log_stream_names: query.stream.clone().map(|stream| vec![stream]),
+            filter_pattern: if query.pattern.is_empty() {
+                None
+            } else {
+                Some(query.pattern.clone())
+            },
+            start_time: Some(start),
+            next_token: next_token.clone(),
+            ..Default::default()
+        };
+
+        let response = runtime.block_on(client.filter_log_events(req))?;
+
+        events.extend(response.events.unwrap_or_default());
+
+        next_token = response.next_token;
+        if next_token.is_none() {
+            return Ok((events, true));
+        }
+
+        if events.len() >= MAX_EVENTS {
+            return Ok((events, false));
+        }
+    }
+}
+
+pub fn format_timestamp(millis: i64) -> String {
+    Local
+        .timestamp_millis(millis)
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string()
+}
+
+/// Formats an event as a line for the `LogView`, prefixed with its local
+/// timestamp. Multi-line messages are split.
+pub fn format_event(event: &FilteredLogEvent) -> Vec<String> {
+    let timestamp = format_timestamp(event.timestamp.unwrap_or(0));
+
+    event
+        .message
+        .clone()
+        .unwrap_or_default()
+        .trim_end()
+        .lines()
+        .map(|line| format!("{} {}", timestamp, line))
+        .collect()
+}
+
+/// Where reading a log left off: the newest timestamp, and the ids of the
+/// events with that timestamp as more events can arrive in the same
+/// millisecond.
+#[derive(Clone, Debug, Default)]
+pub struct LogCursor {
+    pub last: i64,
+    seen: Vec<String>,
+}
+
+impl LogCursor {
+    pub fn new(start: i64) -> Self {
+        LogCursor {
+            last: start,
+            seen: vec![],
+        }
+    }
+
+    /// Drops the events read already, retrieved again when polling from
+    /// `last`, and moves past the others.
+    pub fn advance(&mut self, events: Vec<FilteredLogEvent>) -> Vec<FilteredLogEvent> {
+        let events: Vec<FilteredLogEvent> = events
+            .into_iter()
+            .filter(|event| match &event.event_id {
+                Some(id) => !self.seen.contains(id),
+                None => true,
+            })
+            .collect();
+
+        for event in events.iter() {
+            let timestamp = event.timestamp.unwrap_or(self.last);
+
+            if timestamp > self.last {
+                self.last = timestamp;
+                self.seen.clear();
+            }
+
+            if timestamp == self.last {
+                if let Some(id) = &event.event_id {
+                    self.seen.push(id.clone());
+                }
+            }
+        }
+
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(id: &str, timestamp: i64) -> FilteredLogEvent {
+        FilteredLogEvent {
+            event_id: Some(id.to_string()),
+            timestamp: Some(timestamp),
+            message: Some(id.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn ids(events: &[FilteredLogEvent]) -> Vec<&str> {
+        events
+            .iter()
+            .map(|e| e.event_id.as_deref().unwrap_or(""))
+            .collect()
+    }
+
+    #[test]
+    fn cursor_keeps_position() {
+        let mut cursor = LogCursor::new(100);
+
+        let events = cursor.advance(vec![event("a", 110), event("b", 120)]);
+        assert_eq!(ids(&events), vec!["a", "b"]);
+        assert_eq!(cursor.last, 120);
+
+        // nothing new leaves the position as it is
+        assert!(cursor.advance(vec![]).is_empty());
+        assert_eq!(cursor.last, 120);
+    }
+
+    #[test]
+    fn cursor_skips_events_by_id() {
+        let mut cursor = LogCursor::new(100);
+        cursor.advance(vec![event("a", 110), event("b", 120)]);
+
+        // polling from `last` returns b again, c arrived in the same
+        // millisecond
+        let events = cursor.advance(vec![event("b", 120), event("c", 120)]);
+        assert_eq!(ids(&events), vec!["c"]);
+        assert_eq!(cursor.last, 120);
+
+        let events = cursor.advance(vec![event("b", 120), event("c", 120), event("d", 130)]);
+        assert_eq!(ids(&events), vec!["d"]);
+        assert_eq!(cursor.last, 130);
+
+        // only the ids at the last timestamp are kept
+        assert_eq!(cursor.seen, vec!["d"]);
+    }
+
+    #[test]
+    fn cursor_keeps_events_without_id() {
+        let mut cursor = LogCursor::new(100);
+        let anonymous = FilteredLogEvent {
+            timestamp: Some(110),
+            ..Default::default()
+        };
+
+        assert_eq!(cursor.advance(vec![anonymous.clone()]).len(), 1);
+        assert_eq!(cursor.advance(vec![anonymous]).len(), 1);
+        assert_eq!(cursor.last, 110);
+    }
+
+    #[test]
+    fn narrowing() {
+        assert_eq!(narrowed_start(0, 400), 300);
+        assert_eq!(narrowed_start(300, 400), 375);
+        assert_eq!(narrowed_start(400, 400), 400);
+    }
+}
@@ -0,0 +1,505 @@
+use chrono::{DateTime, Duration, Utc};
+use rusoto_cloudwatch::{
+    CloudWatch, CloudWatchClient, Dimension, GetMetricDataInput, Metric as CloudWatchMetric,
+    MetricDataQuery, MetricStat,
+};
+use rusoto_core::request::HttpClient;
+use rusoto_core::Region;
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::thread;
+use std::time::Instant;
+
+use crate::aws::profile_provider;
+use crate::logs::TimeRange;
+use crate::views::DetailLine;
+
+/// GetMetricData accepts at most 500 queries per request.
+const MAX_QUERIES: usize = 500;
+
+/// Pause between requests, to stay well below the GetMetricData rate limit.
+const REQUEST_INTERVAL: std::time::Duration = std::time::Duration::from_millis(250);
+
+const BLOCKS: &[char] = &['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// The time ranges metrics can be shown for.
+pub const RANGES: &[TimeRange] = &[
+    TimeRange::Hour1,
+    TimeRange::Hours6,
+    TimeRange::Hours24,
+    TimeRange::Days7,
+];
+
+/// Returns the range after `range` in `RANGES`.
+pub fn next_range(range: TimeRange) -> TimeRange {
+    match RANGES.iter().position(|r| *r == range) {
+        Some(idx) => RANGES[(idx + 1) % RANGES.len()],
+        None => RANGES[0],
+    }
+}
+
+/// Returns the period in seconds of the datapoints, chosen to get up to
+/// about 60 datapoints. Instances without detailed monitoring report every
+/// 5 minutes.
+pub fn period(range: TimeRange) -> i64 {
+    match range {
+        TimeRange::Minutes15 => 60,
+        TimeRange::Hour1 | TimeRange::Hours6 => 300,
+        TimeRange::Hours24 => 1800,
+        TimeRange::Days7 => 3 * 3600,
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Metric {
+    Cpu,
+    NetworkIn,
+    NetworkOut,
+    DiskRead,
+    DiskWrite,
+}
+
+impl Metric {
+    pub fn all() -> Vec<Metric> {
+        vec![
+            Metric::Cpu,
+            Metric::NetworkIn,
+            Metric::NetworkOut,
+            Metric::DiskRead,
+            Metric::DiskWrite,
+        ]
+    }
+
+    /// The name of the metric in the AWS/EC2 namespace.
+    fn name(&self) -> &'static str {
+        match self {
+            Metric::Cpu => "CPUUtilization",
+            Metric::NetworkIn => "NetworkIn",
+            Metric::NetworkOut => "NetworkOut",
+            Metric::DiskRead => "EBSReadBytes",
+            Metric::DiskWrite => "EBSWriteBytes",
+        }
+    }
+
+    fn stat(&self) -> &'static str {
+        match self {
+            Metric::Cpu => "Average",
+            _ => "Sum",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Metric::Cpu => "cpu",
+            Metric::NetworkIn => "network in",
+            Metric::NetworkOut => "network out",
+            Metric::DiskRead => "disk read",
+            Metric::DiskWrite => "disk write",
+        }
+    }
+}
+
+/// What is shown in a column of the instances view, combining metrics.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Meter {
+    Cpu,
+    Network,
+    Disk,
+}
+
+impl Meter {
+    fn metrics(&self) -> Vec<Metric> {
+        match self {
+            Meter::Cpu => vec![Metric::Cpu],
+            Meter::Network => vec![Metric::NetworkIn, Metric::NetworkOut],
+            Meter::Disk => vec![Metric::DiskRead, Metric::DiskWrite],
+        }
+    }
+}
+
+/// The datapoints of the metrics of an instance, oldest first, as seconds
+/// since epoch and value.
+#[derive(Clone, Debug)]
+pub struct InstanceMetrics {
+    pub range: TimeRange,
+    pub period: i64,
+    pub fetched: Instant,
+    series: HashMap<Metric, Vec<(i64, f64)>>,
+}
+
+impl InstanceMetrics {
+    pub fn new(range: TimeRange) -> Self {
+        InstanceMetrics {
+            range,
+            period: period(range),
+            fetched: Instant::now(),
+            series: HashMap::new(),
+        }
+    }
+
+    fn datapoints(&self, metric: Metric) -> &[(i64, f64)] {
+        self.series
+            .get(&metric)
+            .map(|datapoints| datapoints.as_slice())
+            .unwrap_or(&[])
+    }
+
+    pub fn values(&self, metric: Metric) -> Vec<f64> {
+        self.datapoints(metric)
+            .iter()
+            .map(|(_, value)| *value)
+            .collect()
+    }
+
+    /// Returns the sum of the metrics of `meter` per timestamp, for the
+    /// timestamps all of them have a datapoint for. CloudWatch leaves out
+    /// missing datapoints, so the series can differ in length.
+    pub fn meter_values(&self, meter: Meter) -> Vec<f64> {
+        let metrics = meter.metrics();
+
+        let mut sums: BTreeMap<i64, (usize, f64)> = BTreeMap::new();
+        for metric in metrics.iter() {
+            for (timestamp, value) in self.datapoints(*metric) {
+                let sum = sums.entry(*timestamp).or_default();
+                sum.0 += 1;
+                sum.1 += value;
+            }
+        }
+
+        sums.into_iter()
+            .filter(|(_, (count, _))| *count == metrics.len())
+            .map(|(_, (_, sum))| sum)
+            .collect()
+    }
+
+    /// Returns the most recent value of `meter`, per second for byte counts.
+    pub fn latest(&self, meter: Meter) -> Option<f64> {
+        let value = *self.meter_values(meter).last()?;
+
+        match meter {
+            Meter::Cpu => Some(value),
+            _ => Some(value / self.period as f64),
+        }
+    }
+
+    /// Returns a sparkline of `meter` followed by its most recent value.
+    pub fn meter(&self, meter: Meter, width: usize) -> String {
+        let values = self.meter_values(meter);
+        let max = match meter {
+            Meter::Cpu => Some(100.0),
+            _ => None,
+        };
+
+        match self.latest(meter) {
+            Some(latest) => format!(
+                "{} {}",
+                sparkline(&values, width, max),
+                format_value(meter, latest)
+            ),
+            None => "".to_string(),
+        }
+    }
+}
+
+fn format_value(meter: Meter, value: f64) -> String {
+    match meter {
+        Meter::Cpu => format!("{:.0}%", value),
+        _ => format!("{}/s", format_bytes(value)),
+    }
+}
+
+fn format_bytes(value: f64) -> String {
+    let units = ["B", "K", "M", "G", "T"];
+
+    let mut value = value;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < units.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 || value >= 10.0 {
+        format!("{:.0}{}", value, units[unit])
+    } else {
+        format!("{:.1}{}", value, units[unit])
+    }
+}
+
+/// Renders `values` as a line of `width` block characters, averaging
+/// neighbouring values when there are more values than characters. Values
+/// are scaled to `max`, or to the largest value when `None`.
+pub fn sparkline(values: &[f64], width: usize, max: Option<f64>) -> String {
+    if values.is_empty() || width == 0 {
+        return "".to_string();
+    }
+
+    let buckets: Vec<f64> = if values.len() > width {
+        (0..width)
+            .map(|i| {
+                let start = i * values.len() / width;
+                let end = (i + 1) * values.len() / width;
+                let bucket = &values[start..end.max(start + 1)];
+                bucket.iter().sum::<f64>() / bucket.len() as f64
+            })
+            .collect()
+    } else {
+        values.to_vec()
+    };
+
+    let max = max.unwrap_or_else(|| buckets.iter().cloned().fold(0.0, f64::max));
+
+    buckets
+        .iter()
+        .map(|value| {
+            if max <= 0.0 {
+                return BLOCKS[0];
+            }
+
+            let level = (value / max * (BLOCKS.len() - 1) as f64).round() as usize;
+            BLOCKS[level.min(BLOCKS.len() - 1)]
+        })
+        .collect()
+}
+
+pub fn new_cloudwatch_client(
+    region: &Region,
+    profile: &str,
+) -> Result<CloudWatchClient, rusoto_core::request::TlsError> {
+    let http_client = HttpClient::new()?;
+
+    let client = CloudWatchClient::new_with(http_client, profile_provider(profile), region.clone());
+
+    Ok(client)
+}
+
+fn query(id: String, instance_id: &str, metric: Metric, period: i64) -> MetricDataQuery {
+    MetricDataQuery {
+        id,
+        metric_stat: Some(MetricStat {
+            metric: CloudWatchMetric {
+                namespace: Some("AWS/EC2".to_string()),
+                metric_name: Some(metric.name().to_string()),
+                dimensions: Some(vec![Dimension {
+                    name: "InstanceId".to_string(),
+                    value: instance_id.to_string(),
+                }]),
+            },
+            period,
+            stat: metric.stat().to_string(),
+            unit: None,
+        }),
+        ..Default::default()
+    }
+}
+
+/// Returns the metrics per instance id over `range`. The instances are
+/// queried in batches, pausing between the requests.
+pub fn get_metrics(
+    region: &Region,
+    profile: &str,
+    instance_ids: &[String],
+    range: TimeRange,
+) -> Result<HashMap<String, InstanceMetrics>, Box<dyn Error>> {
+    let client = new_cloudwatch_client(region, profile)?;
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let metrics = Metric::all();
+    let period = period(range);
+
+    let end = Utc::now();
+    let start = end - Duration::milliseconds(range.millis());
+
+    let mut result = HashMap::new();
+
+    for (n, batch) in instance_ids.chunks(MAX_QUERIES / metrics.len()).enumerate() {
+        if n > 0 {
+            thread::sleep(REQUEST_INTERVAL);
+        }
+
+        // query ids have to start with a lowercase letter
+        let queries: Vec<MetricDataQuery> = batch
+            .iter()
+            .enumerate()
+            .flat_map(|(i, instance_id)| {
+                metrics.iter().enumerate().map(move |(j, metric)| {
+                    query(format!("m{}_{}", i, j), instance_id, *metric, period)
+                })
+            })
+            .collect();
+
+        let mut series: HashMap<String, Vec<(i64, f64)>> = HashMap::new();
+        let mut next_token = None;
+
+        loop {
+            let req = GetMetricDataInput {
+                start_time: start.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+                end_time: end.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+                metric_data_queries: queries.clone(),
+                next_token: next_token.clone(),
+                scan_by: Some("TimestampAscending".to_string()),
+                ..Default::default()
+            };
+
+            let response = runtime.block_on(client.get_metric_data(req))?;
+
+            for data in response.metric_data_results.unwrap_or_default() {
+                if let Some(id) = data.id {
+                    let timestamps = data.timestamps.unwrap_or_default();
+                    let values = data.values.unwrap_or_default();
+
+                    series
+                        .entry(id)
+                        .or_default()
+                        .extend(
+                            timestamps
+                                .iter()
+                                .zip(values)
+                                .filter_map(|(timestamp, value)| {
+                                    let timestamp = DateTime::parse_from_rfc3339(timestamp).ok()?;
+                                    Some((timestamp.timestamp(), value))
+                                }),
+                        );
+                }
+            }
+
+            next_token = response.next_token;
+            if next_token.is_none() {
+                break;
+            }
+
+            thread::sleep(REQUEST_INTERVAL);
+        }
+
+        for (i, instance_id) in batch.iter().enumerate() {
+            let mut instance_metrics = InstanceMetrics::new(range);
+
+            for (j, metric) in metrics.iter().enumerate() {
+                let values = series.remove(&format!("m{}_{}", i, j)).unwrap_or_default();
+                instance_metrics.series.insert(*metric, values);
+            }
+
+            result.insert(instance_id.clone(), instance_metrics);
+        }
+    }
+
+    Ok(result)
+}
+
+/// Returns a wide sparkline with the minimum, average and maximum per
+/// metric, for the metrics tab of the details screen.
+pub fn metric_lines(metrics: &InstanceMetrics) -> Vec<DetailLine> {
+    let mut lines = vec![DetailLine::section(&format!(
+        "last {}, every {} minutes",
+        metrics.range.name(),
+        metrics.period / 60
+    ))];
+
+    for metric in Metric::all() {
+        let values = metrics.values(metric);
+
+        if values.is_empty() {
+            lines.push(DetailLine::field(metric.label(), "no data"));
+            continue;
+        }
+
+        let (scale, meter) = match metric {
+            Metric::Cpu => (Some(100.0), Meter::Cpu),
+            Metric::NetworkIn | Metric::NetworkOut => (None, Meter::Network),
+            Metric::DiskRead | Metric::DiskWrite => (None, Meter::Disk),
+        };
+
+        let show = |value: f64| match meter {
+            Meter::Cpu => format_value(meter, value),
+            _ => format_value(meter, value / metrics.period as f64),
+        };
+
+        let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
+        let max = values.iter().cloned().fold(0.0, f64::max);
+        let avg = values.iter().sum::<f64>() / values.len() as f64;
+
+        lines.push(DetailLine::field(
+            metric.label(),
+            format!(
+                "{}  min {}  avg {}  max {}",
+                sparkline(&values, 60, scale),
+                show(min),
+                show(avg),
+                show(max)
+            ),
+        ));
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics(series: &[(Metric, &[(i64, f64)])]) -> InstanceMetrics {
+        let mut metrics = InstanceMetrics::new(TimeRange::Hour1);
+
+        for (metric, datapoints) in series {
+            metrics.series.insert(*metric, datapoints.to_vec());
+        }
+
+        metrics
+    }
+
+    #[test]
+    fn meter_values_by_timestamp() {
+        // network out misses the datapoint at 300
+        let metrics = metrics(&[
+            (Metric::NetworkIn, &[(0, 1.0), (300, 2.0), (600, 3.0)]),
+            (Metric::NetworkOut, &[(0, 10.0), (600, 30.0)]),
+        ]);
+
+        assert_eq!(metrics.meter_values(Meter::Network), vec![11.0, 33.0]);
+        assert!(metrics.meter_values(Meter::Disk).is_empty());
+    }
+
+    #[test]
+    fn latest_per_second() {
+        let metrics = metrics(&[
+            (Metric::Cpu, &[(0, 20.0), (300, 40.0)]),
+            (Metric::DiskRead, &[(0, 0.0), (300, 3000.0)]),
+            (Metric::DiskWrite, &[(0, 0.0), (300, 600.0)]),
+        ]);
+
+        assert_eq!(metrics.latest(Meter::Cpu), Some(40.0));
+        // 3600 bytes over a period of 300 seconds
+        assert_eq!(metrics.latest(Meter::Disk), Some(12.0));
+        assert_eq!(metrics.latest(Meter::Network), None);
+        assert_eq!(metrics.meter(Meter::Network, 10), "");
+    }
+
+    #[test]
+    fn bytes() {
+        assert_eq!(format_bytes(0.0), "0B");
+        assert_eq!(format_bytes(1023.0), "1023B");
+        assert_eq!(format_bytes(1536.0), "1.5K");
+        assert_eq!(format_bytes(20.0 * 1024.0), "20K");
+        assert_eq!(format_bytes(3.0 * 1024.0 * 1024.0 * 1024.0), "3.0G");
+        assert_eq!(format_bytes(2048.0 * 1024.0_f64.powi(4)), "2048T");
+    }
+
+    #[test]
+    fn sparklines() {
+        assert_eq!(sparkline(&[], 10, None), "");
+        assert_eq!(sparkline(&[1.0], 0, None), "");
+        assert_eq!(sparkline(&[0.0, 50.0, 100.0], 10, Some(100.0)), "▁▅█");
+        assert_eq!(sparkline(&[0.0, 0.0], 10, None), "▁▁");
+        // values above max are capped
+        assert_eq!(sparkline(&[200.0], 10, Some(100.0)), "█");
+        // four values averaged into two characters
+        assert_eq!(sparkline(&[0.0, 0.0, 7.0, 7.0], 2, None), "▁█");
+    }
+
+    #[test]
+    fn ranges() {
+        assert_eq!(next_range(TimeRange::Hour1), TimeRange::Hours6);
+        assert_eq!(next_range(TimeRange::Days7), TimeRange::Hour1);
+        assert_eq!(next_range(TimeRange::Minutes15), TimeRange::Hour1);
+    }
+}
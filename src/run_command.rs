@@ -0,0 +1,520 @@
+use cursive::theme::{BaseColor, Color, ColorStyle};
+use rusoto_core::request::HttpClient;
+use rusoto_core::{Region, RusotoError};
+use rusoto_ec2::Instance;
+use rusoto_ssm::{
+    GetCommandInvocationError, GetCommandInvocationRequest, SendCommandRequest, Ssm, SsmClient,
+};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::error::Error;
+
+use crate::aws::profile_provider;
+use crate::launch::name_tag;
+use crate::views::{Header, TableViewItem};
+
+pub const SHELL_SCRIPT_DOCUMENT: &str = "AWS-RunShellScript";
+pub const POWERSHELL_SCRIPT_DOCUMENT: &str = "AWS-RunPowerShellScript";
+
+/// Seconds an instance has to pick up the command.
+const DELIVERY_TIMEOUT: i64 = 600;
+
+/// Most instances a single `SendCommand` call accepts.
+pub const MAX_COMMAND_INSTANCES: usize = 50;
+
+/// Returns the run document for the platform of an instance, as in
+/// `Instance::platform`.
+pub fn document_for(platform: Option<&str>) -> &'static str {
+    match platform {
+        Some("windows") => POWERSHELL_SCRIPT_DOCUMENT,
+        _ => SHELL_SCRIPT_DOCUMENT,
+    }
+}
+
+/// Groups the instances by their run document, as windows and linux
+/// instances need different documents, in batches of at most
+/// `MAX_COMMAND_INSTANCES`.
+pub fn command_batches(instances: &[Instance]) -> Vec<(&'static str, Vec<&Instance>)> {
+    let mut groups: Vec<(&str, Vec<&Instance>)> = vec![];
+    for instance in instances {
+        let document = document_for(instance.platform.as_deref());
+        match groups.iter_mut().find(|(d, _)| *d == document) {
+            Some((_, group)) => group.push(instance),
+            None => groups.push((document, vec![instance])),
+        }
+    }
+
+    groups
+        .into_iter()
+        .flat_map(|(document, group)| {
+            group
+                .chunks(MAX_COMMAND_INSTANCES)
+                .map(|chunk| (document, chunk.to_vec()))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Sends the batches with `send`, which gets the document and the instance
+/// ids and returns the command id, up to the first batch that fails.
+/// Returns the invocations of the instances that got the command and the
+/// error, if any.
+pub fn send_batches<F>(
+    batches: &[(&str, Vec<&Instance>)],
+    mut send: F,
+) -> (Vec<Invocation>, Option<String>)
+where
+    F: FnMut(&str, &[String]) -> Result<String, Box<dyn Error>>,
+{
+    let mut invocations = vec![];
+
+    for (document, batch) in batches {
+        let ids: Vec<String> = batch
+            .iter()
+            .map(|i| i.instance_id.clone().unwrap_or_default())
+            .collect();
+
+        match send(document, &ids) {
+            Ok(command_id) => invocations.extend(batch.iter().zip(&ids).map(|(i, id)| {
+                Invocation::new(&command_id, id, &name_tag(&i.tags).unwrap_or_default())
+            })),
+            Err(err) => return (invocations, Some(err.to_string())),
+        }
+    }
+
+    (invocations, None)
+}
+
+pub fn new_ssm_client(
+    region: &Region,
+    profile: &str,
+) -> Result<SsmClient, rusoto_core::request::TlsError> {
+    let http_client = HttpClient::new()?;
+
+    let client = SsmClient::new_with(http_client, profile_provider(profile), region.clone());
+
+    Ok(client)
+}
+
+/// Sends `script` to the instances, returning the command id. At most
+/// `MAX_COMMAND_INSTANCES` instances can be given.
+pub fn send_command(
+    region: &Region,
+    profile: &str,
+    document: &str,
+    instance_ids: &[String],
+    script: &str,
+) -> Result<String, Box<dyn Error>> {
+    let client = new_ssm_client(region, profile)?;
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let mut parameters = HashMap::new();
+    parameters.insert(
+        "commands".to_string(),
+        script.lines().map(String::from).collect(),
+    );
+
+    let req = SendCommandRequest {
+        document_name: document.to_string(),
+        instance_ids: Some(instance_ids.to_vec()),
+        parameters: Some(parameters),
+        comment: Some("cloudman".to_string()),
+        timeout_seconds: Some(DELIVERY_TIMEOUT),
+        ..Default::default()
+    };
+
+    let response = runtime.block_on(client.send_command(req))?;
+
+    let command_id = response
+        .command
+        .and_then(|command| command.command_id)
+        .ok_or("The command was not accepted.")?;
+
+    Ok(command_id)
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub enum InvocationColumn {
+    InstanceID,
+    Name,
+    Status,
+    ExitCode,
+    Output,
+}
+
+impl Header for InvocationColumn {
+    fn to_header(&self) -> String {
+        match self {
+            InvocationColumn::InstanceID => "instance-id".to_string(),
+            InvocationColumn::Name => "name".to_string(),
+            InvocationColumn::Status => "status".to_string(),
+            InvocationColumn::ExitCode => "exit".to_string(),
+            InvocationColumn::Output => "output".to_string(),
+        }
+    }
+
+    fn to_header_size(&self, w: usize) -> usize {
+        match self {
+            InvocationColumn::InstanceID => 19,
+            InvocationColumn::Name => (30 * w) / 160,
+            InvocationColumn::Status => 12,
+            InvocationColumn::ExitCode => 5,
+            InvocationColumn::Output => (80 * w) / 160,
+        }
+    }
+}
+
+/// The result of a command on a single instance.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Invocation {
+    pub command_id: String,
+    pub instance_id: String,
+    pub name: String,
+    pub status: String,
+    pub exit_code: Option<i64>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl Invocation {
+    pub fn new(command_id: &str, instance_id: &str, name: &str) -> Self {
+        Invocation {
+            command_id: command_id.to_string(),
+            instance_id: instance_id.to_string(),
+            name: name.to_string(),
+            status: "Pending".to_string(),
+            exit_code: None,
+            stdout: "".to_string(),
+            stderr: "".to_string(),
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        !matches!(
+            self.status.as_str(),
+            "Pending" | "InProgress" | "Delayed" | "Cancelling"
+        )
+    }
+
+    /// Returns the output as lines for the `LogView`.
+    pub fn output_lines(&self) -> Vec<String> {
+        let mut lines = vec![format!(
+            "{} {}: {} (exit code {})",
+            self.instance_id,
+            self.name,
+            self.status,
+            self.exit_code
+                .map(|code| code.to_string())
+                .unwrap_or_else(|| "-".to_string())
+        )];
+
+        lines.push("".to_string());
+        lines.extend(self.stdout.lines().map(String::from));
+
+        if !self.stderr.is_empty() {
+            lines.push("".to_string());
+            lines.push("-- stderr --".to_string());
+            lines.extend(self.stderr.lines().map(String::from));
+        }
+
+        lines
+    }
+}
+
+impl TableViewItem<InvocationColumn> for Invocation {
+    fn to_column(&self, column: InvocationColumn) -> String {
+        match column {
+            InvocationColumn::InstanceID => self.instance_id.clone(),
+            InvocationColumn::Name => self.name.clone(),
+            InvocationColumn::Status => self.status.clone(),
+            InvocationColumn::ExitCode => self
+                .exit_code
+                .map(|code| code.to_string())
+                .unwrap_or_default(),
+            InvocationColumn::Output => {
+                // the first line of output is a good hint of the result
+                let output = if self.stdout.trim().is_empty() {
+                    &self.stderr
+                } else {
+                    &self.stdout
+                };
+
+                output.trim().lines().next().unwrap_or("").to_string()
+            }
+        }
+    }
+
+    fn to_column_color(&self, column: InvocationColumn) -> ColorStyle {
+        match column {
+            InvocationColumn::Status | InvocationColumn::ExitCode => match self.status.as_str() {
+                "Success" => {
+                    ColorStyle::new(Color::Light(BaseColor::Green), Color::TerminalDefault)
+                }
+                _ if self.is_done() => {
+                    ColorStyle::new(Color::Light(BaseColor::Red), Color::TerminalDefault)
+                }
+                _ => ColorStyle::primary(),
+            },
+            _ => ColorStyle::primary(),
+        }
+    }
+
+    fn cmp(&self, other: &Self, column: InvocationColumn) -> Ordering {
+        match column {
+            InvocationColumn::ExitCode => self.exit_code.cmp(&other.exit_code),
+            _ => self.to_column(column).cmp(&other.to_column(column)),
+        }
+    }
+}
+
+/// Returns `invocation` updated with its current status and output.
+pub fn get_command_invocation(
+    region: &Region,
+    profile: &str,
+    invocation: &Invocation,
+) -> Result<Invocation, Box<dyn Error>> {
+    let client = new_ssm_client(region, profile)?;
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let req = GetCommandInvocationRequest {
+        command_id: invocation.command_id.clone(),
+        instance_id: invocation.instance_id.clone(),
+        ..Default::default()
+    };
+
+    let response = match runtime.block_on(client.get_command_invocation(req)) {
+        Ok(response) => response,
+        // right after sending the invocation is not known yet
+        Err(RusotoError::Service(GetCommandInvocationError::InvocationDoesNotExist(_))) => {
+            return Ok(invocation.clone())
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    Ok(Invocation {
+        status: response.status.unwrap_or_default(),
+        // -1 while the command is still running
+        exit_code: response.response_code.filter(|code| *code >= 0),
+        stdout: response.standard_output_content.unwrap_or_default(),
+        stderr: response.standard_error_content.unwrap_or_default(),
+        ..invocation.clone()
+    })
+}
+
+/// Updates the invocations that are not done yet with `get`, returning
+/// whether all of them are done.
+pub fn update_invocations<F>(invocations: &mut [Invocation], mut get: F) -> bool
+where
+    F: FnMut(&Invocation) -> Result<Invocation, Box<dyn Error>>,
+{
+    for invocation in invocations.iter_mut().filter(|i| !i.is_done()) {
+        match get(invocation) {
+            Ok(updated) => *invocation = updated,
+            // shown until the next poll succeeds, the command may well
+            // still be running
+            Err(err) => invocation.stderr = err.to_string(),
+        }
+    }
+
+    invocations.iter().all(|i| i.is_done())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusoto_ec2::Tag;
+
+    fn instance(id: &str, platform: Option<&str>) -> Instance {
+        Instance {
+            instance_id: Some(id.to_string()),
+            platform: platform.map(String::from),
+            tags: Some(vec![Tag {
+                key: Some("Name".to_string()),
+                value: Some(format!("name-{}", id)),
+            }]),
+            ..Default::default()
+        }
+    }
+
+    fn invocation(status: &str) -> Invocation {
+        Invocation {
+            status: status.to_string(),
+            ..Invocation::new("c-1", "i-1", "web")
+        }
+    }
+
+    fn ids(batch: &[&Instance]) -> Vec<String> {
+        batch
+            .iter()
+            .map(|i| i.instance_id.clone().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn batches_by_document() {
+        let instances = vec![
+            instance("i-1", None),
+            instance("i-2", Some("windows")),
+            instance("i-3", None),
+        ];
+
+        let batches = command_batches(&instances);
+
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[0].0, SHELL_SCRIPT_DOCUMENT);
+        assert_eq!(ids(&batches[0].1), vec!["i-1", "i-3"]);
+        assert_eq!(batches[1].0, POWERSHELL_SCRIPT_DOCUMENT);
+        assert_eq!(ids(&batches[1].1), vec!["i-2"]);
+    }
+
+    #[test]
+    fn batches_of_max_instances() {
+        let instances: Vec<Instance> = (0..MAX_COMMAND_INSTANCES * 2 + 1)
+            .map(|n| instance(&format!("i-{}", n), None))
+            .collect();
+
+        let sizes: Vec<usize> = command_batches(&instances)
+            .iter()
+            .map(|(_, batch)| batch.len())
+            .collect();
+
+        assert_eq!(sizes, vec![MAX_COMMAND_INSTANCES, MAX_COMMAND_INSTANCES, 1]);
+    }
+
+    #[test]
+    fn sends_all_batches() {
+        let instances = vec![instance("i-1", None), instance("i-2", Some("windows"))];
+        let batches = command_batches(&instances);
+
+        let mut sent = vec![];
+        let (invocations, failed) = send_batches(&batches, |document, ids| {
+            sent.push((document.to_string(), ids.to_vec()));
+            Ok(format!("c-{}", sent.len()))
+        });
+
+        assert_eq!(failed, None);
+        assert_eq!(
+            sent,
+            vec![
+                (SHELL_SCRIPT_DOCUMENT.to_string(), vec!["i-1".to_string()]),
+                (
+                    POWERSHELL_SCRIPT_DOCUMENT.to_string(),
+                    vec!["i-2".to_string()]
+                ),
+            ]
+        );
+        assert_eq!(
+            invocations,
+            vec![
+                Invocation::new("c-1", "i-1", "name-i-1"),
+                Invocation::new("c-2", "i-2", "name-i-2"),
+            ]
+        );
+    }
+
+    #[test]
+    fn stops_at_failed_batch() {
+        let instances = vec![
+            instance("i-1", None),
+            instance("i-2", Some("windows")),
+            instance("i-3", Some("windows")),
+        ];
+        let batches = command_batches(&instances);
+
+        let mut calls = 0;
+        let (invocations, failed) = send_batches(&batches, |_, _| {
+            calls += 1;
+            match calls {
+                1 => Ok("c-1".to_string()),
+                _ => Err("throttled".into()),
+            }
+        });
+
+        assert_eq!(calls, 2);
+        assert_eq!(failed, Some("throttled".to_string()));
+        assert_eq!(invocations, vec![Invocation::new("c-1", "i-1", "name-i-1")]);
+    }
+
+    #[test]
+    fn updates_running_invocations() {
+        let mut invocations = vec![invocation("InProgress"), invocation("Failed")];
+
+        let mut polled = 0;
+        let done = update_invocations(&mut invocations, |i| {
+            polled += 1;
+            Ok(Invocation {
+                status: "Success".to_string(),
+                exit_code: Some(0),
+                ..i.clone()
+            })
+        });
+
+        // finished invocations are not polled again
+        assert_eq!(polled, 1);
+        assert!(done);
+        assert_eq!(invocations[0].status, "Success");
+        assert_eq!(invocations[1].status, "Failed");
+    }
+
+    #[test]
+    fn keeps_polling_after_error() {
+        let mut invocations = vec![invocation("InProgress")];
+
+        let done = update_invocations(&mut invocations, |_| Err("timed out".into()));
+
+        assert!(!done);
+        assert_eq!(invocations[0].status, "InProgress");
+        assert_eq!(invocations[0].stderr, "timed out");
+
+        let done = update_invocations(&mut invocations, |i| {
+            Ok(Invocation {
+                status: "Success".to_string(),
+                stderr: "".to_string(),
+                ..i.clone()
+            })
+        });
+
+        assert!(done);
+        assert_eq!(invocations[0].stderr, "");
+    }
+
+    #[test]
+    fn done() {
+        for status in &["Pending", "InProgress", "Delayed", "Cancelling"] {
+            assert!(!invocation(status).is_done(), "{}", status);
+        }
+        for status in &["Success", "Failed", "Cancelled", "TimedOut", ""] {
+            assert!(invocation(status).is_done(), "{}", status);
+        }
+    }
+
+    #[test]
+    fn output() {
+        let running = invocation("InProgress");
+        assert_eq!(
+            running.output_lines(),
+            vec!["i-1 web: InProgress (exit code -)", ""]
+        );
+
+        let failed = Invocation {
+            exit_code: Some(2),
+            stdout: "one\ntwo\n".to_string(),
+            stderr: "oops".to_string(),
+            ..invocation("Failed")
+        };
+        assert_eq!(
+            failed.output_lines(),
+            vec![
+                "i-1 web: Failed (exit code 2)",
+                "",
+                "one",
+                "two",
+                "",
+                "-- stderr --",
+                "oops",
+            ]
+        );
+    }
+}
@@ -0,0 +1,471 @@
+use rusoto_core::Region;
+use rusoto_ec2::{DescribeSecurityGroupsRequest, Ec2, Instance, IpPermission, SecurityGroup};
+use std::error::Error;
+
+use crate::aws::new_ec2client;
+use crate::views::DetailLine;
+
+/// Ports that should never be reachable from anywhere: remote access and
+/// databases.
+pub const RISKY_PORTS: &[(i64, &str)] = &[
+    (22, "ssh"),
+    (23, "telnet"),
+    (445, "smb"),
+    (1433, "mssql"),
+    (1521, "oracle"),
+    (3306, "mysql"),
+    (3389, "rdp"),
+    (5432, "postgresql"),
+    (5984, "couchdb"),
+    (6379, "redis"),
+    (9042, "cassandra"),
+    (9200, "elasticsearch"),
+    (11211, "memcached"),
+    (27017, "mongodb"),
+];
+
+const ANYWHERE: &[&str] = &["0.0.0.0/0", "::/0"];
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Rule {
+    pub inbound: bool,
+    pub protocol: String,
+    pub from_port: Option<i64>,
+    pub to_port: Option<i64>,
+    /// Cidr, prefix list or security group.
+    pub peer: String,
+    pub description: String,
+}
+
+impl Rule {
+    /// Whether the rule is for tcp or udp, by name or protocol number.
+    fn has_ports(&self) -> bool {
+        matches!(self.protocol.as_str(), "tcp" | "udp" | "6" | "17")
+    }
+
+    fn is_icmp(&self) -> bool {
+        matches!(self.protocol.as_str(), "icmp" | "icmpv6" | "1" | "58")
+    }
+
+    fn all_ports(&self) -> bool {
+        self.protocol == "-1"
+            || (self.has_ports() && self.from_port == Some(0) && self.to_port == Some(65535))
+    }
+
+    pub fn is_open_to_world(&self) -> bool {
+        ANYWHERE.contains(&self.peer.as_str())
+    }
+
+    pub fn covers(&self, port: i64) -> bool {
+        if self.all_ports() {
+            return true;
+        }
+
+        match (self.from_port, self.to_port) {
+            (Some(from), Some(to)) if self.has_ports() => from <= port && port <= to,
+            _ => false,
+        }
+    }
+
+    /// Names of the risky ports reachable from anywhere through this rule.
+    pub fn risks(&self) -> Vec<&'static str> {
+        if !self.inbound || !self.is_open_to_world() {
+            return vec![];
+        }
+
+        RISKY_PORTS
+            .iter()
+            .filter(|(port, _)| self.covers(*port))
+            .map(|(_, name)| *name)
+            .collect()
+    }
+
+    pub fn protocol_name(&self) -> String {
+        match self.protocol.as_str() {
+            "-1" => "all".to_string(),
+            "6" => "tcp".to_string(),
+            "17" => "udp".to_string(),
+            "1" => "icmp".to_string(),
+            "58" => "icmpv6".to_string(),
+            protocol => protocol.to_string(),
+        }
+    }
+
+    pub fn ports(&self) -> String {
+        if self.all_ports() {
+            return "all".to_string();
+        }
+
+        // icmp rules hold the type and code instead of ports, -1 for any
+        if self.is_icmp() {
+            return match (self.from_port, self.to_port) {
+                (Some(-1), _) | (None, _) => "any type".to_string(),
+                (Some(kind), Some(-1)) | (Some(kind), None) => format!("type {}", kind),
+                (Some(kind), Some(code)) => format!("type {} code {}", kind, code),
+            };
+        }
+
+        match (self.from_port, self.to_port) {
+            (Some(from), Some(to)) if from == to => from.to_string(),
+            (Some(from), Some(to)) => format!("{}-{}", from, to),
+            (Some(from), None) => from.to_string(),
+            (None, _) => "".to_string(),
+        }
+    }
+}
+
+fn permission_rules(permission: &IpPermission, inbound: bool) -> Vec<Rule> {
+    let rule = |peer: Option<String>, description: &Option<String>| Rule {
+        inbound,
+        protocol: permission.ip_protocol.clone().unwrap_or_default(),
+        from_port: permission.from_port,
+        to_port: permission.to_port,
+        peer: peer.unwrap_or_default(),
+        description: description.clone().unwrap_or_default(),
+    };
+
+    let mut rules = vec![];
+
+    for range in permission.ip_ranges.iter().flatten() {
+        rules.push(rule(range.cidr_ip.clone(), &range.description));
+    }
+
+    for range in permission.ipv_6_ranges.iter().flatten() {
+        rules.push(rule(range.cidr_ipv_6.clone(), &range.description));
+    }
+
+    for prefix_list in permission.prefix_list_ids.iter().flatten() {
+        rules.push(rule(
+            prefix_list.prefix_list_id.clone(),
+            &prefix_list.description,
+        ));
+    }
+
+    for pair in permission.user_id_group_pairs.iter().flatten() {
+        rules.push(rule(pair.group_id.clone(), &pair.description));
+    }
+
+    rules
+}
+
+/// Returns the inbound and outbound rules of a group, one per peer.
+pub fn rules(group: &SecurityGroup) -> Vec<Rule> {
+    let inbound = group
+        .ip_permissions
+        .iter()
+        .flatten()
+        .flat_map(|p| permission_rules(p, true));
+
+    let outbound = group
+        .ip_permissions_egress
+        .iter()
+        .flatten()
+        .flat_map(|p| permission_rules(p, false));
+
+    inbound.chain(outbound).collect()
+}
+
+/// Inbound ports of an instance that are reachable from anywhere.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Exposure {
+    pub ports: Vec<String>,
+    pub risks: Vec<&'static str>,
+}
+
+impl Exposure {
+    pub fn is_open(&self) -> bool {
+        !self.ports.is_empty()
+    }
+
+    pub fn is_risky(&self) -> bool {
+        !self.risks.is_empty()
+    }
+
+    pub fn summary(&self) -> String {
+        self.ports.join(",")
+    }
+}
+
+fn instance_groups<'a>(instance: &Instance, groups: &'a [SecurityGroup]) -> Vec<&'a SecurityGroup> {
+    let ids: Vec<String> = instance
+        .security_groups
+        .iter()
+        .flatten()
+        .filter_map(|g| g.group_id.clone())
+        .collect();
+
+    groups
+        .iter()
+        .filter(|g| g.group_id.as_ref().is_some_and(|id| ids.contains(id)))
+        .collect()
+}
+
+/// Whether the instance has a public address, its own or an elastic ip.
+fn has_public_ip(instance: &Instance) -> bool {
+    let non_empty = |ip: &Option<String>| ip.as_ref().is_some_and(|ip| !ip.is_empty());
+
+    non_empty(&instance.public_ip_address)
+        || instance
+            .network_interfaces
+            .iter()
+            .flatten()
+            .any(|interface| {
+                interface
+                    .association
+                    .as_ref()
+                    .is_some_and(|association| non_empty(&association.public_ip))
+            })
+}
+
+/// Returns the exposure of an instance through its groups, out of `groups`.
+/// Instances without a public address can't be reached from anywhere.
+pub fn exposure(instance: &Instance, groups: &[SecurityGroup]) -> Exposure {
+    let mut exposure = Exposure::default();
+
+    if !has_public_ip(instance) {
+        return exposure;
+    }
+
+    for group in instance_groups(instance, groups) {
+        for rule in rules(group)
+            .into_iter()
+            .filter(|r| r.inbound && r.is_open_to_world())
+        {
+            let ports = rule.ports();
+            if !exposure.ports.contains(&ports) {
+                exposure.ports.push(ports);
+            }
+
+            for risk in rule.risks() {
+                if !exposure.risks.contains(&risk) {
+                    exposure.risks.push(risk);
+                }
+            }
+        }
+    }
+
+    exposure
+}
+
+/// Returns the security groups with the given ids, all groups of the region
+/// when `None`.
+pub fn get_security_groups(
+    region: &Region,
+    profile: &str,
+    group_ids: Option<Vec<String>>,
+) -> Result<Vec<SecurityGroup>, Box<dyn Error>> {
+    let client = new_ec2client(region, profile)?;
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let mut groups = vec![];
+    let mut next_token = None;
+
+    loop {
+        let req = DescribeSecurityGroupsRequest {
+            group_ids: group_ids.clone(),
+            next_token: next_token.clone(),
+            ..Default::default()
+        };
+
+        let response = runtime.block_on(client.describe_security_groups(req))?;
+
+        groups.extend(response.security_groups.unwrap_or_default());
+
+        next_token = response.next_token;
+        if next_token.is_none() {
+            break;
+        }
+    }
+
+    Ok(groups)
+}
+
+fn rule_line(rule: &Rule) -> DetailLine {
+    let label = format!("{} {}", rule.protocol_name(), rule.ports());
+
+    let value = if rule.description.is_empty() {
+        rule.peer.clone()
+    } else {
+        format!("{} ({})", rule.peer, rule.description)
+    };
+
+    let risks = rule.risks();
+    if !risks.is_empty() {
+        DetailLine::Alert(
+            label,
+            format!("{}  open {} to the world", value, risks.join(", ")),
+        )
+    } else if rule.inbound && rule.is_open_to_world() {
+        DetailLine::Warning(label, value)
+    } else {
+        DetailLine::Field(label, value)
+    }
+}
+
+/// The effective rules of the groups of an instance, for the security tab.
+pub fn security_lines(instance: &Instance, groups: &[SecurityGroup]) -> Vec<DetailLine> {
+    let mut lines = vec![];
+
+    for group in instance_groups(instance, groups) {
+        let rules = rules(group);
+
+        lines.push(DetailLine::Blank);
+        lines.push(DetailLine::section(&format!(
+            "{} {} ({})",
+            group.group_id.clone().unwrap_or_default(),
+            group.group_name.clone().unwrap_or_default(),
+            group.description.clone().unwrap_or_default(),
+        )));
+
+        lines.push(DetailLine::section("  inbound"));
+        lines.extend(rules.iter().filter(|r| r.inbound).map(rule_line));
+
+        lines.push(DetailLine::section("  outbound"));
+        lines.extend(rules.iter().filter(|r| !r.inbound).map(rule_line));
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusoto_ec2::{GroupIdentifier, IpRange};
+
+    fn rule(protocol: &str, from_port: i64, to_port: i64, peer: &str) -> Rule {
+        Rule {
+            inbound: true,
+            protocol: protocol.to_string(),
+            from_port: Some(from_port),
+            to_port: Some(to_port),
+            peer: peer.to_string(),
+            description: "".to_string(),
+        }
+    }
+
+    fn permission(protocol: &str, from_port: i64, to_port: i64, cidr: &str) -> IpPermission {
+        IpPermission {
+            ip_protocol: Some(protocol.to_string()),
+            from_port: Some(from_port),
+            to_port: Some(to_port),
+            ip_ranges: Some(vec![IpRange {
+                cidr_ip: Some(cidr.to_string()),
+                description: None,
+            }]),
+            ..Default::default()
+        }
+    }
+
+    fn group(id: &str, permissions: Vec<IpPermission>) -> SecurityGroup {
+        SecurityGroup {
+            group_id: Some(id.to_string()),
+            ip_permissions: Some(permissions),
+            ..Default::default()
+        }
+    }
+
+    fn instance(public_ip: Option<&str>, group_ids: &[&str]) -> Instance {
+        Instance {
+            public_ip_address: public_ip.map(String::from),
+            security_groups: Some(
+                group_ids
+                    .iter()
+                    .map(|id| GroupIdentifier {
+                        group_id: Some(id.to_string()),
+                        group_name: None,
+                    })
+                    .collect(),
+            ),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn risks() {
+        assert_eq!(rule("tcp", 22, 22, "0.0.0.0/0").risks(), vec!["ssh"]);
+        assert_eq!(rule("6", 3306, 3306, "::/0").risks(), vec!["mysql"]);
+        assert_eq!(
+            rule("tcp", 5000, 6500, "0.0.0.0/0").risks(),
+            vec!["postgresql", "couchdb", "redis"]
+        );
+        assert_eq!(
+            rule("-1", -1, -1, "0.0.0.0/0").risks().len(),
+            RISKY_PORTS.len()
+        );
+
+        // icmp has no ports, whatever the type and code
+        assert!(rule("icmp", -1, -1, "0.0.0.0/0").risks().is_empty());
+        assert!(rule("1", 0, 65535, "0.0.0.0/0").risks().is_empty());
+
+        assert!(rule("tcp", 22, 22, "10.0.0.0/8").risks().is_empty());
+        assert!(rule("tcp", 443, 443, "0.0.0.0/0").risks().is_empty());
+
+        let mut outbound = rule("tcp", 0, 65535, "0.0.0.0/0");
+        outbound.inbound = false;
+        assert!(outbound.risks().is_empty());
+    }
+
+    #[test]
+    fn ports() {
+        assert_eq!(rule("-1", -1, -1, "0.0.0.0/0").ports(), "all");
+        assert_eq!(rule("17", 0, 65535, "0.0.0.0/0").ports(), "all");
+        assert_eq!(rule("tcp", 80, 80, "0.0.0.0/0").ports(), "80");
+        assert_eq!(rule("tcp", 8000, 8080, "0.0.0.0/0").ports(), "8000-8080");
+        assert_eq!(rule("icmp", -1, -1, "0.0.0.0/0").ports(), "any type");
+        assert_eq!(rule("icmp", 8, -1, "0.0.0.0/0").ports(), "type 8");
+        assert_eq!(rule("icmp", 3, 4, "0.0.0.0/0").ports(), "type 3 code 4");
+    }
+
+    #[test]
+    fn exposure_of_groups() {
+        let groups = vec![
+            group(
+                "sg-web",
+                vec![
+                    permission("tcp", 443, 443, "0.0.0.0/0"),
+                    permission("tcp", 22, 22, "0.0.0.0/0"),
+                ],
+            ),
+            group(
+                "sg-db",
+                vec![
+                    permission("tcp", 5432, 5432, "10.0.0.0/8"),
+                    permission("tcp", 22, 22, "0.0.0.0/0"),
+                ],
+            ),
+        ];
+
+        let web = exposure(
+            &instance(Some("203.0.113.1"), &["sg-web", "sg-db"]),
+            &groups,
+        );
+        assert_eq!(web.ports, vec!["443", "22"]);
+        assert_eq!(web.risks, vec!["ssh"]);
+        assert!(web.is_open() && web.is_risky());
+
+        let db = exposure(&instance(Some("203.0.113.2"), &["sg-db"]), &groups);
+        assert_eq!(db.ports, vec!["22"]);
+
+        let other = exposure(&instance(Some("203.0.113.3"), &["sg-other"]), &groups);
+        assert_eq!(other, Exposure::default());
+    }
+
+    #[test]
+    fn exposure_needs_public_ip() {
+        let groups = vec![group(
+            "sg-open",
+            vec![permission("-1", -1, -1, "0.0.0.0/0")],
+        )];
+
+        assert_eq!(
+            exposure(&instance(None, &["sg-open"]), &groups),
+            Exposure::default()
+        );
+        assert_eq!(
+            exposure(&instance(Some(""), &["sg-open"]), &groups),
+            Exposure::default()
+        );
+        assert!(exposure(&instance(Some("203.0.113.1"), &["sg-open"]), &groups).is_risky());
+    }
+}
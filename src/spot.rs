@@ -0,0 +1,610 @@
+use chrono::{DateTime, Duration, Utc};
+use cursive::theme::{BaseColor, Color, ColorStyle};
+use rusoto_core::Region;
+use rusoto_ec2::{
+    CancelSpotFleetRequestsRequest, CancelSpotInstanceRequestsRequest,
+    DescribeSpotFleetRequestsRequest, DescribeSpotInstanceRequestsRequest,
+    DescribeSpotPriceHistoryRequest, Ec2, SpotFleetRequestConfig, SpotInstanceRequest, SpotPrice,
+    TerminateInstancesRequest,
+};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::error::Error;
+
+use crate::aws::new_ec2client;
+use crate::metrics::sparkline;
+use crate::views::{DetailLine, Header, TableViewItem};
+
+/// How far back the price history goes.
+pub const PRICE_HISTORY_DAYS: i64 = 7;
+
+/// Datapoints of the price sparklines, one per three hours.
+const PRICE_BUCKETS: usize = 56;
+
+/// Status codes of requests that wait in vain or lost their instance.
+const PROBLEM_CODES: &[&str] = &[
+    "bad-parameters",
+    "capacity-not-available",
+    "capacity-oversubscribed",
+    "constraint-not-fulfillable",
+    "instance-terminated-by-price",
+    "instance-terminated-capacity-oversubscribed",
+    "instance-terminated-no-capacity",
+    "price-too-low",
+    "system-error",
+];
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub enum SpotColumn {
+    RequestID,
+    Kind,
+    State,
+    Status,
+    InstanceType,
+    MaxPrice,
+    Instances,
+    Zone,
+    Created,
+}
+
+impl Header for SpotColumn {
+    fn to_header(&self) -> String {
+        match self {
+            SpotColumn::RequestID => "request-id".to_string(),
+            SpotColumn::Kind => "kind".to_string(),
+            SpotColumn::State => "state".to_string(),
+            SpotColumn::Status => "status".to_string(),
+            SpotColumn::InstanceType => "type".to_string(),
+            SpotColumn::MaxPrice => "max-price".to_string(),
+            SpotColumn::Instances => "instances".to_string(),
+            SpotColumn::Zone => "zone".to_string(),
+            SpotColumn::Created => "created".to_string(),
+        }
+    }
+
+    fn to_header_size(&self, w: usize) -> usize {
+        match self {
+            SpotColumn::RequestID => 46,
+            SpotColumn::Kind => 8,
+            SpotColumn::State => 10,
+            SpotColumn::Status => (30 * w) / 160,
+            SpotColumn::InstanceType => 20,
+            SpotColumn::MaxPrice => 10,
+            SpotColumn::Instances => 20,
+            SpotColumn::Zone => 12,
+            SpotColumn::Created => 17,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum SpotKind {
+    Request,
+    Fleet,
+}
+
+/// A spot instance request or a spot fleet request.
+#[derive(Clone, PartialEq, Debug)]
+pub struct SpotRequest {
+    pub kind: SpotKind,
+    pub id: String,
+    pub state: String,
+    /// The status code of a request, the activity status of a fleet.
+    pub status: String,
+    pub message: String,
+    pub instance_types: Vec<String>,
+    pub product: String,
+    pub max_price: String,
+    /// The instance of a request, the fulfilled and target capacity of a
+    /// fleet.
+    pub instances: String,
+    pub instance_id: Option<String>,
+    pub zone: String,
+    pub created: String,
+}
+
+impl SpotRequest {
+    fn from_request(request: SpotInstanceRequest) -> Self {
+        let status = request.status.unwrap_or_default();
+        let specification = request.launch_specification.unwrap_or_default();
+        let placement = specification.placement;
+
+        SpotRequest {
+            kind: SpotKind::Request,
+            id: request.spot_instance_request_id.unwrap_or_default(),
+            state: request.state.unwrap_or_default(),
+            status: status.code.unwrap_or_default(),
+            message: status.message.unwrap_or_default(),
+            instance_types: specification.instance_type.into_iter().collect(),
+            product: request.product_description.unwrap_or_default(),
+            max_price: request.spot_price.unwrap_or_default(),
+            instances: request.instance_id.clone().unwrap_or_default(),
+            instance_id: request.instance_id,
+            zone: request
+                .launched_availability_zone
+                .or_else(|| placement.and_then(|p| p.availability_zone))
+                .unwrap_or_default(),
+            created: request.create_time.unwrap_or_default(),
+        }
+    }
+
+    fn from_fleet(fleet: SpotFleetRequestConfig) -> Self {
+        let config = fleet.spot_fleet_request_config.unwrap_or_default();
+
+        let mut instance_types: Vec<String> = config
+            .launch_specifications
+            .iter()
+            .flatten()
+            .filter_map(|spec| spec.instance_type.clone())
+            .chain(
+                config
+                    .launch_template_configs
+                    .iter()
+                    .flatten()
+                    .flat_map(|c| c.overrides.clone().unwrap_or_default())
+                    .filter_map(|o| o.instance_type),
+            )
+            .collect();
+        instance_types.sort();
+        instance_types.dedup();
+
+        SpotRequest {
+            kind: SpotKind::Fleet,
+            id: fleet.spot_fleet_request_id.unwrap_or_default(),
+            state: fleet.spot_fleet_request_state.unwrap_or_default(),
+            status: fleet.activity_status.unwrap_or_default(),
+            message: "".to_string(),
+            instance_types,
+            product: "".to_string(),
+            max_price: config.spot_price.unwrap_or_default(),
+            instances: format!(
+                "{}/{}",
+                config.fulfilled_capacity.unwrap_or_default(),
+                config.target_capacity
+            ),
+            instance_id: None,
+            zone: "".to_string(),
+            created: fleet.create_time.unwrap_or_default(),
+        }
+    }
+
+    /// Whether the request can still launch instances.
+    pub fn is_active(&self) -> bool {
+        ["open", "active", "submitted", "modifying"].contains(&self.state.as_str())
+    }
+
+    pub fn has_problem(&self) -> bool {
+        PROBLEM_CODES.contains(&self.status.as_str()) || self.status == "error"
+    }
+}
+
+fn format_date(date: &str) -> String {
+    DateTime::parse_from_rfc3339(date)
+        .map(|d| d.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|_| date.to_string())
+}
+
+impl TableViewItem<SpotColumn> for SpotRequest {
+    fn to_column(&self, column: SpotColumn) -> String {
+        match column {
+            SpotColumn::RequestID => self.id.clone(),
+            SpotColumn::Kind => match self.kind {
+                SpotKind::Request => "request".to_string(),
+                SpotKind::Fleet => "fleet".to_string(),
+            },
+            SpotColumn::State => self.state.clone(),
+            SpotColumn::Status => self.status.clone(),
+            SpotColumn::InstanceType => self.instance_types.join(","),
+            SpotColumn::MaxPrice => self.max_price.clone(),
+            SpotColumn::Instances => self.instances.clone(),
+            SpotColumn::Zone => self.zone.clone(),
+            SpotColumn::Created => format_date(&self.created),
+        }
+    }
+
+    fn to_column_color(&self, column: SpotColumn) -> ColorStyle {
+        match column {
+            SpotColumn::State if self.state == "failed" => {
+                ColorStyle::new(Color::Light(BaseColor::Red), Color::TerminalDefault)
+            }
+            SpotColumn::State if self.state == "active" => {
+                ColorStyle::new(Color::Dark(BaseColor::Green), Color::TerminalDefault)
+            }
+            SpotColumn::Status if self.has_problem() => {
+                ColorStyle::new(Color::Light(BaseColor::Yellow), Color::TerminalDefault)
+            }
+            _ => ColorStyle::primary(),
+        }
+    }
+
+    fn cmp(&self, other: &Self, column: SpotColumn) -> Ordering {
+        match column {
+            SpotColumn::MaxPrice => self
+                .max_price
+                .parse::<f64>()
+                .ok()
+                .partial_cmp(&other.max_price.parse::<f64>().ok())
+                .unwrap_or(Ordering::Equal),
+            SpotColumn::Created => self.created.cmp(&other.created),
+            _ => self
+                .to_column(column)
+                .to_lowercase()
+                .cmp(&other.to_column(column).to_lowercase()),
+        }
+    }
+}
+
+/// Returns the spot instance requests and the spot fleet requests, the
+/// newest first.
+pub fn get_spot_requests(
+    region: &Region,
+    profile: &str,
+) -> Result<Vec<SpotRequest>, Box<dyn Error>> {
+    let client = new_ec2client(region, profile)?;
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let mut requests = vec![];
+    let mut next_token = None;
+
+    loop {
+        let req = DescribeSpotInstanceRequestsRequest {
+            next_token: next_token.clone(),
+            ..Default::default()
+        };
+
+        let response = runtime.block_on(client.describe_spot_instance_requests(req))?;
+
+        requests.extend(
+            response
+                .spot_instance_requests
+                .unwrap_or_default()
+                .into_iter()
+                .map(SpotRequest::from_request),
+        );
+
+        next_token = response.next_token;
+        if next_token.is_none() {
+            break;
+        }
+    }
+
+    loop {
+        let req = DescribeSpotFleetRequestsRequest {
+            next_token: next_token.clone(),
+            ..Default::default()
+        };
+
+        let response = runtime.block_on(client.describe_spot_fleet_requests(req))?;
+
+        requests.extend(
+            response
+                .spot_fleet_request_configs
+                .unwrap_or_default()
+                .into_iter()
+                .map(SpotRequest::from_fleet),
+        );
+
+        next_token = response.next_token;
+        if next_token.is_none() {
+            break;
+        }
+    }
+
+    requests.sort_by(|a, b| b.created.cmp(&a.created));
+
+    Ok(requests)
+}
+
+/// Cancels a spot request, optionally terminating its instances. Instances
+/// of a canceled request keep running otherwise.
+pub fn cancel_spot_request(
+    region: &Region,
+    profile: &str,
+    request: &SpotRequest,
+    terminate: bool,
+    dry_run: bool,
+) -> Result<(), Box<dyn Error>> {
+    let client = new_ec2client(region, profile)?;
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    match request.kind {
+        SpotKind::Request => {
+            let req = CancelSpotInstanceRequestsRequest {
+                spot_instance_request_ids: vec![request.id.clone()],
+                dry_run: Some(dry_run),
+            };
+
+            runtime.block_on(client.cancel_spot_instance_requests(req))?;
+
+            if let (true, Some(instance_id)) = (terminate, &request.instance_id) {
+                let req = TerminateInstancesRequest {
+                    instance_ids: vec![instance_id.clone()],
+                    dry_run: Some(dry_run),
+                };
+
+                runtime.block_on(client.terminate_instances(req))?;
+            }
+        }
+        SpotKind::Fleet => {
+            let req = CancelSpotFleetRequestsRequest {
+                spot_fleet_request_ids: vec![request.id.clone()],
+                terminate_instances: terminate,
+                dry_run: Some(dry_run),
+            };
+
+            let response = runtime.block_on(client.cancel_spot_fleet_requests(req))?;
+
+            if let Some(error) = response
+                .unsuccessful_fleet_requests
+                .unwrap_or_default()
+                .into_iter()
+                .find_map(|item| item.error)
+            {
+                return Err(format!(
+                    "{}: {}",
+                    error.code.unwrap_or_default(),
+                    error.message.unwrap_or_default()
+                )
+                .into());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the spot prices of an instance type in all availability zones of
+/// the region over the last `PRICE_HISTORY_DAYS`.
+pub fn get_spot_price_history(
+    region: &Region,
+    profile: &str,
+    instance_type: &str,
+    product: &str,
+) -> Result<Vec<SpotPrice>, Box<dyn Error>> {
+    let client = new_ec2client(region, profile)?;
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let end = Utc::now();
+    let start = end - Duration::days(PRICE_HISTORY_DAYS);
+
+    let mut prices = vec![];
+    let mut next_token = None;
+
+    loop {
+        let req = DescribeSpotPriceHistoryRequest {
+            instance_types: Some(vec![instance_type.to_string()]),
+            product_descriptions: Some(vec![product.to_string()]),
+            start_time: Some(start.format("%Y-%m-%dT%H:%M:%SZ").to_string()),
+            end_time: Some(end.format("%Y-%m-%dT%H:%M:%SZ").to_string()),
+            next_token: next_token.clone(),
+            ..Default::default()
+        };
+
+        let response = runtime.block_on(client.describe_spot_price_history(req))?;
+
+        prices.extend(response.spot_price_history.unwrap_or_default());
+
+        // the last page comes with an empty token
+        next_token = response.next_token.filter(|token| !token.is_empty());
+        if next_token.is_none() {
+            break;
+        }
+    }
+
+    Ok(prices)
+}
+
+/// Returns the price in effect at the end of each of `buckets` equal parts
+/// of `start..end`, from prices sorted by time.
+fn sample(prices: &[(i64, f64)], start: i64, end: i64, buckets: usize) -> Vec<f64> {
+    (1..=buckets)
+        .map(|i| {
+            let t = start + (end - start) * i as i64 / buckets as i64;
+
+            prices
+                .iter()
+                .rev()
+                .find(|(timestamp, _)| *timestamp <= t)
+                .or_else(|| prices.first())
+                .map(|(_, price)| *price)
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// Returns per availability zone the current price, a sparkline and the
+/// lowest and highest price, for the price history screen.
+pub fn price_lines(instance_type: &str, product: &str, prices: &[SpotPrice]) -> Vec<DetailLine> {
+    let end = Utc::now().timestamp();
+    let start = end - Duration::days(PRICE_HISTORY_DAYS).num_seconds();
+
+    let mut zones: BTreeMap<String, Vec<(i64, f64)>> = BTreeMap::new();
+
+    for price in prices {
+        let timestamp = price
+            .timestamp
+            .as_ref()
+            .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+            .map(|t| t.timestamp());
+        let value = price
+            .spot_price
+            .as_ref()
+            .and_then(|p| p.parse::<f64>().ok());
+
+        if let (Some(timestamp), Some(value)) = (timestamp, value) {
+            zones
+                .entry(price.availability_zone.clone().unwrap_or_default())
+                .or_default()
+                .push((timestamp, value));
+        }
+    }
+
+    let mut lines = vec![DetailLine::section(&format!(
+        "{} {}, last {} days",
+        instance_type, product, PRICE_HISTORY_DAYS
+    ))];
+
+    if zones.is_empty() {
+        lines.push(DetailLine::field("", "no prices"));
+    }
+
+    for (zone, mut points) in zones {
+        points.sort_by_key(|(timestamp, _)| *timestamp);
+
+        let values: Vec<f64> = points.iter().map(|(_, price)| *price).collect();
+        let current = values.last().cloned().unwrap_or_default();
+        let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
+        let max = values.iter().cloned().fold(0.0, f64::max);
+
+        lines.push(DetailLine::field(
+            &zone,
+            format!(
+                "{:.4}  {}  min {:.4}  max {:.4}",
+                current,
+                sparkline(
+                    &sample(&points, start, end, PRICE_BUCKETS),
+                    PRICE_BUCKETS,
+                    None
+                ),
+                min,
+                max
+            ),
+        ));
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusoto_ec2::{
+        LaunchSpecification, LaunchTemplateConfig, LaunchTemplateOverrides,
+        SpotFleetLaunchSpecification, SpotFleetRequestConfigData, SpotInstanceStatus,
+        SpotPlacement,
+    };
+
+    fn request(launched_zone: Option<&str>) -> SpotInstanceRequest {
+        SpotInstanceRequest {
+            spot_instance_request_id: Some("sir-0123abcd".to_string()),
+            state: Some("active".to_string()),
+            status: Some(SpotInstanceStatus {
+                code: Some("fulfilled".to_string()),
+                message: Some("Your spot request is fulfilled.".to_string()),
+                ..Default::default()
+            }),
+            launch_specification: Some(LaunchSpecification {
+                instance_type: Some("m5.large".to_string()),
+                placement: Some(SpotPlacement {
+                    availability_zone: Some("eu-west-1b".to_string()),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            product_description: Some("Linux/UNIX".to_string()),
+            spot_price: Some("0.0400".to_string()),
+            instance_id: Some("i-0123456789abcdef0".to_string()),
+            launched_availability_zone: launched_zone.map(String::from),
+            create_time: Some("2020-06-01T12:00:00.000Z".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn from_request() {
+        let request = SpotRequest::from_request(request(Some("eu-west-1a")));
+
+        assert_eq!(request.kind, SpotKind::Request);
+        assert_eq!(request.id, "sir-0123abcd");
+        assert_eq!(request.status, "fulfilled");
+        assert_eq!(request.message, "Your spot request is fulfilled.");
+        assert_eq!(request.instance_types, vec!["m5.large"]);
+        assert_eq!(request.product, "Linux/UNIX");
+        assert_eq!(request.instances, "i-0123456789abcdef0");
+        assert_eq!(request.instance_id, Some("i-0123456789abcdef0".to_string()));
+        assert_eq!(request.zone, "eu-west-1a");
+        assert!(request.is_active());
+        assert!(!request.has_problem());
+    }
+
+    #[test]
+    fn from_request_zone_of_placement() {
+        let request = SpotRequest::from_request(request(None));
+
+        assert_eq!(request.zone, "eu-west-1b");
+    }
+
+    #[test]
+    fn from_empty_request() {
+        let request = SpotRequest::from_request(SpotInstanceRequest::default());
+
+        assert!(request.instance_types.is_empty());
+        assert_eq!(request.zone, "");
+        assert_eq!(request.instance_id, None);
+    }
+
+    #[test]
+    fn from_fleet() {
+        let fleet = SpotFleetRequestConfig {
+            spot_fleet_request_id: Some("sfr-0123abcd".to_string()),
+            spot_fleet_request_state: Some("active".to_string()),
+            activity_status: Some("pending_fulfillment".to_string()),
+            spot_fleet_request_config: Some(SpotFleetRequestConfigData {
+                launch_specifications: Some(vec![
+                    SpotFleetLaunchSpecification {
+                        instance_type: Some("m5.large".to_string()),
+                        ..Default::default()
+                    },
+                    SpotFleetLaunchSpecification {
+                        instance_type: Some("c5.large".to_string()),
+                        ..Default::default()
+                    },
+                ]),
+                launch_template_configs: Some(vec![LaunchTemplateConfig {
+                    overrides: Some(vec![
+                        LaunchTemplateOverrides {
+                            instance_type: Some("m5.large".to_string()),
+                            ..Default::default()
+                        },
+                        LaunchTemplateOverrides {
+                            instance_type: Some("r5.large".to_string()),
+                            ..Default::default()
+                        },
+                    ]),
+                    ..Default::default()
+                }]),
+                fulfilled_capacity: Some(2.0),
+                target_capacity: 4,
+                spot_price: Some("0.1".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let request = SpotRequest::from_fleet(fleet);
+
+        assert_eq!(request.kind, SpotKind::Fleet);
+        assert_eq!(request.id, "sfr-0123abcd");
+        assert_eq!(request.status, "pending_fulfillment");
+        assert_eq!(
+            request.instance_types,
+            vec!["c5.large", "m5.large", "r5.large"]
+        );
+        assert_eq!(request.instances, "2/4");
+        assert_eq!(request.max_price, "0.1");
+        assert!(request.is_active());
+    }
+
+    #[test]
+    fn sample_prices() {
+        let prices = vec![(100, 1.0), (150, 2.0), (180, 3.0)];
+
+        // the first price stands in for the time before it
+        assert_eq!(sample(&prices, 0, 200, 4), vec![1.0, 1.0, 2.0, 3.0]);
+        assert_eq!(sample(&prices, 100, 200, 2), vec![2.0, 3.0]);
+        assert_eq!(sample(&[], 0, 200, 2), vec![0.0, 0.0]);
+    }
+}
@@ -0,0 +1,470 @@
+use rusoto_ec2::Instance;
+use std::path::{Path, PathBuf};
+
+use crate::config::{Address, SshConfig};
+
+/// Login users of common AMIs, matched against the lowercased image name.
+const IMAGE_USERS: &[(&str, &str)] = &[
+    ("ubuntu", "ubuntu"),
+    ("debian", "admin"),
+    ("centos", "centos"),
+    ("fedora", "fedora"),
+    ("bitnami", "bitnami"),
+    ("freebsd", "ec2-user"),
+    ("amzn", "ec2-user"),
+    ("rhel", "ec2-user"),
+    ("suse", "ec2-user"),
+];
+
+const DEFAULT_USER: &str = "ec2-user";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SshTarget {
+    pub user: String,
+    pub host: String,
+    pub key: Option<PathBuf>,
+    pub jump: Option<String>,
+    /// `ProxyCommand`, used to tunnel ssh through ssm.
+    pub proxy: Option<String>,
+}
+
+impl SshTarget {
+    /// Options shared by ssh and scp.
+    pub fn options(&self) -> Vec<String> {
+        let mut args = vec![];
+
+        if let Some(key) = &self.key {
+            args.push("-i".to_string());
+            args.push(key.to_string_lossy().to_string());
+        }
+
+        // -J is not understood by older scp versions
+        if let Some(jump) = &self.jump {
+            args.push("-o".to_string());
+            args.push(format!("ProxyJump={}", jump));
+        }
+
+        if let Some(proxy) = &self.proxy {
+            args.push("-o".to_string());
+            args.push(format!("ProxyCommand={}", proxy));
+        }
+
+        args
+    }
+
+    pub fn destination(&self) -> String {
+        format!("{}@{}", self.user, self.host)
+    }
+
+    /// Returns `path` on the target, as understood by scp.
+    pub fn remote(&self, path: &str) -> String {
+        format!("{}:{}", self.destination(), path)
+    }
+
+    /// Returns the argument vector for `scp`, recursively copying `source`
+    /// to `destination`.
+    pub fn copy_command(&self, source: &str, destination: &str) -> Vec<String> {
+        let mut args = vec!["scp".to_string(), "-r".to_string()];
+        args.extend(self.options());
+        // neither path is read as an option, whatever it starts with
+        args.push("--".to_string());
+        args.push(source.to_string());
+        args.push(destination.to_string());
+        args
+    }
+
+    /// Returns the argument vector for `ssh`.
+    pub fn command(&self) -> Vec<String> {
+        let mut args = vec!["ssh".to_string()];
+        args.extend(self.options());
+        args.push("--".to_string());
+        args.push(self.destination());
+        args
+    }
+}
+
+/// Expands a leading `~` to the home directory.
+pub fn expand_home(path: &str) -> PathBuf {
+    match dirs::home_dir() {
+        Some(home) if path.starts_with("~/") => home.join(&path[2..]),
+        _ => PathBuf::from(path),
+    }
+}
+
+fn tag<'a>(instance: &'a Instance, key: &str) -> Option<&'a str> {
+    instance
+        .tags
+        .as_ref()?
+        .iter()
+        .find(|t| {
+            t.key
+                .as_deref()
+                .is_some_and(|k| k.eq_ignore_ascii_case(key))
+        })
+        .and_then(|t| t.value.as_deref())
+        .filter(|v| !v.is_empty())
+}
+
+/// Whether `user` is a plain login name. The user tag can be set by anyone
+/// allowed to tag the instance, and must not pass for an ssh option such as
+/// `-oProxyCommand=...`.
+fn is_valid_user(user: &str) -> bool {
+    let mut chars = user.chars();
+
+    // upper case letters too, for `Administrator` on Windows
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '_' || c == '-')
+}
+
+/// Picks the login user: the user tag, a configured user for the AMI id or
+/// name, the platform or well known AMI names, and the configured default.
+/// Fails on users that aren't plain login names.
+pub fn resolve_user(
+    instance: &Instance,
+    image_name: Option<&str>,
+    config: &SshConfig,
+) -> Result<String, String> {
+    let user = guess_user(instance, image_name, config);
+
+    if is_valid_user(&user) {
+        Ok(user)
+    } else {
+        Err(format!("{:?} is not a valid login user.", user))
+    }
+}
+
+fn guess_user(instance: &Instance, image_name: Option<&str>, config: &SshConfig) -> String {
+    if let Some(user) = tag(instance, &config.user_tag) {
+        return user.to_string();
+    }
+
+    let image_id = instance.image_id.clone().unwrap_or_default();
+    let image_name = image_name.unwrap_or("").to_lowercase();
+
+    if let Some(user) = config.users.get(&image_id) {
+        return user.clone();
+    }
+
+    // the most specific pattern, as the users are not kept in file order
+    if let Some((_, user)) = config
+        .users
+        .iter()
+        .filter(|(pattern, _)| image_name.contains(&pattern.to_lowercase()))
+        .max_by(|(a, _), (b, _)| a.len().cmp(&b.len()).then_with(|| b.cmp(a)))
+    {
+        return user.clone();
+    }
+
+    if instance.platform.as_deref() == Some("windows") {
+        return "Administrator".to_string();
+    }
+
+    if let Some((_, user)) = IMAGE_USERS
+        .iter()
+        .find(|(pattern, _)| image_name.contains(pattern))
+    {
+        return user.to_string();
+    }
+
+    config
+        .user
+        .clone()
+        .unwrap_or_else(|| DEFAULT_USER.to_string())
+}
+
+/// Maps the key pair name of the instance to a local private key, if any.
+pub fn resolve_key(instance: &Instance, config: &SshConfig) -> Option<PathBuf> {
+    let key_name = instance.key_name.as_ref()?;
+
+    if let Some(path) = config.keys.get(key_name) {
+        return Some(expand_home(path));
+    }
+
+    let dir = expand_home(&config.key_dir);
+
+    [format!("{}.pem", key_name), key_name.to_string()]
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| Path::new(path).is_file())
+}
+
+pub fn resolve_host(instance: &Instance, address: Address, jump: bool) -> Option<String> {
+    let non_empty = |v: &Option<String>| v.clone().filter(|v| !v.is_empty());
+
+    let public_ip = non_empty(&instance.public_ip_address);
+    let private_ip = non_empty(&instance.private_ip_address);
+    let public_dns = non_empty(&instance.public_dns_name);
+    let private_dns = non_empty(&instance.private_dns_name);
+
+    match address {
+        Address::Public => public_ip,
+        Address::Private => private_ip,
+        Address::Dns => public_dns.or(private_dns),
+        // behind a bastion the private address is reachable
+        Address::Auto if jump => private_ip.or(public_ip),
+        Address::Auto => public_ip.or(private_ip),
+    }
+}
+
+pub fn resolve(
+    instance: &Instance,
+    image_name: Option<&str>,
+    config: &SshConfig,
+) -> Result<SshTarget, String> {
+    let jump = instance
+        .vpc_id
+        .as_ref()
+        .and_then(|vpc_id| config.bastions.get(vpc_id))
+        .cloned();
+
+    let host = resolve_host(instance, config.address, jump.is_some())
+        .ok_or_else(|| "The instance has no address to connect to.".to_string())?;
+
+    Ok(SshTarget {
+        user: resolve_user(instance, image_name, config)?,
+        host,
+        key: resolve_key(instance, config),
+        jump,
+        proxy: None,
+    })
+}
+
+/// Resolves the target for ssh tunneled through ssm, which only needs the
+/// instance id; `proxy` is the command as returned by
+/// `ssm::ssh_proxy_command`.
+pub fn resolve_ssm(
+    instance: &Instance,
+    image_name: Option<&str>,
+    config: &SshConfig,
+    proxy: String,
+) -> Result<SshTarget, String> {
+    Ok(SshTarget {
+        user: resolve_user(instance, image_name, config)?,
+        host: instance.instance_id.clone().unwrap_or_default(),
+        key: resolve_key(instance, config),
+        jump: None,
+        proxy: Some(proxy),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp::PrivateDir;
+    use rusoto_ec2::Tag;
+
+    fn instance(tags: &[(&str, &str)]) -> Instance {
+        Instance {
+            instance_id: Some("i-0123456789abcdef0".to_string()),
+            image_id: Some("ami-0123456789abcdef0".to_string()),
+            tags: Some(
+                tags.iter()
+                    .map(|(key, value)| Tag {
+                        key: Some(key.to_string()),
+                        value: Some(value.to_string()),
+                    })
+                    .collect(),
+            ),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn user_from_tag() {
+        let config = SshConfig::default();
+        let instance = instance(&[("SSH-User", "deploy")]);
+
+        assert_eq!(
+            resolve_user(&instance, Some("ubuntu-focal"), &config),
+            Ok("deploy".to_string())
+        );
+    }
+
+    #[test]
+    fn user_rejects_options() {
+        let config = SshConfig::default();
+
+        for user in &["-oProxyCommand=touch /tmp/x", "a b", "root@host", "1st"] {
+            let instance = instance(&[("ssh-user", user)]);
+            assert!(resolve_user(&instance, None, &config).is_err(), "{}", user);
+        }
+    }
+
+    #[test]
+    fn user_from_config() {
+        let mut config = SshConfig::default();
+        config
+            .users
+            .insert("ami-0123456789abcdef0".to_string(), "core".to_string());
+        config
+            .users
+            .insert("Flatcar".to_string(), "flat".to_string());
+
+        assert_eq!(
+            resolve_user(&instance(&[]), Some("ubuntu"), &config),
+            Ok("core".to_string())
+        );
+
+        let mut other = instance(&[]);
+        other.image_id = Some("ami-0fedcba9876543210".to_string());
+        assert_eq!(
+            resolve_user(&other, Some("flatcar-stable"), &config),
+            Ok("flat".to_string())
+        );
+    }
+
+    #[test]
+    fn user_from_overlapping_patterns() {
+        let mut config = SshConfig::default();
+        config
+            .users
+            .insert("ubuntu".to_string(), "ubuntu".to_string());
+        config
+            .users
+            .insert("ubuntu-eks".to_string(), "eks".to_string());
+        config.users.insert("eks".to_string(), "other".to_string());
+        config.users.insert("EKS-".to_string(), "other".to_string());
+
+        // the longest pattern wins, whatever the order of the map
+        for _ in 0..10 {
+            assert_eq!(
+                resolve_user(&instance(&[]), Some("ubuntu-eks/k8s_1.18"), &config),
+                Ok("eks".to_string())
+            );
+        }
+
+        // patterns of the same length by name
+        config.users.insert("k8s_".to_string(), "k8s".to_string());
+        config.users.insert("1.18".to_string(), "v118".to_string());
+        assert_eq!(
+            resolve_user(&instance(&[]), Some("amazon-eks-k8s_1.18"), &config),
+            Ok("v118".to_string())
+        );
+    }
+
+    #[test]
+    fn user_from_image() {
+        let mut config = SshConfig::default();
+
+        assert_eq!(
+            resolve_user(&instance(&[]), Some("Ubuntu-Focal-20.04"), &config),
+            Ok("ubuntu".to_string())
+        );
+        assert_eq!(
+            resolve_user(&instance(&[]), None, &config),
+            Ok(DEFAULT_USER.to_string())
+        );
+
+        // the configured default only applies to unknown images
+        config.user = Some("admin".to_string());
+        assert_eq!(
+            resolve_user(&instance(&[]), Some("amzn2-ami-hvm"), &config),
+            Ok("ec2-user".to_string())
+        );
+        assert_eq!(
+            resolve_user(&instance(&[]), Some("centos-8"), &config),
+            Ok("centos".to_string())
+        );
+        assert_eq!(
+            resolve_user(&instance(&[]), Some("custom"), &config),
+            Ok("admin".to_string())
+        );
+
+        let mut windows = instance(&[]);
+        windows.platform = Some("windows".to_string());
+        assert_eq!(
+            resolve_user(&windows, Some("Windows_Server-2019"), &config),
+            Ok("Administrator".to_string())
+        );
+    }
+
+    #[test]
+    fn host() {
+        let mut instance = instance(&[]);
+        instance.private_ip_address = Some("10.0.0.1".to_string());
+        instance.public_dns_name = Some("".to_string());
+        instance.private_dns_name = Some("ip-10-0-0-1.internal".to_string());
+
+        assert_eq!(resolve_host(&instance, Address::Public, false), None);
+        assert_eq!(
+            resolve_host(&instance, Address::Auto, false),
+            Some("10.0.0.1".to_string())
+        );
+        assert_eq!(
+            resolve_host(&instance, Address::Dns, false),
+            Some("ip-10-0-0-1.internal".to_string())
+        );
+
+        instance.public_ip_address = Some("203.0.113.1".to_string());
+        assert_eq!(
+            resolve_host(&instance, Address::Auto, false),
+            Some("203.0.113.1".to_string())
+        );
+        assert_eq!(
+            resolve_host(&instance, Address::Auto, true),
+            Some("10.0.0.1".to_string())
+        );
+        assert_eq!(
+            resolve_host(&instance, Address::Private, false),
+            Some("10.0.0.1".to_string())
+        );
+    }
+
+    #[test]
+    fn key() {
+        let dir = PrivateDir::new("ssh-test").unwrap();
+        dir.create_file("deploy.pem").unwrap();
+        dir.create_file("legacy").unwrap();
+
+        let mut config = SshConfig::default();
+        config.key_dir = dir.path().to_string_lossy().to_string();
+        config
+            .keys
+            .insert("shared".to_string(), "/keys/shared.pem".to_string());
+
+        let mut instance = instance(&[]);
+        assert_eq!(resolve_key(&instance, &config), None);
+
+        instance.key_name = Some("deploy".to_string());
+        assert_eq!(
+            resolve_key(&instance, &config),
+            Some(dir.path().join("deploy.pem"))
+        );
+
+        instance.key_name = Some("legacy".to_string());
+        assert_eq!(
+            resolve_key(&instance, &config),
+            Some(dir.path().join("legacy"))
+        );
+
+        instance.key_name = Some("missing".to_string());
+        assert_eq!(resolve_key(&instance, &config), None);
+
+        instance.key_name = Some("shared".to_string());
+        assert_eq!(
+            resolve_key(&instance, &config),
+            Some(PathBuf::from("/keys/shared.pem"))
+        );
+    }
+
+    #[test]
+    fn command_ends_options() {
+        let target = SshTarget {
+            user: "ec2-user".to_string(),
+            host: "203.0.113.1".to_string(),
+            key: None,
+            jump: None,
+            proxy: None,
+        };
+
+        assert_eq!(target.command(), vec!["ssh", "--", "ec2-user@203.0.113.1"]);
+        assert_eq!(
+            target.copy_command("-local", &target.remote("/tmp")),
+            vec!["scp", "-r", "--", "-local", "ec2-user@203.0.113.1:/tmp"]
+        );
+    }
+}
@@ -0,0 +1,249 @@
+use rusoto_core::Region;
+
+use crate::config::SsmConfig;
+
+/// Document used to run a configured shell (or any command) after connecting.
+pub const INTERACTIVE_COMMAND_DOCUMENT: &str = "AWS-StartInteractiveCommand";
+
+/// Returns the argument vector for `aws ssm start-session` to `target`.
+///
+/// Values are passed as separate arguments and never through a shell, so
+/// profile names or tags can't inject commands.
+pub fn start_session_command(
+    profile: &str,
+    region: &Region,
+    target: &str,
+    config: &SsmConfig,
+) -> Vec<String> {
+    let mut args: Vec<String> = vec![
+        "aws",
+        "ssm",
+        "start-session",
+        "--profile",
+        profile,
+        "--region",
+        region.name(),
+        "--target",
+        target,
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect();
+
+    let document = match (&config.document, &config.shell) {
+        (Some(document), _) => Some(document.clone()),
+        (None, Some(_)) => Some(INTERACTIVE_COMMAND_DOCUMENT.to_string()),
+        (None, None) => None,
+    };
+
+    if let Some(document) = document {
+        args.push("--document-name".to_string());
+        args.push(document);
+    }
+
+    if let Some(shell) = &config.shell {
+        args.push("--parameters".to_string());
+        args.push(serde_json::json!({ "command": [shell] }).to_string());
+    }
+
+    args
+}
+
+/// Returns the argument vector for a port forwarding session from
+/// `local_port` to `remote_port` on `target`, or on `remote_host` as seen
+/// from `target`.
+pub fn port_forwarding_command(
+    profile: &str,
+    region: &Region,
+    target: &str,
+    local_port: u16,
+    remote_host: Option<&str>,
+    remote_port: u16,
+) -> Vec<String> {
+    let mut args: Vec<String> = vec![
+        "aws",
+        "ssm",
+        "start-session",
+        "--profile",
+        profile,
+        "--region",
+        region.name(),
+        "--target",
+        target,
+        "--document-name",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect();
+
+    let parameters = match remote_host {
+        Some(host) => {
+            args.push("AWS-StartPortForwardingSessionToRemoteHost".to_string());
+            serde_json::json!({
+                "host": [host],
+                "portNumber": [remote_port.to_string()],
+                "localPortNumber": [local_port.to_string()],
+            })
+        }
+        None => {
+            args.push("AWS-StartPortForwardingSession".to_string());
+            serde_json::json!({
+                "portNumber": [remote_port.to_string()],
+                "localPortNumber": [local_port.to_string()],
+            })
+        }
+    };
+
+    args.push("--parameters".to_string());
+    args.push(parameters.to_string());
+
+    args
+}
+
+/// Document tunneling ssh connections, used as ssh `ProxyCommand`.
+pub const SSH_SESSION_DOCUMENT: &str = "AWS-StartSSHSession";
+
+/// Quotes `value` for a POSIX shell.
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Returns the ssh `ProxyCommand` tunneling the connection through ssm, with
+/// the instance id as host name. ssh runs it through a shell, so values are
+/// quoted.
+pub fn ssh_proxy_command(profile: &str, region: &Region) -> String {
+    format!(
+        "aws ssm start-session --target %h --document-name {} --parameters portNumber=%p --profile {} --region {}",
+        SSH_SESSION_DOCUMENT,
+        shell_quote(profile),
+        shell_quote(region.name()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(v: &[&str]) -> Vec<String> {
+        v.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn default_session() {
+        let cmd = start_session_command(
+            "default",
+            &Region::EuWest1,
+            "i-0123456789abcdef0",
+            &SsmConfig::default(),
+        );
+
+        assert_eq!(
+            cmd,
+            args(&[
+                "aws",
+                "ssm",
+                "start-session",
+                "--profile",
+                "default",
+                "--region",
+                "eu-west-1",
+                "--target",
+                "i-0123456789abcdef0",
+            ])
+        );
+    }
+
+    #[test]
+    fn values_are_not_quoted_or_interpreted() {
+        let cmd = start_session_command(
+            "prod\"; rm -rf ~; echo \"",
+            &Region::UsEast1,
+            "i-1$(id)",
+            &SsmConfig::default(),
+        );
+
+        assert_eq!(cmd[4], "prod\"; rm -rf ~; echo \"");
+        assert_eq!(cmd[6], "us-east-1");
+        assert_eq!(cmd[8], "i-1$(id)");
+    }
+
+    #[test]
+    fn shell_uses_interactive_command_document() {
+        let config = SsmConfig {
+            document: None,
+            shell: Some("bash -l".to_string()),
+        };
+
+        let cmd = start_session_command("default", &Region::EuWest1, "i-1", &config);
+
+        assert_eq!(
+            cmd[9..].to_vec(),
+            args(&[
+                "--document-name",
+                "AWS-StartInteractiveCommand",
+                "--parameters",
+                r#"{"command":["bash -l"]}"#,
+            ])
+        );
+    }
+
+    #[test]
+    fn configured_document() {
+        let config = SsmConfig {
+            document: Some("Custom-Session".to_string()),
+            shell: None,
+        };
+
+        let cmd = start_session_command("default", &Region::EuWest1, "i-1", &config);
+
+        assert_eq!(
+            cmd[9..].to_vec(),
+            args(&["--document-name", "Custom-Session"])
+        );
+    }
+
+    #[test]
+    fn shell_is_json_escaped() {
+        let config = SsmConfig {
+            document: None,
+            shell: Some(r#"sudo -iu "app""#.to_string()),
+        };
+
+        let cmd = start_session_command("default", &Region::EuWest1, "i-1", &config);
+
+        assert_eq!(cmd[12], r#"{"command":["sudo -iu \"app\""]}"#);
+    }
+
+    #[test]
+    fn port_forwarding_to_remote_host() {
+        let cmd = port_forwarding_command(
+            "default",
+            &Region::EuWest1,
+            "i-1",
+            15432,
+            Some("db.internal"),
+            5432,
+        );
+
+        assert_eq!(
+            cmd[9..].to_vec(),
+            args(&[
+                "--document-name",
+                "AWS-StartPortForwardingSessionToRemoteHost",
+                "--parameters",
+                r#"{"host":["db.internal"],"localPortNumber":["15432"],"portNumber":["5432"]}"#,
+            ])
+        );
+    }
+
+    #[test]
+    fn ssh_proxy_command_quotes_values() {
+        let cmd = ssh_proxy_command("it's; id", &Region::EuWest1);
+
+        assert_eq!(
+            cmd,
+            "aws ssm start-session --target %h --document-name AWS-StartSSHSession \
+             --parameters portNumber=%p --profile 'it'\\''s; id' --region 'eu-west-1'"
+        );
+    }
+}
@@ -0,0 +1,380 @@
+use rusoto_core::Region;
+use rusoto_ec2::{CreateTagsRequest, DeleteTagsRequest, Ec2, Instance, Tag};
+use std::error::Error;
+
+use crate::aws::new_ec2client;
+
+const MAX_KEY_LENGTH: usize = 128;
+const MAX_VALUE_LENGTH: usize = 256;
+
+/// Tags to set and tag keys to delete.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct TagChanges {
+    pub set: Vec<(String, String)>,
+    pub delete: Vec<String>,
+}
+
+impl TagChanges {
+    /// Returns the changes that turn the tags `before` into `after`.
+    pub fn between(before: &[(String, String)], after: &[(String, String)]) -> Self {
+        TagChanges {
+            set: after
+                .iter()
+                .filter(|tag| !before.contains(tag))
+                .cloned()
+                .collect(),
+            delete: before
+                .iter()
+                .filter(|(key, _)| !after.iter().any(|(k, _)| k == key))
+                .map(|(key, _)| key.clone())
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.set.is_empty() && self.delete.is_empty()
+    }
+}
+
+fn tag_pairs(tags: &Option<Vec<Tag>>) -> Vec<(String, String)> {
+    tags.iter()
+        .flatten()
+        .map(|t| {
+            (
+                t.key.clone().unwrap_or_default(),
+                t.value.clone().unwrap_or_default(),
+            )
+        })
+        .collect()
+}
+
+fn instance_tags(instance: &Instance) -> Vec<(String, String)> {
+    tag_pairs(&instance.tags)
+}
+
+/// Returns the tags of a resource that can be copied to other resources,
+/// leaving out the reserved `aws:` tags.
+pub fn copyable_tags(tags: &Option<Vec<Tag>>) -> Vec<(String, String)> {
+    tag_pairs(tags)
+        .into_iter()
+        .filter(|(key, _)| !key.to_lowercase().starts_with("aws:"))
+        .collect()
+}
+
+/// Returns the tags with the same value on all instances, sorted by key. The
+/// reserved `aws:` tags are left out, as they can't be changed.
+pub fn common_tags(instances: &[Instance]) -> Vec<(String, String)> {
+    let mut tags = match instances.first() {
+        Some(instance) => copyable_tags(&instance.tags),
+        None => return vec![],
+    };
+
+    for instance in instances.iter().skip(1) {
+        let other = copyable_tags(&instance.tags);
+        tags.retain(|tag| other.contains(tag));
+    }
+
+    tags.sort();
+    tags
+}
+
+/// Formats tags as `key = value` lines, as read by `parse_tags`.
+pub fn format_tags(tags: &[(String, String)]) -> String {
+    tags.iter()
+        .map(|(key, value)| format!("{} = {}\n", key, value))
+        .collect()
+}
+
+/// Parses `key = value` lines, skipping empty lines and lines starting with
+/// `#`. Values may be empty.
+pub fn parse_tags(text: &str) -> Result<Vec<(String, String)>, String> {
+    let mut tags: Vec<(String, String)> = vec![];
+
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let idx = match line.find('=') {
+            Some(idx) => idx,
+            None => return Err(format!("Line {}: expected key = value.", n + 1)),
+        };
+
+        let key = line[..idx].trim().to_string();
+        let value = line[idx + 1..].trim().to_string();
+
+        if key.is_empty() {
+            return Err(format!("Line {}: the key is empty.", n + 1));
+        }
+        if key.to_lowercase().starts_with("aws:") {
+            return Err(format!("Line {}: aws: tags are reserved.", n + 1));
+        }
+        if key.chars().count() > MAX_KEY_LENGTH {
+            return Err(format!(
+                "Line {}: keys are at most {} characters.",
+                n + 1,
+                MAX_KEY_LENGTH
+            ));
+        }
+        if value.chars().count() > MAX_VALUE_LENGTH {
+            return Err(format!(
+                "Line {}: values are at most {} characters.",
+                n + 1,
+                MAX_VALUE_LENGTH
+            ));
+        }
+        if tags.iter().any(|(k, _)| *k == key) {
+            return Err(format!("Line {}: {} is set twice.", n + 1, key));
+        }
+
+        tags.push((key, value));
+    }
+
+    Ok(tags)
+}
+
+/// Returns what `changes` do to the tags of `instance` as diff lines,
+/// prefixed with `+ ` and `- `. Changes without effect are left out.
+pub fn preview(instance: &Instance, changes: &TagChanges) -> Vec<String> {
+    let tags = instance_tags(instance);
+    let current = |key: &str| tags.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone());
+
+    let mut lines = vec![];
+
+    for key in changes.delete.iter() {
+        if let Some(value) = current(key) {
+            lines.push(format!("- {} = {}", key, value));
+        }
+    }
+
+    for (key, value) in changes.set.iter() {
+        match current(key) {
+            Some(ref old) if old == value => {}
+            Some(old) => {
+                lines.push(format!("- {} = {}", key, old));
+                lines.push(format!("+ {} = {}", key, value));
+            }
+            None => lines.push(format!("+ {} = {}", key, value)),
+        }
+    }
+
+    lines
+}
+
+/// Sets and deletes the tags of the given resources.
+pub fn change_tags(
+    region: &Region,
+    profile: &str,
+    resources: Vec<String>,
+    changes: &TagChanges,
+    dry_run: bool,
+) -> Result<(), Box<dyn Error>> {
+    let client = new_ec2client(region, profile)?;
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    if !changes.set.is_empty() {
+        let req = CreateTagsRequest {
+            resources: resources.clone(),
+            tags: changes
+                .set
+                .iter()
+                .map(|(key, value)| Tag {
+                    key: Some(key.clone()),
+                    value: Some(value.clone()),
+                })
+                .collect(),
+            dry_run: Some(dry_run),
+        };
+
+        runtime.block_on(client.create_tags(req))?;
+    }
+
+    // without tags DeleteTags would remove all tags
+    if !changes.delete.is_empty() {
+        let req = DeleteTagsRequest {
+            resources,
+            tags: Some(
+                changes
+                    .delete
+                    .iter()
+                    .map(|key| Tag {
+                        key: Some(key.clone()),
+                        value: None,
+                    })
+                    .collect(),
+            ),
+            dry_run: Some(dry_run),
+        };
+
+        runtime.block_on(client.delete_tags(req))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags(v: &[(&str, &str)]) -> Vec<(String, String)> {
+        v.iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn instance(v: &[(&str, &str)]) -> Instance {
+        Instance {
+            tags: Some(
+                v.iter()
+                    .map(|(key, value)| Tag {
+                        key: Some(key.to_string()),
+                        value: Some(value.to_string()),
+                    })
+                    .collect(),
+            ),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            parse_tags("# comment\n\n Name = web \nteam=ops\n"),
+            Ok(tags(&[("Name", "web"), ("team", "ops")]))
+        );
+        assert_eq!(parse_tags(""), Ok(vec![]));
+    }
+
+    #[test]
+    fn parse_values() {
+        // only the first = separates key and value
+        assert_eq!(
+            parse_tags("query = a=b&c=d"),
+            Ok(tags(&[("query", "a=b&c=d")]))
+        );
+        assert_eq!(parse_tags("empty =\n"), Ok(tags(&[("empty", "")])));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            parse_tags("Name = a\nteam = b\nName = c"),
+            Err("Line 3: Name is set twice.".to_string())
+        );
+        assert_eq!(
+            parse_tags("aws:cloudformation:stack-name = x"),
+            Err("Line 1: aws: tags are reserved.".to_string())
+        );
+        assert_eq!(
+            parse_tags("AWS:Thing = x"),
+            Err("Line 1: aws: tags are reserved.".to_string())
+        );
+        assert_eq!(
+            parse_tags("Name"),
+            Err("Line 1: expected key = value.".to_string())
+        );
+        assert_eq!(
+            parse_tags(" = value"),
+            Err("Line 1: the key is empty.".to_string())
+        );
+        assert!(parse_tags(&format!("{} = x", "k".repeat(MAX_KEY_LENGTH + 1))).is_err());
+        assert!(parse_tags(&format!("k = {}", "v".repeat(MAX_VALUE_LENGTH + 1))).is_err());
+    }
+
+    #[test]
+    fn format_parses_back() {
+        let before = tags(&[("Name", "web"), ("empty", ""), ("query", "a=b")]);
+
+        assert_eq!(parse_tags(&format_tags(&before)), Ok(before));
+    }
+
+    #[test]
+    fn common() {
+        let instances = vec![
+            instance(&[("env", "prod"), ("team", "ops"), ("Name", "web-1")]),
+            instance(&[("team", "ops"), ("env", "prod"), ("Name", "web-2")]),
+        ];
+
+        assert_eq!(
+            common_tags(&instances),
+            tags(&[("env", "prod"), ("team", "ops")])
+        );
+        assert_eq!(common_tags(&[]), vec![]);
+    }
+
+    #[test]
+    fn common_round_trip() {
+        let instances = vec![instance(&[
+            ("Name", "web"),
+            ("aws:cloudformation:stack-name", "web-stack"),
+        ])];
+
+        let before = common_tags(&instances);
+        assert_eq!(before, tags(&[("Name", "web")]));
+
+        // the tags are edited as text, unchanged text changes nothing
+        let after = parse_tags(&format_tags(&before)).unwrap();
+        assert!(TagChanges::between(&before, &after).is_empty());
+    }
+
+    #[test]
+    fn between() {
+        let before = tags(&[("env", "prod"), ("team", "ops"), ("old", "x")]);
+        let after = tags(&[("env", "staging"), ("team", "ops"), ("new", "")]);
+
+        assert_eq!(
+            TagChanges::between(&before, &after),
+            TagChanges {
+                set: tags(&[("env", "staging"), ("new", "")]),
+                delete: vec!["old".to_string()],
+            }
+        );
+        assert!(TagChanges::between(&before, &before).is_empty());
+    }
+
+    #[test]
+    fn between_renamed() {
+        let before = tags(&[("env", "prod"), ("team", "ops")]);
+        let after = tags(&[("environment", "prod"), ("team", "ops")]);
+
+        assert_eq!(
+            TagChanges::between(&before, &after),
+            TagChanges {
+                set: tags(&[("environment", "prod")]),
+                delete: vec!["env".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn preview_per_instance() {
+        let changes = TagChanges {
+            set: tags(&[("environment", "prod"), ("team", "ops")]),
+            delete: vec!["env".to_string(), "missing".to_string()],
+        };
+
+        assert_eq!(
+            preview(
+                &instance(&[("env", "prod"), ("team", "dev"), ("Name", "web")]),
+                &changes
+            ),
+            vec![
+                "- env = prod",
+                "+ environment = prod",
+                "- team = dev",
+                "+ team = ops",
+            ]
+        );
+
+        // changes without effect are left out
+        assert_eq!(
+            preview(
+                &instance(&[("environment", "prod"), ("team", "ops")]),
+                &changes
+            ),
+            Vec::<String>::new()
+        );
+    }
+}
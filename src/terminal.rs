@@ -0,0 +1,176 @@
+use cursive::Cursive;
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::process::{Command, ExitStatus};
+
+use crate::config::Terminal;
+use crate::temp::PrivateDir;
+
+/// Resolves `Terminal::Auto` to the multiplexer cloudman is running in.
+pub fn detect(terminal: Terminal) -> Terminal {
+    if terminal != Terminal::Auto {
+        return terminal;
+    }
+
+    if env::var("TMUX").is_ok() {
+        Terminal::Tmux
+    } else if env::var("STY").is_ok() {
+        Terminal::Screen
+    } else if env::var("ZELLIJ").is_ok() {
+        Terminal::Zellij
+    } else {
+        Terminal::Foreground
+    }
+}
+
+/// Starts `command` (an argument vector, not run through a shell) in a new
+/// pane, window or tab of `terminal`. For `Terminal::Foreground` the user
+/// interface is suspended until the command exits.
+pub fn launch(siv: &mut Cursive, terminal: Terminal, command: &[String]) -> io::Result<()> {
+    if command.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Empty command.",
+        ));
+    }
+
+    let mut cmd = match detect(terminal) {
+        Terminal::Tmux => {
+            let mut cmd = Command::new("tmux");
+            cmd.args(["split-window", "-h", "--"]);
+            cmd
+        }
+        Terminal::Screen => {
+            let mut cmd = Command::new("screen");
+            cmd.args(["-X", "screen"]);
+            cmd
+        }
+        Terminal::Zellij => {
+            let mut cmd = Command::new("zellij");
+            cmd.args(["run", "--"]);
+            cmd
+        }
+        Terminal::Kitty => {
+            let mut cmd = Command::new("kitty");
+            cmd.args(["@", "launch", "--type=tab"]);
+            cmd
+        }
+        Terminal::Wezterm => {
+            let mut cmd = Command::new("wezterm");
+            cmd.args(["cli", "spawn", "--"]);
+            cmd
+        }
+        Terminal::Foreground | Terminal::Auto => {
+            let status = suspend(siv, || {
+                Command::new(&command[0]).args(&command[1..]).status()
+            })?;
+            return check(status);
+        }
+    };
+
+    let output = cmd.args(pause_on_error(command)).output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Wraps `command` so that a pane, which closes when the command exits,
+/// waits for a key when it failed and its error output stays readable. The
+/// command is passed as arguments, not interpolated in the script.
+fn pause_on_error(command: &[String]) -> Vec<String> {
+    let mut wrapped = vec![
+        "bash".to_string(),
+        "-c".to_string(),
+        r#""$@" || { status=$?; echo; read -n 1 -s -r -p "Exited with $status, press any key to continue"; exit $status; }"#
+            .to_string(),
+        "cloudman".to_string(),
+    ];
+    wrapped.extend(command.iter().cloned());
+    wrapped
+}
+
+fn check(status: ExitStatus) -> io::Result<()> {
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!(
+            "The session exited with {}.",
+            status
+        )))
+    }
+}
+
+fn write_to_tty(bytes: &[u8]) -> io::Result<()> {
+    File::create("/dev/tty")?.write_all(bytes)
+}
+
+/// Copies `text` to the clipboard of the terminal emulator using OSC 52,
+/// which also works over ssh.
+pub fn copy_to_clipboard(text: &str) -> io::Result<()> {
+    let sequence = format!("\x1B]52;c;{}\x07", base64::encode(text));
+
+    // tmux only passes the sequence on when wrapped
+    if env::var("TMUX").is_ok() {
+        write_to_tty(format!("\x1BPtmux;\x1B{}\x1B\\", sequence).as_bytes())
+    } else {
+        write_to_tty(sequence.as_bytes())
+    }
+}
+
+/// Hands the terminal back to the shell while `f` runs, and restores the
+/// user interface afterwards.
+pub fn suspend<F, R>(siv: &mut Cursive, f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    // the ncurses backend asks for mouse drag events, which would otherwise
+    // end up as garbage in the foreground process.
+    let _ = write_to_tty(b"\x1B[?1002l");
+    ncurses::endwin();
+
+    let result = f();
+
+    ncurses::refresh();
+    let _ = write_to_tty(b"\x1B[?1002h");
+    siv.clear();
+
+    result
+}
+
+/// Opens `content` in `$VISUAL` or `$EDITOR` (vi when neither is set) with
+/// the user interface suspended, and returns the edited content.
+pub fn edit(siv: &mut Cursive, name: &str, content: &str) -> io::Result<String> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+
+    // removed with its directory on every return
+    let dir = PrivateDir::new("edit")?;
+    let (path, mut file) = dir.create_file(name)?;
+    file.write_all(content.as_bytes())?;
+    drop(file);
+
+    // the editor may come with arguments, e.g. "code --wait"
+    let status = suspend(siv, || {
+        Command::new("sh")
+            .arg("-c")
+            .arg(format!("{} \"$1\"", editor))
+            .arg("sh")
+            .arg(&path)
+            .status()
+    });
+
+    match status {
+        Ok(status) if status.success() => fs::read_to_string(&path),
+        Ok(status) => Err(io::Error::other(format!(
+            "{} exited with {}.",
+            editor, status
+        ))),
+        Err(err) => Err(err),
+    }
+}
@@ -0,0 +1,242 @@
+use cursive::theme::{BaseColor, Color, ColorStyle};
+use std::cmp::Ordering;
+use std::io;
+use std::os::unix::process::CommandExt;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+use crate::temp::PrivateDir;
+use crate::views::{Header, TableViewItem};
+
+/// How long a new tunnel has to stay up before it is considered started.
+pub const STARTUP_GRACE: Duration = Duration::from_millis(1500);
+
+struct Tunnel {
+    child: Child,
+    instance_id: String,
+    local_port: u16,
+    remote_host: Option<String>,
+    remote_port: u16,
+    started: Instant,
+    log: PathBuf,
+    // removed with the log when the tunnel is dropped
+    _log_dir: PrivateDir,
+}
+
+impl Tunnel {
+    fn info(&mut self) -> TunnelInfo {
+        let status = match self.child.try_wait() {
+            Ok(Some(status)) => format!("exited ({})", status.code().unwrap_or(-1)),
+            Ok(None) => "running".to_string(),
+            Err(_) => "unknown".to_string(),
+        };
+
+        TunnelInfo {
+            pid: self.child.id(),
+            instance_id: self.instance_id.clone(),
+            local_port: self.local_port,
+            remote: format!(
+                "{}:{}",
+                self.remote_host
+                    .clone()
+                    .unwrap_or_else(|| "localhost".to_string()),
+                self.remote_port
+            ),
+            uptime: self.started.elapsed().as_secs(),
+            status,
+        }
+    }
+
+    fn stop(&mut self) {
+        if let Ok(None) = self.child.try_wait() {
+            // the aws cli runs the session-manager-plugin as a child, so the
+            // whole process group has to go.
+            unsafe {
+                libc::kill(-(self.child.id() as i32), libc::SIGTERM);
+            }
+        }
+
+        let _ = self.child.wait();
+    }
+}
+
+/// Port forwarding sessions started from cloudman, stopped when dropped.
+#[derive(Default)]
+pub struct Tunnels {
+    tunnels: Vec<Tunnel>,
+}
+
+impl Tunnels {
+    pub fn new() -> Self {
+        Tunnels { tunnels: vec![] }
+    }
+
+    /// Starts `command` in the background, returning its pid. Whether the
+    /// session came up is known after `STARTUP_GRACE`, see `check_started`.
+    pub fn start(
+        &mut self,
+        command: &[String],
+        instance_id: &str,
+        local_port: u16,
+        remote_host: Option<String>,
+        remote_port: u16,
+    ) -> io::Result<u32> {
+        let log_dir = PrivateDir::new("tunnel")?;
+        let (log, output) = log_dir.create_file(&format!("{}.log", local_port))?;
+
+        let mut cmd = Command::new(&command[0]);
+        cmd.args(&command[1..])
+            .stdin(Stdio::null())
+            .stdout(output.try_clone()?)
+            .stderr(output);
+
+        // run in a process group of its own, see `Tunnel::stop`
+        unsafe {
+            cmd.pre_exec(|| {
+                libc::setpgid(0, 0);
+                Ok(())
+            });
+        }
+
+        let child = cmd.spawn()?;
+
+        let pid = child.id();
+
+        self.tunnels.push(Tunnel {
+            child,
+            instance_id: instance_id.to_string(),
+            local_port,
+            remote_host,
+            remote_port,
+            started: Instant::now(),
+            log,
+            _log_dir: log_dir,
+        });
+
+        Ok(pid)
+    }
+
+    /// Fails when the session of `pid` exited already, with the output of
+    /// the command as error. The tunnel is then removed.
+    pub fn check_started(&mut self, pid: u32) -> io::Result<()> {
+        let idx = match self.tunnels.iter().position(|t| t.child.id() == pid) {
+            Some(idx) => idx,
+            None => return Ok(()),
+        };
+
+        if let Some(status) = self.tunnels[idx].child.try_wait()? {
+            let tunnel = self.tunnels.remove(idx);
+            let output = std::fs::read_to_string(&tunnel.log).unwrap_or_default();
+
+            return Err(io::Error::other(format!(
+                "The session exited with {}.\n\n{}",
+                status,
+                output.trim()
+            )));
+        }
+
+        Ok(())
+    }
+
+    pub fn list(&mut self) -> Vec<TunnelInfo> {
+        self.tunnels.iter_mut().map(|t| t.info()).collect()
+    }
+
+    pub fn stop(&mut self, pid: u32) {
+        if let Some(idx) = self.tunnels.iter().position(|t| t.child.id() == pid) {
+            self.tunnels.remove(idx).stop();
+        }
+    }
+
+    pub fn stop_all(&mut self) {
+        for mut tunnel in self.tunnels.drain(..) {
+            tunnel.stop();
+        }
+    }
+}
+
+impl Drop for Tunnels {
+    fn drop(&mut self) {
+        self.stop_all();
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub enum TunnelColumn {
+    Pid,
+    InstanceID,
+    LocalPort,
+    Remote,
+    Uptime,
+    Status,
+}
+
+impl Header for TunnelColumn {
+    fn to_header(&self) -> String {
+        match self {
+            TunnelColumn::Pid => "pid".to_string(),
+            TunnelColumn::InstanceID => "instance-id".to_string(),
+            TunnelColumn::LocalPort => "local-port".to_string(),
+            TunnelColumn::Remote => "remote".to_string(),
+            TunnelColumn::Uptime => "uptime".to_string(),
+            TunnelColumn::Status => "status".to_string(),
+        }
+    }
+
+    fn to_header_size(&self, w: usize) -> usize {
+        match self {
+            TunnelColumn::Pid => 8,
+            TunnelColumn::InstanceID => 19,
+            TunnelColumn::LocalPort => 10,
+            TunnelColumn::Remote => (40 * w) / 160,
+            TunnelColumn::Uptime => 10,
+            TunnelColumn::Status => 12,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct TunnelInfo {
+    pub pid: u32,
+    pub instance_id: String,
+    pub local_port: u16,
+    pub remote: String,
+    pub uptime: u64,
+    pub status: String,
+}
+
+fn format_duration(secs: u64) -> String {
+    format!("{}h{:02}m{:02}s", secs / 3600, (secs / 60) % 60, secs % 60)
+}
+
+impl TableViewItem<TunnelColumn> for TunnelInfo {
+    fn to_column(&self, column: TunnelColumn) -> String {
+        match column {
+            TunnelColumn::Pid => self.pid.to_string(),
+            TunnelColumn::InstanceID => self.instance_id.clone(),
+            TunnelColumn::LocalPort => self.local_port.to_string(),
+            TunnelColumn::Remote => self.remote.clone(),
+            TunnelColumn::Uptime => format_duration(self.uptime),
+            TunnelColumn::Status => self.status.clone(),
+        }
+    }
+
+    fn to_column_color(&self, column: TunnelColumn) -> ColorStyle {
+        match column {
+            TunnelColumn::Status if self.status != "running" => {
+                ColorStyle::new(Color::Light(BaseColor::Red), Color::TerminalDefault)
+            }
+            _ => ColorStyle::primary(),
+        }
+    }
+
+    fn cmp(&self, other: &Self, column: TunnelColumn) -> Ordering {
+        match column {
+            TunnelColumn::Pid => self.pid.cmp(&other.pid),
+            TunnelColumn::LocalPort => self.local_port.cmp(&other.local_port),
+            TunnelColumn::Uptime => self.uptime.cmp(&other.uptime),
+            _ => self.to_column(column).cmp(&other.to_column(column)),
+        }
+    }
+}
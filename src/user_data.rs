@@ -0,0 +1,156 @@
+use rusoto_core::Region;
+use rusoto_ec2::{
+    BlobAttributeValue, DescribeInstanceAttributeRequest, Ec2, ModifyInstanceAttributeRequest,
+};
+use std::error::Error;
+
+use crate::aws::new_ec2client;
+use crate::views::Syntax;
+
+/// Returns the decoded user data of an instance, empty when it has none.
+pub fn get_user_data(
+    region: &Region,
+    profile: &str,
+    instance_id: &str,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let client = new_ec2client(region, profile)?;
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let req = DescribeInstanceAttributeRequest {
+        attribute: "userData".to_string(),
+        instance_id: instance_id.to_string(),
+        ..Default::default()
+    };
+
+    let response = runtime.block_on(client.describe_instance_attribute(req))?;
+
+    match response.user_data.and_then(|attribute| attribute.value) {
+        Some(value) => Ok(base64::decode(value.trim())?),
+        None => Ok(vec![]),
+    }
+}
+
+/// Replaces the user data of a stopped instance.
+pub fn modify_user_data(
+    region: &Region,
+    profile: &str,
+    instance_id: &str,
+    user_data: &[u8],
+    dry_run: bool,
+) -> Result<(), Box<dyn Error>> {
+    let client = new_ec2client(region, profile)?;
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    // rusoto sends blobs as they are, the api expects them base64 encoded
+    let req = ModifyInstanceAttributeRequest {
+        instance_id: instance_id.to_string(),
+        user_data: Some(BlobAttributeValue {
+            value: Some(base64::encode(user_data).into_bytes().into()),
+        }),
+        dry_run: Some(dry_run),
+        ..Default::default()
+    };
+
+    runtime.block_on(client.modify_instance_attribute(req))?;
+
+    Ok(())
+}
+
+/// Whether the user data is gzip compressed, which cloud-init supports.
+pub fn is_gzip(user_data: &[u8]) -> bool {
+    user_data.starts_with(&[0x1f, 0x8b])
+}
+
+/// Returns how to highlight the user data: cloud-config is YAML, anything
+/// else is most likely a script.
+pub fn user_data_syntax(user_data: &str) -> Syntax {
+    if user_data.starts_with("#cloud-config") {
+        Syntax::Data
+    } else {
+        Syntax::Shell
+    }
+}
+
+/// Returns the lines of `new` prefixed with `+ ` when added, and the lines of
+/// `old` prefixed with `- ` when removed, unchanged lines with two spaces.
+pub fn diff(old: &str, new: &str) -> Vec<String> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // lengths of the longest common subsequences of the remaining lines
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = vec![];
+    let (mut i, mut j) = (0, 0);
+
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push(format!("  {}", old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            lines.push(format!("- {}", old[i]));
+            i += 1;
+        } else {
+            lines.push(format!("+ {}", new[j]));
+            j += 1;
+        }
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(v: &[&str]) -> Vec<String> {
+        v.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn diff_unchanged() {
+        assert_eq!(diff("a\nb", "a\nb"), lines(&["  a", "  b"]));
+        assert!(diff("", "").is_empty());
+    }
+
+    #[test]
+    fn diff_inserted() {
+        assert_eq!(diff("a\nc", "a\nb\nc"), lines(&["  a", "+ b", "  c"]));
+        assert_eq!(diff("", "a\nb"), lines(&["+ a", "+ b"]));
+        assert_eq!(diff("a", "a\nb"), lines(&["  a", "+ b"]));
+    }
+
+    #[test]
+    fn diff_removed() {
+        assert_eq!(diff("a\nb\nc", "a\nc"), lines(&["  a", "- b", "  c"]));
+        assert_eq!(diff("a\nb", ""), lines(&["- a", "- b"]));
+        assert_eq!(diff("a\nb", "b"), lines(&["- a", "  b"]));
+    }
+
+    #[test]
+    fn diff_replaced() {
+        assert_eq!(
+            diff("a\nb\nc", "a\nx\nc"),
+            lines(&["  a", "- b", "+ x", "  c"])
+        );
+        assert_eq!(
+            diff(
+                "#!/bin/sh\necho one\necho two\n",
+                "#!/bin/sh\necho two\necho three\n"
+            ),
+            lines(&["  #!/bin/sh", "- echo one", "  echo two", "+ echo three"])
+        );
+    }
+}
@@ -203,6 +203,10 @@ impl View for BottomBarView {
                         key: "F7".to_string(),
                         name: "Region".to_string(),
                     },
+                    Column {
+                        key: "F8".to_string(),
+                        name: "SSH".to_string(),
+                    },
                     Column {
                         key: "F10".to_string(),
                         name: "Quit".to_string(),
@@ -211,6 +215,10 @@ impl View for BottomBarView {
                         key: "L".to_string(),
                         name: "Log".to_string(),
                     },
+                    Column {
+                        key: "P".to_string(),
+                        name: "Shot".to_string(),
+                    },
                 ];
 
                 printer.with_color(
@@ -0,0 +1,199 @@
+extern crate cursive;
+
+use cursive::direction::Direction;
+use cursive::event::{Event, EventResult, Key};
+use cursive::theme::{BaseColor, Color, ColorStyle, PaletteColor};
+use cursive::vec::Vec2;
+use cursive::view::{ScrollBase, View};
+use cursive::Printer;
+
+/// Width of the label column.
+const LABEL_WIDTH: usize = 24;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum DetailLine {
+    Section(String),
+    Field(String, String),
+    /// A field that needs attention.
+    Warning(String, String),
+    /// A field that is a problem.
+    Alert(String, String),
+    Blank,
+}
+
+impl DetailLine {
+    pub fn field<V: Into<String>>(label: &str, value: V) -> Self {
+        DetailLine::Field(label.to_string(), value.into())
+    }
+
+    pub fn section(title: &str) -> Self {
+        DetailLine::Section(title.to_string())
+    }
+}
+
+/// Label / value pairs spread over tabs, each tab scrolling on its own.
+pub struct DetailsView {
+    tabs: Vec<(String, Vec<DetailLine>)>,
+    current: usize,
+
+    scrollbase: ScrollBase,
+}
+
+impl DetailsView {
+    pub fn new() -> Self {
+        DetailsView {
+            tabs: vec![],
+            current: 0,
+            scrollbase: ScrollBase::new().right_padding(0),
+        }
+    }
+
+    pub fn tab(mut self, name: &str, lines: Vec<DetailLine>) -> Self {
+        self.set_tab(name, lines);
+        self
+    }
+
+    /// Replaces the lines of tab `name`, adding the tab if it is new.
+    pub fn set_tab(&mut self, name: &str, lines: Vec<DetailLine>) {
+        match self.tabs.iter_mut().find(|(n, _)| n == name) {
+            Some(tab) => tab.1 = lines,
+            None => self.tabs.push((name.to_string(), lines)),
+        }
+    }
+
+    pub fn current_tab(&self) -> Option<&str> {
+        self.tabs.get(self.current).map(|(name, _)| name.as_str())
+    }
+
+    pub fn select_tab(&mut self, idx: usize) {
+        if idx < self.tabs.len() {
+            self.current = idx;
+            self.scrollbase.scroll_top();
+        }
+    }
+
+    fn lines(&self) -> &[DetailLine] {
+        self.tabs
+            .get(self.current)
+            .map(|(_, lines)| lines.as_slice())
+            .unwrap_or(&[])
+    }
+}
+
+impl Default for DetailsView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn draw_field(printer: &Printer<'_, '_>, label: &str, value: &str, style: ColorStyle) {
+    printer.with_color(
+        ColorStyle::new(PaletteColor::TitleSecondary, PaletteColor::Background),
+        |p| p.print((0, 0), &format!("{:>width$}:", label, width = LABEL_WIDTH)),
+    );
+    printer.with_color(style, |p| p.print((LABEL_WIDTH + 2, 0), value));
+}
+
+impl View for DetailsView {
+    fn draw(&self, printer: &Printer<'_, '_>) {
+        let mut x = 0;
+        for (i, (name, _)) in self.tabs.iter().enumerate() {
+            let label = format!(" {} {} ", i + 1, name);
+
+            let style = if i == self.current {
+                ColorStyle::new(PaletteColor::HighlightText, PaletteColor::Highlight)
+            } else {
+                ColorStyle::new(PaletteColor::TitleSecondary, PaletteColor::Background)
+            };
+
+            printer.with_color(style, |p| p.print((x, 0), &label));
+            x += label.chars().count() + 1;
+        }
+
+        let printer = printer.offset((0, 2));
+
+        self.scrollbase
+            .draw(&printer, |printer, i| match self.lines().get(i) {
+                Some(DetailLine::Section(title)) => {
+                    printer.with_color(
+                        ColorStyle::new(PaletteColor::TitleSecondary, PaletteColor::Background),
+                        |p| p.print((0, 0), title),
+                    );
+                }
+                Some(DetailLine::Field(label, value)) => {
+                    draw_field(printer, label, value, ColorStyle::primary())
+                }
+                Some(DetailLine::Warning(label, value)) => draw_field(
+                    printer,
+                    label,
+                    value,
+                    ColorStyle::new(Color::Light(BaseColor::Yellow), Color::TerminalDefault),
+                ),
+                Some(DetailLine::Alert(label, value)) => draw_field(
+                    printer,
+                    label,
+                    value,
+                    ColorStyle::new(Color::Light(BaseColor::Red), Color::TerminalDefault),
+                ),
+                _ => {}
+            });
+    }
+
+    fn required_size(&mut self, constraint: Vec2) -> Vec2 {
+        let height = constraint.y.saturating_sub(2);
+        let h = std::cmp::max(self.lines().len(), height);
+
+        self.scrollbase.set_heights(height, h);
+
+        constraint
+    }
+
+    fn take_focus(&mut self, _: Direction) -> bool {
+        true
+    }
+
+    fn on_event(&mut self, event: Event) -> EventResult {
+        let height = self.scrollbase.view_height;
+        let tabs = self.tabs.len();
+
+        match event {
+            Event::Key(Key::Tab) | Event::Key(Key::Right) if tabs > 0 => {
+                self.select_tab((self.current + 1) % tabs);
+                EventResult::Consumed(None)
+            }
+            Event::Shift(Key::Tab) | Event::Key(Key::Left) if tabs > 0 => {
+                self.select_tab((self.current + tabs - 1) % tabs);
+                EventResult::Consumed(None)
+            }
+            Event::Char(c @ '1'..='9') => {
+                self.select_tab(c as usize - '1' as usize);
+                EventResult::Consumed(None)
+            }
+            Event::Key(Key::Up) => {
+                self.scrollbase.scroll_up(1);
+                EventResult::Consumed(None)
+            }
+            Event::Key(Key::Down) => {
+                self.scrollbase.scroll_down(1);
+                EventResult::Consumed(None)
+            }
+            Event::Key(Key::PageUp) => {
+                self.scrollbase.scroll_up(height);
+                EventResult::Consumed(None)
+            }
+            Event::Key(Key::PageDown) => {
+                self.scrollbase.scroll_down(height);
+                EventResult::Consumed(None)
+            }
+            Event::Key(Key::Home) | Event::Char('g') => {
+                self.scrollbase.scroll_top();
+                EventResult::Consumed(None)
+            }
+            Event::Key(Key::End) | Event::Char('G') => {
+                self.scrollbase.scroll_bottom();
+                EventResult::Consumed(None)
+            }
+            _ => EventResult::Ignored,
+        }
+    }
+}
@@ -0,0 +1,542 @@
+extern crate cursive;
+
+use cursive::direction::Direction;
+use cursive::event::{Event, EventResult, Key};
+use cursive::theme::{BaseColor, Color, ColorStyle, PaletteColor};
+use cursive::vec::Vec2;
+use cursive::view::{ScrollBase, View};
+use cursive::Printer;
+
+/// How the lines of a `DocumentView` are highlighted.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Syntax {
+    /// JSON or YAML, including cloud-config.
+    Data,
+    Shell,
+    /// Lines prefixed with `+ `, `- ` or two spaces.
+    Diff,
+}
+
+/// A pretty-printed JSON or YAML document, foldable by indentation and
+/// searchable.
+pub struct DocumentView {
+    lines: Vec<String>,
+    syntax: Syntax,
+    folded: Vec<usize>,
+    current: usize,
+    search: String,
+
+    scrollbase: ScrollBase,
+}
+
+fn indent(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+fn is_closing(line: &str) -> bool {
+    let line = line.trim_start();
+    line.starts_with('}') || line.starts_with(']')
+}
+
+fn color(c: BaseColor) -> ColorStyle {
+    ColorStyle::new(Color::Light(c), Color::TerminalDefault)
+}
+
+fn value_color(value: &str) -> ColorStyle {
+    let value = value.trim_end_matches(',');
+
+    match value {
+        "true" | "false" | "null" | "~" => color(BaseColor::Magenta),
+        _ if value.starts_with('"') || value.starts_with('\'') => color(BaseColor::Green),
+        _ if value.parse::<f64>().is_ok() => color(BaseColor::Cyan),
+        "{" | "[" | "{}" | "[]" | "}" | "]" => ColorStyle::primary(),
+        _ => color(BaseColor::Green),
+    }
+}
+
+/// Splits a line into its indentation, key and value, each with its color.
+fn highlight(line: &str) -> Vec<(String, ColorStyle)> {
+    let trimmed = line.trim_start();
+    let mut parts = vec![(line[..indent(line)].to_string(), ColorStyle::primary())];
+
+    let (item, rest) = match trimmed.strip_prefix("- ") {
+        Some(rest) => ("- ", rest),
+        None => ("", trimmed),
+    };
+    parts.push((item.to_string(), ColorStyle::primary()));
+
+    // json keys are quoted, yaml keys end at the first ": "
+    let key_end = if rest.starts_with('"') {
+        rest.find("\": ")
+            .or_else(|| rest.find("\":"))
+            .map(|idx| idx + 2)
+    } else if is_closing(rest) {
+        None
+    } else {
+        rest.find(": ").map(|idx| idx + 1).or_else(|| {
+            if rest.ends_with(':') {
+                Some(rest.len())
+            } else {
+                None
+            }
+        })
+    };
+
+    match key_end {
+        Some(end) => {
+            parts.push((rest[..end].to_string(), color(BaseColor::Blue)));
+            let value = &rest[end..];
+            parts.push((value.to_string(), value_color(value.trim())));
+        }
+        None => parts.push((rest.to_string(), value_color(rest))),
+    }
+
+    parts
+}
+
+const SHELL_KEYWORDS: &[&str] = &[
+    "if", "then", "else", "elif", "fi", "for", "while", "until", "do", "done", "case", "esac",
+    "in", "function", "return", "exit", "export", "local", "set",
+];
+
+/// Splits a shell line into comments, strings, variables, keywords and the
+/// rest.
+fn highlight_shell(line: &str) -> Vec<(String, ColorStyle)> {
+    let mut parts = vec![];
+    let mut word = String::new();
+
+    let flush = |word: &mut String, parts: &mut Vec<(String, ColorStyle)>| {
+        if !word.is_empty() {
+            let style = if SHELL_KEYWORDS.contains(&word.as_str()) {
+                color(BaseColor::Magenta)
+            } else {
+                ColorStyle::primary()
+            };
+            parts.push((word.clone(), style));
+            word.clear();
+        }
+    };
+
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '#' if word.is_empty() => {
+                let comment: String = std::iter::once(c).chain(chars.by_ref()).collect();
+                parts.push((comment, color(BaseColor::Blue)));
+            }
+            '"' | '\'' => {
+                flush(&mut word, &mut parts);
+
+                let mut string = c.to_string();
+                for next in chars.by_ref() {
+                    string.push(next);
+                    if next == c {
+                        break;
+                    }
+                }
+                parts.push((string, color(BaseColor::Green)));
+            }
+            '$' => {
+                flush(&mut word, &mut parts);
+
+                let mut variable = c.to_string();
+                while let Some(next) = chars.peek() {
+                    if next.is_alphanumeric() || "_{}@#?*".contains(*next) {
+                        variable.push(*next);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                parts.push((variable, color(BaseColor::Cyan)));
+            }
+            c if c.is_alphanumeric() || c == '_' || c == '-' => word.push(c),
+            c => {
+                flush(&mut word, &mut parts);
+                parts.push((c.to_string(), ColorStyle::primary()));
+            }
+        }
+    }
+
+    flush(&mut word, &mut parts);
+
+    parts
+}
+
+fn highlight_diff(line: &str) -> Vec<(String, ColorStyle)> {
+    let style = if line.starts_with('+') {
+        color(BaseColor::Green)
+    } else if line.starts_with('-') {
+        color(BaseColor::Red)
+    } else {
+        ColorStyle::primary()
+    };
+
+    vec![(line.to_string(), style)]
+}
+
+impl DocumentView {
+    pub fn new(lines: Vec<String>) -> Self {
+        DocumentView {
+            lines,
+            syntax: Syntax::Data,
+            folded: vec![],
+            current: 0,
+            search: "".to_string(),
+            scrollbase: ScrollBase::new().right_padding(0),
+        }
+    }
+
+    pub fn syntax(mut self, syntax: Syntax) -> Self {
+        self.syntax = syntax;
+        self
+    }
+
+    pub fn set_syntax(&mut self, syntax: Syntax) {
+        self.syntax = syntax;
+    }
+
+    pub fn set_lines(&mut self, lines: Vec<String>) {
+        self.lines = lines;
+        self.folded.clear();
+        self.current = 0;
+        self.scrollbase.scroll_top();
+    }
+
+    pub fn content(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    /// Index of the last line of the block starting at `idx`, including the
+    /// closing bracket of json objects and arrays.
+    fn block_end(&self, idx: usize) -> usize {
+        let level = indent(&self.lines[idx]);
+
+        let mut end = idx;
+        while end + 1 < self.lines.len() && indent(&self.lines[end + 1]) > level {
+            end += 1;
+        }
+
+        if end > idx && end + 1 < self.lines.len() {
+            let next = &self.lines[end + 1];
+            if indent(next) == level && is_closing(next) {
+                end += 1;
+            }
+        }
+
+        end
+    }
+
+    /// Returns the lines and the block under the cursor, e.g. to copy it.
+    pub fn current_block(&self) -> String {
+        if self.lines.is_empty() {
+            return "".to_string();
+        }
+
+        let end = self.block_end(self.current);
+        self.lines[self.current..=end].join("\n")
+    }
+
+    /// Indices of the lines that are not folded away.
+    fn visible(&self) -> Vec<usize> {
+        let mut visible = vec![];
+
+        let mut idx = 0;
+        while idx < self.lines.len() {
+            visible.push(idx);
+
+            if self.folded.contains(&idx) {
+                idx = self.block_end(idx);
+            }
+
+            idx += 1;
+        }
+
+        visible
+    }
+
+    pub fn toggle_fold(&mut self) {
+        if let Some(pos) = self.folded.iter().position(|idx| *idx == self.current) {
+            self.folded.remove(pos);
+        } else if self.block_end(self.current) > self.current {
+            self.folded.push(self.current);
+        }
+    }
+
+    /// Folds every block below the top level.
+    pub fn fold_all(&mut self) {
+        self.folded = (0..self.lines.len())
+            .filter(|idx| indent(&self.lines[*idx]) > 0 && self.block_end(*idx) > *idx)
+            .collect();
+
+        // keep the cursor on a visible line
+        let visible = self.visible();
+        while !visible.contains(&self.current) && self.current > 0 {
+            self.current -= 1;
+        }
+    }
+
+    pub fn unfold_all(&mut self) {
+        self.folded.clear();
+    }
+
+    /// Moves the cursor to the next line matching `pattern`, case
+    /// insensitively, unfolding the blocks containing it.
+    pub fn search(&mut self, pattern: &str) -> bool {
+        self.search = pattern.to_lowercase();
+        self.search_next(true, false)
+    }
+
+    fn search_next(&mut self, forward: bool, skip_current: bool) -> bool {
+        if self.search.is_empty() || self.lines.is_empty() {
+            return false;
+        }
+
+        let len = self.lines.len();
+        let start = if skip_current { 1 } else { 0 };
+
+        for step in start..=len {
+            let idx = if forward {
+                (self.current + step) % len
+            } else {
+                (self.current + len - step % len) % len
+            };
+
+            if self.lines[idx].to_lowercase().contains(&self.search) {
+                let folded: Vec<usize> = self
+                    .folded
+                    .iter()
+                    .cloned()
+                    .filter(|f| !(*f < idx && idx <= self.block_end(*f)))
+                    .collect();
+
+                self.folded = folded;
+                self.current = idx;
+                self.scroll_to_current();
+                return true;
+            }
+        }
+
+        false
+    }
+
+    fn scroll_to_current(&mut self) {
+        if let Some(row) = self.visible().iter().position(|idx| *idx == self.current) {
+            self.scrollbase.scroll_to(row);
+        }
+    }
+
+    fn move_cursor(&mut self, rows: isize) {
+        let visible = self.visible();
+
+        let row = visible
+            .iter()
+            .position(|idx| *idx == self.current)
+            .unwrap_or(0) as isize;
+
+        let row = (row + rows).max(0).min(visible.len() as isize - 1).max(0) as usize;
+
+        if let Some(idx) = visible.get(row) {
+            self.current = *idx;
+            self.scrollbase.scroll_to(row);
+        }
+    }
+}
+
+impl View for DocumentView {
+    fn draw(&self, printer: &Printer<'_, '_>) {
+        let visible = self.visible();
+
+        self.scrollbase.draw(printer, |printer, row| {
+            let idx = match visible.get(row) {
+                Some(idx) => *idx,
+                None => return,
+            };
+
+            let line = &self.lines[idx];
+
+            if idx == self.current {
+                printer.with_color(
+                    ColorStyle::new(PaletteColor::HighlightText, PaletteColor::Highlight),
+                    |p| {
+                        p.print_hline((0, 0), printer.size.x, " ");
+                        p.print((0, 0), line);
+                    },
+                );
+            } else {
+                let mut x = 0;
+                let parts = match self.syntax {
+                    Syntax::Data => highlight(line),
+                    Syntax::Shell => highlight_shell(line),
+                    Syntax::Diff => highlight_diff(line),
+                };
+
+                for (text, style) in parts {
+                    printer.with_color(style, |p| p.print((x, 0), &text));
+                    x += text.chars().count();
+                }
+            }
+
+            if self.folded.contains(&idx) {
+                let x = line.chars().count();
+                printer.with_color(color(BaseColor::Yellow), |p| p.print((x, 0), " …"));
+            }
+        });
+    }
+
+    fn required_size(&mut self, constraint: Vec2) -> Vec2 {
+        let h = std::cmp::max(self.visible().len(), constraint.y);
+
+        self.scrollbase.set_heights(constraint.y, h);
+
+        constraint
+    }
+
+    fn take_focus(&mut self, _: Direction) -> bool {
+        true
+    }
+
+    fn on_event(&mut self, event: Event) -> EventResult {
+        let height = self.scrollbase.view_height as isize;
+
+        match event {
+            Event::Key(Key::Up) => self.move_cursor(-1),
+            Event::Key(Key::Down) => self.move_cursor(1),
+            Event::Key(Key::PageUp) => self.move_cursor(-height),
+            Event::Key(Key::PageDown) => self.move_cursor(height),
+            Event::Key(Key::Home) | Event::Char('g') => {
+                self.move_cursor(-(self.lines.len() as isize))
+            }
+            Event::Key(Key::End) | Event::Char('G') => self.move_cursor(self.lines.len() as isize),
+            Event::Key(Key::Enter) | Event::Char(' ') => self.toggle_fold(),
+            Event::Char('-') => self.fold_all(),
+            Event::Char('+') => self.unfold_all(),
+            Event::Char('n') => {
+                self.search_next(true, true);
+            }
+            Event::Char('N') => {
+                self.search_next(false, true);
+            }
+            _ => return EventResult::Ignored,
+        }
+
+        EventResult::Consumed(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn view(text: &str) -> DocumentView {
+        DocumentView::new(text.lines().map(String::from).collect())
+    }
+
+    const JSON: &str = r#"{
+  "a": {
+    "b": 1
+  },
+  "c": [
+    2
+  ]
+}"#;
+
+    const YAML: &str = "a:
+  b:
+    c: x
+d: y";
+
+    #[test]
+    fn block_ends() {
+        let json = view(JSON);
+        assert_eq!(json.block_end(0), 7);
+        assert_eq!(json.block_end(1), 3);
+        assert_eq!(json.block_end(2), 2);
+        assert_eq!(json.block_end(4), 6);
+
+        let yaml = view(YAML);
+        assert_eq!(yaml.block_end(0), 2);
+        assert_eq!(yaml.block_end(1), 2);
+        assert_eq!(yaml.block_end(3), 3);
+    }
+
+    #[test]
+    fn fold_keeps_closing_bracket() {
+        let mut json = view(JSON);
+        json.current = 1;
+        json.toggle_fold();
+
+        assert_eq!(json.visible(), vec![0, 1, 4, 5, 6, 7]);
+        assert_eq!(json.current_block(), "  \"a\": {\n    \"b\": 1\n  },");
+
+        json.toggle_fold();
+        assert_eq!(json.visible(), (0..8).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn fold_only_blocks() {
+        let mut json = view(JSON);
+        json.current = 2;
+        json.toggle_fold();
+
+        assert!(json.folded.is_empty());
+    }
+
+    #[test]
+    fn fold_all_below_top_level() {
+        let mut json = view(JSON);
+        json.current = 5;
+        json.fold_all();
+
+        assert_eq!(json.folded, vec![1, 4]);
+        assert_eq!(json.visible(), vec![0, 1, 4, 7]);
+        assert_eq!(json.current, 4);
+
+        json.unfold_all();
+        assert_eq!(json.visible(), (0..8).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn search_unfolds_blocks() {
+        let mut yaml = view(YAML);
+        yaml.folded = vec![0, 1];
+        assert_eq!(yaml.visible(), vec![0, 3]);
+
+        assert!(yaml.search("C: X"));
+        assert_eq!(yaml.current, 2);
+        assert!(yaml.folded.is_empty());
+        assert_eq!(yaml.visible(), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn search_keeps_other_folds() {
+        let mut json = view(JSON);
+        json.fold_all();
+
+        assert!(json.search("\"b\""));
+        assert_eq!(json.current, 2);
+        assert_eq!(json.folded, vec![4]);
+    }
+
+    #[test]
+    fn search_next_wraps() {
+        let mut yaml = view(YAML);
+        assert!(yaml.search("y"));
+        assert_eq!(yaml.current, 3);
+
+        // the current line only matches again after wrapping around
+        assert!(yaml.search_next(true, true));
+        assert_eq!(yaml.current, 3);
+
+        yaml.search = ":".to_string();
+        assert!(yaml.search_next(true, true));
+        assert_eq!(yaml.current, 0);
+        assert!(yaml.search_next(false, true));
+        assert_eq!(yaml.current, 3);
+        assert!(yaml.search_next(false, true));
+        assert_eq!(yaml.current, 2);
+
+        assert!(!yaml.search("z"));
+        assert_eq!(yaml.current, 2);
+    }
+}
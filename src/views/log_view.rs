@@ -8,6 +8,8 @@ use cursive::Printer;
 
 pub struct LogView {
     lines: Vec<String>,
+    skip: usize,
+    follow: bool,
 
     scrollbase: ScrollBase,
 }
@@ -19,9 +21,36 @@ impl LogView {
 
         LogView {
             lines,
+            // ignore the first line, as it is incomplete
+            skip: 1,
+            follow: false,
             scrollbase: ScrollBase::new().right_padding(0),
         }
     }
+
+    /// Create a view of already complete lines, e.g. log events.
+    pub fn with_lines(lines: Vec<String>) -> Self {
+        LogView {
+            lines,
+            skip: 0,
+            follow: false,
+            scrollbase: ScrollBase::new().right_padding(0),
+        }
+    }
+
+    pub fn set_lines(&mut self, lines: Vec<String>) {
+        self.lines = lines;
+        self.scrollbase.scroll_top();
+    }
+
+    pub fn append_lines(&mut self, lines: Vec<String>) {
+        self.lines.extend(lines);
+    }
+
+    /// Keep the view scrolled to the last line while new lines are appended.
+    pub fn set_follow(&mut self, follow: bool) {
+        self.follow = follow;
+    }
 }
 
 fn parse_lines(buf: &[u8]) -> Vec<String> {
@@ -38,10 +67,7 @@ fn parse_lines(buf: &[u8]) -> Vec<String> {
 impl View for LogView {
     fn draw(&self, printer: &Printer<'_, '_>) {
         self.scrollbase.draw(printer, |printer, i| {
-            let lines = self.lines.clone();
-
-            // ignore the first line, as it is incomplete
-            if let Some(line) = lines.get(i+1) {
+            if let Some(line) = self.lines.get(i + self.skip) {
                 printer.print((0, 0), line);
             } else {
                 printer.print((0, 0), "⍇");
@@ -50,12 +76,14 @@ impl View for LogView {
     }
 
     fn required_size(&mut self, constraint: Vec2) -> Vec2 {
-        let lines = self.lines.clone();
-
-        let h = std::cmp::max(lines.len(), constraint.y);
+        let h = std::cmp::max(self.lines.len(), constraint.y);
 
         self.scrollbase.set_heights(constraint.y, h+1);
 
+        if self.follow {
+            self.scrollbase.scroll_bottom();
+        }
+
         constraint
     }
 
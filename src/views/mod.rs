@@ -1,11 +1,19 @@
 mod bottombar_view;
+mod details_view;
+mod document_view;
 mod foo_view;
 mod key_codes;
 mod log_view;
+mod notification_view;
+mod screenshot_view;
 mod table_view;
 
 pub use self::bottombar_view::{BottomBarType, BottomBarView, Column};
+pub use self::details_view::{DetailLine, DetailsView};
+pub use self::document_view::{DocumentView, Syntax};
 pub use self::foo_view::Foo;
 pub use self::key_codes::KeyCodeView;
 pub use self::log_view::LogView;
+pub use self::notification_view::{NotificationLevel, NotificationView};
+pub use self::screenshot_view::ScreenshotView;
 pub use self::table_view::{Header, InstancesView, TableViewItem};
@@ -0,0 +1,76 @@
+extern crate cursive;
+
+use cursive::theme::{BaseColor, Color, ColorStyle};
+use cursive::vec::Vec2;
+use cursive::view::View;
+use cursive::Printer;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum NotificationLevel {
+    /// Something is in progress.
+    Info,
+    Success,
+    Error,
+}
+
+/// One line per running background task, e.g. an image being created. Takes
+/// no space when there is nothing to report.
+pub struct NotificationView {
+    notifications: Vec<(String, NotificationLevel, String)>,
+}
+
+impl NotificationView {
+    pub fn new() -> Self {
+        NotificationView {
+            notifications: vec![],
+        }
+    }
+
+    /// Shows `message`, replacing the notification with the same key.
+    pub fn notify(&mut self, key: &str, level: NotificationLevel, message: &str) {
+        match self.notifications.iter_mut().find(|(k, _, _)| k == key) {
+            Some(notification) => {
+                notification.1 = level;
+                notification.2 = message.to_string();
+            }
+            None => self
+                .notifications
+                .push((key.to_string(), level, message.to_string())),
+        }
+    }
+
+    pub fn remove(&mut self, key: &str) {
+        self.notifications.retain(|(k, _, _)| k != key);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.notifications.is_empty()
+    }
+}
+
+impl Default for NotificationView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl View for NotificationView {
+    fn draw(&self, printer: &Printer<'_, '_>) {
+        for (y, (_, level, message)) in self.notifications.iter().enumerate() {
+            let color = match level {
+                NotificationLevel::Info => Color::Light(BaseColor::Cyan),
+                NotificationLevel::Success => Color::Light(BaseColor::Green),
+                NotificationLevel::Error => Color::Light(BaseColor::Red),
+            };
+
+            printer.with_color(ColorStyle::new(color, Color::TerminalDefault), |printer| {
+                printer.print_hline((0, y), printer.size.x, " ");
+                printer.print((1, y), message);
+            });
+        }
+    }
+
+    fn required_size(&mut self, constraint: Vec2) -> Vec2 {
+        Vec2::new(constraint.x, self.notifications.len())
+    }
+}
@@ -0,0 +1,74 @@
+extern crate cursive;
+
+use cursive::direction::Direction;
+use cursive::event::{Event, EventResult};
+use cursive::theme::{Color, ColorStyle};
+use cursive::vec::Vec2;
+use cursive::view::View;
+use cursive::Printer;
+use image::{ImageFormat, RgbImage};
+
+pub struct ScreenshotView {
+    image: RgbImage,
+}
+
+impl ScreenshotView {
+    /// Decodes a JPEG console screenshot, as returned by `GetConsoleScreenshot`.
+    pub fn from_jpeg(buf: &[u8]) -> Result<Self, image::ImageError> {
+        let image = image::load_from_memory_with_format(buf, ImageFormat::Jpeg)?.to_rgb8();
+
+        Ok(ScreenshotView { image })
+    }
+
+    fn pixel(&self, x: u32, y: u32) -> Color {
+        let p = self.image.get_pixel(x, y);
+        Color::Rgb(p[0], p[1], p[2])
+    }
+}
+
+impl View for ScreenshotView {
+    fn draw(&self, printer: &Printer<'_, '_>) {
+        let (iw, ih) = self.image.dimensions();
+        if iw == 0 || ih == 0 || printer.size.x == 0 || printer.size.y == 0 {
+            return;
+        }
+
+        // every cell holds two vertical pixels using the upper half block,
+        // so the image is scaled to fit width x (2 * height) pixels.
+        let cols = printer.size.x as u64;
+        let rows = printer.size.y as u64 * 2;
+
+        let (w, h) = if cols * ih as u64 > rows * iw as u64 {
+            ((rows * iw as u64 / ih as u64).max(1), rows)
+        } else {
+            (cols, (cols * ih as u64 / iw as u64).max(1))
+        };
+
+        let offset = (cols - w) / 2;
+
+        for y in 0..h.div_ceil(2) {
+            for x in 0..w {
+                let px = (x * iw as u64 / w) as u32;
+                let top = ((2 * y) * ih as u64 / h) as u32;
+                let bottom = ((2 * y + 1) * ih as u64 / h).min(ih as u64 - 1) as u32;
+
+                printer.with_color(
+                    ColorStyle::new(self.pixel(px, top), self.pixel(px, bottom)),
+                    |p| p.print(((offset + x) as usize, y as usize), "▀"),
+                );
+            }
+        }
+    }
+
+    fn required_size(&mut self, constraint: Vec2) -> Vec2 {
+        constraint
+    }
+
+    fn take_focus(&mut self, _: Direction) -> bool {
+        true
+    }
+
+    fn on_event(&mut self, _: Event) -> EventResult {
+        EventResult::Ignored
+    }
+}
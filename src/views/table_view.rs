@@ -3,7 +3,7 @@ extern crate cursive;
 use cursive::direction::Direction;
 use cursive::event::*;
 use cursive::event::{Event, EventResult, Key};
-use cursive::theme::{Color, ColorStyle, PaletteColor};
+use cursive::theme::{BaseColor, Color, ColorStyle, PaletteColor};
 use cursive::vec::Vec2;
 use cursive::view::View;
 use cursive::view::*;
@@ -14,21 +14,21 @@ use std::rc::Rc;
 
 pub type OnSubmit<T> = Option<Rc<dyn Fn(&mut Cursive, Option<T>)>>;
 
-pub struct InstancesView<
-    T: TableViewItem<H> + PartialEq,
-    H: Eq + Hash + Copy + Clone + Header + 'static,
-> {
+pub struct InstancesView<T: TableViewItem<H> + PartialEq, H: Eq + Hash + Clone + Header + 'static> {
     instances: Vec<T>,
     scrollbase: ScrollBase,
     current_index: usize,
     columns: Vec<H>,
+    sort_column: Option<H>,
+    sort_ascending: bool,
+    marked: Vec<T>,
 
     on_submit: OnSubmit<T>,
 }
 
 pub trait TableViewItem<H>: Clone + Sized
 where
-    H: Eq + Hash + Copy + Clone + Header + 'static,
+    H: Eq + Hash + Clone + Header + 'static,
 {
     /// Method returning a string representation of the item for the
     /// specified column from type `H`.
@@ -47,8 +47,8 @@ pub trait Header {
     fn to_header_size(&self, w: usize) -> usize;
 }
 
-impl<T: TableViewItem<H> + PartialEq + 'static, H: Eq + Hash + Copy + Clone + Header + 'static>
-    Default for InstancesView<T, H>
+impl<T: TableViewItem<H> + PartialEq + 'static, H: Eq + Hash + Clone + Header + 'static> Default
+    for InstancesView<T, H>
 {
     /// Creates a new empty `TableView` without any columns.
     ///
@@ -58,7 +58,7 @@ impl<T: TableViewItem<H> + PartialEq + 'static, H: Eq + Hash + Copy + Clone + He
     }
 }
 
-impl<T: TableViewItem<H> + PartialEq + 'static, H: Eq + Hash + Copy + Clone + Header + 'static>
+impl<T: TableViewItem<H> + PartialEq + 'static, H: Eq + Hash + Clone + Header + 'static>
     InstancesView<T, H>
 {
     /// Create a new `FlexiLoggerView` which is wrapped in a `ScrollView`.
@@ -68,6 +68,9 @@ impl<T: TableViewItem<H> + PartialEq + 'static, H: Eq + Hash + Copy + Clone + He
             scrollbase: ScrollBase::new().right_padding(0),
             current_index: 0,
             columns: vec![],
+            sort_column: None,
+            sort_ascending: true,
+            marked: vec![],
 
             on_submit: None,
         }
@@ -79,6 +82,9 @@ impl<T: TableViewItem<H> + PartialEq + 'static, H: Eq + Hash + Copy + Clone + He
             scrollbase: ScrollBase::new().right_padding(0),
             current_index: 0,
             columns: vec![],
+            sort_column: None,
+            sort_ascending: true,
+            marked: vec![],
 
             on_submit: None,
         }
@@ -134,12 +140,125 @@ impl<T: TableViewItem<H> + PartialEq + 'static, H: Eq + Hash + Copy + Clone + He
         &self.instances
     }
 
+    /// Returns the items in view.
+    pub fn visible_items(&self) -> &[T] {
+        // the first line is the header
+        let start = self.scrollbase.start_line.saturating_sub(1);
+        let end = start + self.scrollbase.view_height;
+
+        &self.instances[start.min(self.instances.len())..end.min(self.instances.len())]
+    }
+
+    /// Updates the items in place, keeping the order, selection and marks.
+    pub fn update_items<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&mut T),
+    {
+        for item in self.instances.iter_mut() {
+            f(item);
+        }
+    }
+
     pub fn set_instances(&mut self, instances: Vec<T>) -> &Self {
         self.instances = instances;
+
+        let instances = &self.instances;
+        self.marked.retain(|item| instances.contains(item));
+
+        self.sort();
         self.set_selected_item(0);
         self
     }
 
+    pub fn is_marked(&self, item: &T) -> bool {
+        self.marked.contains(item)
+    }
+
+    /// Returns the marked items, in display order.
+    pub fn marked_items(&self) -> Vec<&T> {
+        self.instances
+            .iter()
+            .filter(|item| self.is_marked(item))
+            .collect()
+    }
+
+    /// Returns the marked items, or the selected item when none are marked.
+    pub fn marked_or_selected(&self) -> Vec<&T> {
+        let marked = self.marked_items();
+        if marked.is_empty() {
+            self.item().into_iter().collect()
+        } else {
+            marked
+        }
+    }
+
+    pub fn clear_marks(&mut self) {
+        self.marked.clear();
+    }
+
+    fn toggle_mark(&mut self) {
+        if let Some(item) = self.item().cloned() {
+            match self.marked.iter().position(|m| *m == item) {
+                Some(idx) => {
+                    self.marked.remove(idx);
+                }
+                None => self.marked.push(item),
+            }
+        }
+
+        if self.current_index + 1 < self.instances.len() {
+            self.current_index += 1;
+        }
+    }
+
+    /// Sorts the items by `column`, and keeps them sorted when they are
+    /// replaced.
+    pub fn sort_by(&mut self, column: H, ascending: bool) {
+        self.sort_column = Some(column);
+        self.sort_ascending = ascending;
+
+        let item = self.item().cloned();
+
+        self.sort();
+
+        if let Some(item) = item {
+            self.set_item(&item);
+        }
+    }
+
+    fn sort(&mut self) {
+        if let Some(column) = self.sort_column.clone() {
+            let ascending = self.sort_ascending;
+
+            self.instances.sort_by(|a, b| {
+                let ordering = TableViewItem::cmp(a, b, column.clone());
+                if ascending {
+                    ordering
+                } else {
+                    ordering.reverse()
+                }
+            });
+        }
+    }
+
+    fn sort_next(&mut self, forward: bool) {
+        if self.columns.is_empty() {
+            return;
+        }
+
+        let idx = match self
+            .sort_column
+            .as_ref()
+            .and_then(|c| self.columns.iter().position(|column| column == c))
+        {
+            Some(idx) if forward => (idx + 1) % self.columns.len(),
+            Some(idx) => (idx + self.columns.len() - 1) % self.columns.len(),
+            None => 0,
+        };
+
+        self.sort_by(self.columns[idx].clone(), self.sort_ascending);
+    }
+
     fn make_submit_cb(&self) -> Option<Callback> {
         self.on_submit.clone().map(|cb| {
             Callback::from_fn(move |s| {
@@ -149,7 +268,7 @@ impl<T: TableViewItem<H> + PartialEq + 'static, H: Eq + Hash + Copy + Clone + He
     }
 }
 
-impl<T: TableViewItem<H> + PartialEq + 'static, H: Eq + Hash + Copy + Clone + Header + 'static> View
+impl<T: TableViewItem<H> + PartialEq + 'static, H: Eq + Hash + Clone + Header + 'static> View
     for InstancesView<T, H>
 {
     fn draw(&self, printer: &Printer<'_, '_>) {
@@ -166,14 +285,22 @@ impl<T: TableViewItem<H> + PartialEq + 'static, H: Eq + Hash + Copy + Clone + He
                         for column in self.columns.iter() {
                             let w = column.to_header_size(printer.size.x);
 
+                            let header = match &self.sort_column {
+                                Some(c) if c == column && self.sort_ascending => {
+                                    format!("{}▲", column.to_header())
+                                }
+                                Some(c) if c == column => format!("{}▼", column.to_header()),
+                                _ => column.to_header(),
+                            };
+
                             let s = format!(
                                 "{:.width$} ",
-                                format!("{:<width$}", &column.to_header(), width = w),
+                                format!("{:<width$}", &header, width = w),
                                 width = w
                             );
                             printer.print((x, 0), &s);
 
-                            x += s.len();
+                            x += s.chars().count();
                         }
                     },
                 );
@@ -203,7 +330,7 @@ impl<T: TableViewItem<H> + PartialEq + 'static, H: Eq + Hash + Copy + Clone + He
                     let w = column.to_header_size(printer.size.x);
                     let s = format!(
                         "{:.width$} ",
-                        format!("{:<width$}", &instance.to_column(*column), width = w),
+                        format!("{:<width$}", &instance.to_column(column.clone()), width = w),
                         width = w
                     );
 
@@ -220,13 +347,15 @@ impl<T: TableViewItem<H> + PartialEq + 'static, H: Eq + Hash + Copy + Clone + He
                                     PaletteColor::HighlightInactive,
                                 )
                             }
+                        } else if self.is_marked(instance) {
+                            ColorStyle::new(Color::Light(BaseColor::Yellow), Color::TerminalDefault)
                         } else {
-                            instance.to_column_color(*column)
+                            instance.to_column_color(column.clone())
                         },
                         |p| p.print((x, 0), &s),
                     );
 
-                    x += s.len();
+                    x += s.chars().count();
                 }
             }
         });
@@ -272,8 +401,10 @@ impl<T: TableViewItem<H> + PartialEq + 'static, H: Eq + Hash + Copy + Clone + He
                 EventResult::Consumed(None)
             }
             Event::Key(Key::PageDown) => {
-                let idx = std::cmp::min(self.instances.len() - 1, self.current_index + 10);
-                self.current_index = idx;
+                if !self.instances.is_empty() {
+                    let idx = std::cmp::min(self.instances.len() - 1, self.current_index + 10);
+                    self.current_index = idx;
+                }
                 EventResult::Consumed(None)
             }
             Event::Key(Key::Home) => {
@@ -281,7 +412,9 @@ impl<T: TableViewItem<H> + PartialEq + 'static, H: Eq + Hash + Copy + Clone + He
                 EventResult::Consumed(None)
             }
             Event::Key(Key::End) => {
-                self.current_index = self.instances.len() - 1;
+                if !self.instances.is_empty() {
+                    self.current_index = self.instances.len() - 1;
+                }
                 EventResult::Consumed(None)
             }
             Event::Shift(Key::Home) => {
@@ -289,12 +422,38 @@ impl<T: TableViewItem<H> + PartialEq + 'static, H: Eq + Hash + Copy + Clone + He
                 EventResult::Consumed(None)
             }
             Event::Shift(Key::End) => {
-                self.current_index = self.instances.len() - 1;
+                if !self.instances.is_empty() {
+                    self.current_index = self.instances.len() - 1;
+                }
                 EventResult::Consumed(None)
             }
             Event::Key(Key::Enter) => EventResult::Consumed(self.make_submit_cb()),
+            Event::Char(' ') => {
+                self.toggle_mark();
+                EventResult::Consumed(None)
+            }
+            Event::Char('u') => {
+                self.clear_marks();
+                EventResult::Consumed(None)
+            }
+            Event::Char('>') => {
+                self.sort_next(true);
+                EventResult::Consumed(None)
+            }
+            Event::Char('<') => {
+                self.sort_next(false);
+                EventResult::Consumed(None)
+            }
+            Event::Char('I') => {
+                if let Some(column) = self.sort_column.clone() {
+                    self.sort_by(column, !self.sort_ascending);
+                }
+                EventResult::Consumed(None)
+            }
             Event::Char('H') => {
-                self.current_index = self.instances.len() - 1;
+                if !self.instances.is_empty() {
+                    self.current_index = self.instances.len() - 1;
+                }
                 EventResult::Consumed(None)
             }
             _ => EventResult::Ignored,
@@ -0,0 +1,703 @@
+use chrono::{DateTime, Utc};
+use cursive::theme::{BaseColor, Color, ColorStyle};
+use rusoto_core::Region;
+use rusoto_ec2::{
+    AttachVolumeRequest, CreateSnapshotRequest, DeleteSnapshotRequest, DeleteVolumeRequest,
+    DescribeSnapshotsRequest, DescribeVolumesRequest, DetachVolumeRequest, Ec2, Filter, Image,
+    ModifyVolumeRequest, Snapshot, Volume,
+};
+use std::cmp::Ordering;
+use std::error::Error;
+
+use crate::aws::new_ec2client;
+use crate::images::image_snapshot_ids;
+use crate::views::{DetailLine, Header, TableViewItem};
+
+/// Types a volume can be changed to. gp3 is missing as rusoto 0.44 can't set
+/// its throughput, nor does it know gp3 has iops of its own.
+pub const VOLUME_TYPES: &[&str] = &["gp2", "io1", "io2", "st1", "sc1", "standard"];
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub enum VolumeColumn {
+    VolumeID,
+    Name,
+    Device,
+    Size,
+    Type,
+    Iops,
+    Encrypted,
+    State,
+    Attachment,
+    DeleteOnTermination,
+    Zone,
+}
+
+impl Header for VolumeColumn {
+    fn to_header(&self) -> String {
+        match self {
+            VolumeColumn::VolumeID => "volume-id".to_string(),
+            VolumeColumn::Name => "name".to_string(),
+            VolumeColumn::Device => "device".to_string(),
+            VolumeColumn::Size => "size".to_string(),
+            VolumeColumn::Type => "type".to_string(),
+            VolumeColumn::Iops => "iops".to_string(),
+            VolumeColumn::Encrypted => "encrypted".to_string(),
+            VolumeColumn::State => "state".to_string(),
+            VolumeColumn::Attachment => "attachment".to_string(),
+            VolumeColumn::DeleteOnTermination => "delete-on-term".to_string(),
+            VolumeColumn::Zone => "zone".to_string(),
+        }
+    }
+
+    fn to_header_size(&self, w: usize) -> usize {
+        match self {
+            VolumeColumn::VolumeID => 22,
+            VolumeColumn::Name => (30 * w) / 160,
+            VolumeColumn::Device => 12,
+            VolumeColumn::Size => 8,
+            VolumeColumn::Type => 9,
+            VolumeColumn::Iops => 7,
+            VolumeColumn::Encrypted => 9,
+            VolumeColumn::State => 10,
+            VolumeColumn::Attachment => 20,
+            VolumeColumn::DeleteOnTermination => 14,
+            VolumeColumn::Zone => 12,
+        }
+    }
+}
+
+fn text(value: &Option<String>) -> String {
+    value.clone().unwrap_or_default()
+}
+
+fn flag(value: Option<bool>) -> String {
+    match value {
+        Some(true) => "yes".to_string(),
+        Some(false) => "no".to_string(),
+        None => "".to_string(),
+    }
+}
+
+/// Returns the value of tag `key` of a volume.
+pub fn volume_tag(volume: &Volume, key: &str) -> Option<String> {
+    volume
+        .tags
+        .iter()
+        .flatten()
+        .find(|t| t.key.as_deref() == Some(key))
+        .and_then(|t| t.value.clone())
+}
+
+/// Returns the attachment as `<instance-id> (<state>)`.
+pub fn attachment(volume: &Volume) -> String {
+    volume
+        .attachments
+        .iter()
+        .flatten()
+        .next()
+        .map(|a| format!("{} ({})", text(&a.instance_id), text(&a.state)))
+        .unwrap_or_default()
+}
+
+impl TableViewItem<VolumeColumn> for Volume {
+    fn to_column(&self, column: VolumeColumn) -> String {
+        let attachment_of = self.attachments.iter().flatten().next();
+
+        match column {
+            VolumeColumn::VolumeID => text(&self.volume_id),
+            VolumeColumn::Name => volume_tag(self, "Name").unwrap_or_default(),
+            VolumeColumn::Device => attachment_of.map(|a| text(&a.device)).unwrap_or_default(),
+            VolumeColumn::Size => self
+                .size
+                .map(|size| format!("{} GiB", size))
+                .unwrap_or_default(),
+            VolumeColumn::Type => text(&self.volume_type),
+            VolumeColumn::Iops => self.iops.map(|iops| iops.to_string()).unwrap_or_default(),
+            VolumeColumn::Encrypted => flag(self.encrypted),
+            VolumeColumn::State => text(&self.state),
+            VolumeColumn::Attachment => attachment(self),
+            VolumeColumn::DeleteOnTermination => attachment_of
+                .map(|a| flag(a.delete_on_termination))
+                .unwrap_or_default(),
+            VolumeColumn::Zone => text(&self.availability_zone),
+        }
+    }
+
+    fn to_column_color(&self, column: VolumeColumn) -> ColorStyle {
+        match column {
+            VolumeColumn::Encrypted if self.encrypted == Some(false) => {
+                ColorStyle::new(Color::Light(BaseColor::Yellow), Color::TerminalDefault)
+            }
+            VolumeColumn::State => match self.state.as_deref() {
+                Some("in-use") => {
+                    ColorStyle::new(Color::Light(BaseColor::Green), Color::TerminalDefault)
+                }
+                Some("available") => {
+                    ColorStyle::new(Color::Light(BaseColor::Yellow), Color::TerminalDefault)
+                }
+                Some("error") => {
+                    ColorStyle::new(Color::Light(BaseColor::Red), Color::TerminalDefault)
+                }
+                _ => ColorStyle::primary(),
+            },
+            _ => ColorStyle::primary(),
+        }
+    }
+
+    fn cmp(&self, other: &Self, column: VolumeColumn) -> Ordering {
+        match column {
+            VolumeColumn::Size => self.size.cmp(&other.size),
+            VolumeColumn::Iops => self.iops.cmp(&other.iops),
+            _ => self
+                .to_column(column)
+                .to_lowercase()
+                .cmp(&other.to_column(column).to_lowercase()),
+        }
+    }
+}
+
+/// Returns the volumes matching `filters`, all volumes when empty.
+pub fn get_volumes(
+    region: &Region,
+    profile: &str,
+    filters: Vec<Filter>,
+) -> Result<Vec<Volume>, Box<dyn Error>> {
+    let client = new_ec2client(region, profile)?;
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let mut volumes = vec![];
+    let mut next_token = None;
+
+    loop {
+        let req = DescribeVolumesRequest {
+            filters: if filters.is_empty() {
+                None
+            } else {
+                Some(filters.clone())
+            },
+            next_token: next_token.clone(),
+            ..Default::default()
+        };
+
+        let response = runtime.block_on(client.describe_volumes(req))?;
+
+        volumes.extend(response.volumes.unwrap_or_default());
+
+        next_token = response.next_token;
+        if next_token.is_none() {
+            break;
+        }
+    }
+
+    Ok(volumes)
+}
+
+/// Returns the volumes attached to an instance.
+pub fn get_instance_volumes(
+    region: &Region,
+    profile: &str,
+    instance_id: &str,
+) -> Result<Vec<Volume>, Box<dyn Error>> {
+    get_volumes(
+        region,
+        profile,
+        vec![Filter {
+            name: Some("attachment.instance-id".to_string()),
+            values: Some(vec![instance_id.to_string()]),
+        }],
+    )
+}
+
+/// Returns the number and total size in GiB of the volumes not attached to
+/// any instance, which are paid for without being used.
+pub fn unattached(volumes: &[Volume]) -> (usize, i64) {
+    volumes
+        .iter()
+        .filter(|volume| volume.state.as_deref() == Some("available"))
+        .fold((0, 0), |(count, size), volume| {
+            (count + 1, size + volume.size.unwrap_or_default())
+        })
+}
+
+/// Attaches an available volume to an instance in the same availability
+/// zone as `device`, e.g. `/dev/sdf`.
+pub fn attach_volume(
+    region: &Region,
+    profile: &str,
+    volume_id: &str,
+    instance_id: &str,
+    device: &str,
+    dry_run: bool,
+) -> Result<(), Box<dyn Error>> {
+    let client = new_ec2client(region, profile)?;
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let req = AttachVolumeRequest {
+        volume_id: volume_id.to_string(),
+        instance_id: instance_id.to_string(),
+        device: device.to_string(),
+        dry_run: Some(dry_run),
+    };
+
+    runtime.block_on(client.attach_volume(req))?;
+
+    Ok(())
+}
+
+/// Detaches a volume from its instance. With `force` the volume is detached
+/// even when the instance does not release it, risking data loss.
+pub fn detach_volume(
+    region: &Region,
+    profile: &str,
+    volume_id: &str,
+    force: bool,
+    dry_run: bool,
+) -> Result<(), Box<dyn Error>> {
+    let client = new_ec2client(region, profile)?;
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let req = DetachVolumeRequest {
+        volume_id: volume_id.to_string(),
+        force: Some(force),
+        dry_run: Some(dry_run),
+        ..Default::default()
+    };
+
+    runtime.block_on(client.detach_volume(req))?;
+
+    Ok(())
+}
+
+pub fn delete_volume(
+    region: &Region,
+    profile: &str,
+    volume_id: &str,
+    dry_run: bool,
+) -> Result<(), Box<dyn Error>> {
+    let client = new_ec2client(region, profile)?;
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let req = DeleteVolumeRequest {
+        volume_id: volume_id.to_string(),
+        dry_run: Some(dry_run),
+    };
+
+    runtime.block_on(client.delete_volume(req))?;
+
+    Ok(())
+}
+
+/// Snapshots a volume, returning the snapshot id.
+pub fn create_snapshot(
+    region: &Region,
+    profile: &str,
+    volume_id: &str,
+    description: &str,
+    dry_run: bool,
+) -> Result<String, Box<dyn Error>> {
+    let client = new_ec2client(region, profile)?;
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let req = CreateSnapshotRequest {
+        volume_id: volume_id.to_string(),
+        description: Some(description.to_string()),
+        dry_run: Some(dry_run),
+        ..Default::default()
+    };
+
+    let snapshot = runtime.block_on(client.create_snapshot(req))?;
+
+    Ok(text(&snapshot.snapshot_id))
+}
+
+pub fn delete_snapshot(
+    region: &Region,
+    profile: &str,
+    snapshot_id: &str,
+    dry_run: bool,
+) -> Result<(), Box<dyn Error>> {
+    let client = new_ec2client(region, profile)?;
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let req = DeleteSnapshotRequest {
+        snapshot_id: snapshot_id.to_string(),
+        dry_run: Some(dry_run),
+    };
+
+    runtime.block_on(client.delete_snapshot(req))?;
+
+    Ok(())
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub enum SnapshotColumn {
+    SnapshotID,
+    Name,
+    Size,
+    Age,
+    Volume,
+    Image,
+    State,
+    Encrypted,
+    Description,
+}
+
+impl Header for SnapshotColumn {
+    fn to_header(&self) -> String {
+        match self {
+            SnapshotColumn::SnapshotID => "snapshot-id".to_string(),
+            SnapshotColumn::Name => "name".to_string(),
+            SnapshotColumn::Size => "size".to_string(),
+            SnapshotColumn::Age => "age".to_string(),
+            SnapshotColumn::Volume => "volume".to_string(),
+            SnapshotColumn::Image => "image".to_string(),
+            SnapshotColumn::State => "state".to_string(),
+            SnapshotColumn::Encrypted => "encrypted".to_string(),
+            SnapshotColumn::Description => "description".to_string(),
+        }
+    }
+
+    fn to_header_size(&self, w: usize) -> usize {
+        match self {
+            SnapshotColumn::SnapshotID => 24,
+            SnapshotColumn::Name => (25 * w) / 160,
+            SnapshotColumn::Size => 8,
+            SnapshotColumn::Age => 6,
+            SnapshotColumn::Volume => 22,
+            SnapshotColumn::Image => 22,
+            SnapshotColumn::State => 10,
+            SnapshotColumn::Encrypted => 9,
+            SnapshotColumn::Description => (40 * w) / 160,
+        }
+    }
+}
+
+/// A snapshot, with the image it belongs to if any. The snapshot can't be
+/// deleted as long as the image is registered.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct SnapshotRow {
+    pub snapshot: Snapshot,
+    pub image_id: Option<String>,
+}
+
+impl SnapshotRow {
+    pub fn snapshot_id(&self) -> String {
+        text(&self.snapshot.snapshot_id)
+    }
+}
+
+/// Pairs the snapshots with the images using them.
+pub fn snapshot_rows(snapshots: Vec<Snapshot>, images: &[Image]) -> Vec<SnapshotRow> {
+    snapshots
+        .into_iter()
+        .map(|snapshot| {
+            let image_id = snapshot.snapshot_id.as_ref().and_then(|snapshot_id| {
+                images
+                    .iter()
+                    .find(|image| image_snapshot_ids(image).contains(snapshot_id))
+                    .and_then(|image| image.image_id.clone())
+            });
+
+            SnapshotRow { snapshot, image_id }
+        })
+        .collect()
+}
+
+/// Formats the time since `start` as e.g. `5m`, `3h` or `12d`.
+fn age(start: &Option<String>) -> String {
+    let start = match start
+        .as_ref()
+        .and_then(|start| DateTime::parse_from_rfc3339(start).ok())
+    {
+        Some(start) => start,
+        None => return "".to_string(),
+    };
+
+    let minutes = (Utc::now() - start.with_timezone(&Utc))
+        .num_minutes()
+        .max(0);
+
+    if minutes < 60 {
+        format!("{}m", minutes)
+    } else if minutes < 24 * 60 {
+        format!("{}h", minutes / 60)
+    } else {
+        format!("{}d", minutes / (24 * 60))
+    }
+}
+
+fn snapshot_tag(snapshot: &Snapshot, key: &str) -> Option<String> {
+    snapshot
+        .tags
+        .iter()
+        .flatten()
+        .find(|t| t.key.as_deref() == Some(key))
+        .and_then(|t| t.value.clone())
+}
+
+impl TableViewItem<SnapshotColumn> for SnapshotRow {
+    fn to_column(&self, column: SnapshotColumn) -> String {
+        let snapshot = &self.snapshot;
+
+        match column {
+            SnapshotColumn::SnapshotID => text(&snapshot.snapshot_id),
+            SnapshotColumn::Name => snapshot_tag(snapshot, "Name").unwrap_or_default(),
+            SnapshotColumn::Size => snapshot
+                .volume_size
+                .map(|size| format!("{} GiB", size))
+                .unwrap_or_default(),
+            SnapshotColumn::Age => age(&snapshot.start_time),
+            SnapshotColumn::Volume => text(&snapshot.volume_id),
+            SnapshotColumn::Image => text(&self.image_id),
+            SnapshotColumn::State => match snapshot.state.as_deref() {
+                Some("pending") => format!("pending {}", text(&snapshot.progress)),
+                _ => text(&snapshot.state),
+            },
+            SnapshotColumn::Encrypted => flag(snapshot.encrypted),
+            SnapshotColumn::Description => text(&snapshot.description),
+        }
+    }
+
+    fn to_column_color(&self, column: SnapshotColumn) -> ColorStyle {
+        match column {
+            SnapshotColumn::Encrypted if self.snapshot.encrypted == Some(false) => {
+                ColorStyle::new(Color::Light(BaseColor::Yellow), Color::TerminalDefault)
+            }
+            SnapshotColumn::State => match self.snapshot.state.as_deref() {
+                Some("completed") => {
+                    ColorStyle::new(Color::Light(BaseColor::Green), Color::TerminalDefault)
+                }
+                Some("error") => {
+                    ColorStyle::new(Color::Light(BaseColor::Red), Color::TerminalDefault)
+                }
+                _ => ColorStyle::primary(),
+            },
+            _ => ColorStyle::primary(),
+        }
+    }
+
+    fn cmp(&self, other: &Self, column: SnapshotColumn) -> Ordering {
+        match column {
+            SnapshotColumn::Size => self.snapshot.volume_size.cmp(&other.snapshot.volume_size),
+            // the later the start, the younger the snapshot
+            SnapshotColumn::Age => other.snapshot.start_time.cmp(&self.snapshot.start_time),
+            _ => self
+                .to_column(column)
+                .to_lowercase()
+                .cmp(&other.to_column(column).to_lowercase()),
+        }
+    }
+}
+
+/// Returns the snapshots owned by the account, the newest first.
+pub fn get_snapshots(region: &Region, profile: &str) -> Result<Vec<Snapshot>, Box<dyn Error>> {
+    let client = new_ec2client(region, profile)?;
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let mut snapshots = vec![];
+    let mut next_token = None;
+
+    loop {
+        let req = DescribeSnapshotsRequest {
+            owner_ids: Some(vec!["self".to_string()]),
+            next_token: next_token.clone(),
+            ..Default::default()
+        };
+
+        let response = runtime.block_on(client.describe_snapshots(req))?;
+
+        snapshots.extend(response.snapshots.unwrap_or_default());
+
+        next_token = response.next_token;
+        if next_token.is_none() {
+            break;
+        }
+    }
+
+    snapshots.sort_by(|a, b| b.start_time.cmp(&a.start_time));
+
+    Ok(snapshots)
+}
+
+/// Changes size, type and iops of a volume, returning the state of the
+/// modification. Unchanged values are `None`.
+pub fn modify_volume(
+    region: &Region,
+    profile: &str,
+    volume_id: &str,
+    size: Option<i64>,
+    volume_type: Option<String>,
+    iops: Option<i64>,
+    dry_run: bool,
+) -> Result<String, Box<dyn Error>> {
+    let client = new_ec2client(region, profile)?;
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let req = ModifyVolumeRequest {
+        volume_id: volume_id.to_string(),
+        size,
+        volume_type,
+        iops,
+        dry_run: Some(dry_run),
+    };
+
+    let response = runtime.block_on(client.modify_volume(req))?;
+
+    Ok(response
+        .volume_modification
+        .and_then(|m| m.modification_state)
+        .unwrap_or_default())
+}
+
+/// Details of the volumes for the storage tab of an instance.
+pub fn volume_lines(volumes: &[Volume]) -> Vec<DetailLine> {
+    let mut lines = vec![];
+
+    for volume in volumes {
+        let attachment_of = volume.attachments.iter().flatten().next().cloned();
+        let attachment_of = attachment_of.unwrap_or_default();
+
+        lines.push(DetailLine::Blank);
+        lines.push(DetailLine::section(&format!(
+            "{} {}",
+            text(&attachment_of.device),
+            text(&volume.volume_id)
+        )));
+        lines.push(DetailLine::field(
+            "name",
+            volume_tag(volume, "Name").unwrap_or_default(),
+        ));
+        lines.push(DetailLine::field(
+            "size",
+            volume
+                .size
+                .map(|s| format!("{} GiB", s))
+                .unwrap_or_default(),
+        ));
+        lines.push(DetailLine::field("type", text(&volume.volume_type)));
+        lines.push(DetailLine::field(
+            "iops",
+            volume.iops.map(|i| i.to_string()).unwrap_or_default(),
+        ));
+        lines.push(DetailLine::field("encrypted", flag(volume.encrypted)));
+        lines.push(DetailLine::field("kms key", text(&volume.kms_key_id)));
+        lines.push(DetailLine::field("snapshot-id", text(&volume.snapshot_id)));
+        lines.push(DetailLine::field("state", text(&volume.state)));
+        lines.push(DetailLine::field(
+            "attachment state",
+            text(&attachment_of.state),
+        ));
+        lines.push(DetailLine::field(
+            "attach time",
+            text(&attachment_of.attach_time),
+        ));
+        lines.push(DetailLine::field(
+            "delete on termination",
+            flag(attachment_of.delete_on_termination),
+        ));
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use rusoto_ec2::{BlockDeviceMapping, EbsBlockDevice};
+
+    fn volume(state: &str, size: Option<i64>) -> Volume {
+        Volume {
+            state: Some(state.to_string()),
+            size,
+            ..Default::default()
+        }
+    }
+
+    fn snapshot(id: &str) -> Snapshot {
+        Snapshot {
+            snapshot_id: Some(id.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn image(id: &str, snapshot_ids: &[&str]) -> Image {
+        Image {
+            image_id: Some(id.to_string()),
+            block_device_mappings: Some(
+                snapshot_ids
+                    .iter()
+                    .map(|snapshot_id| BlockDeviceMapping {
+                        ebs: Some(EbsBlockDevice {
+                            snapshot_id: Some(snapshot_id.to_string()),
+                            ..Default::default()
+                        }),
+                        ..Default::default()
+                    })
+                    .collect(),
+            ),
+            ..Default::default()
+        }
+    }
+
+    fn ago(duration: Duration) -> Option<String> {
+        Some((Utc::now() - duration).to_rfc3339())
+    }
+
+    #[test]
+    fn unattached_volumes() {
+        let volumes = vec![
+            volume("available", Some(8)),
+            volume("in-use", Some(100)),
+            volume("available", Some(20)),
+            volume("available", None),
+            volume("creating", Some(50)),
+        ];
+
+        assert_eq!(unattached(&volumes), (3, 28));
+        assert_eq!(unattached(&[]), (0, 0));
+    }
+
+    #[test]
+    fn snapshots_of_images() {
+        let images = vec![
+            image("ami-1", &["snap-1"]),
+            image("ami-2", &["snap-2", "snap-3"]),
+        ];
+
+        let rows = snapshot_rows(
+            vec![
+                snapshot("snap-3"),
+                snapshot("snap-4"),
+                snapshot("snap-1"),
+                Snapshot::default(),
+            ],
+            &images,
+        );
+
+        let image_ids: Vec<Option<&str>> = rows.iter().map(|r| r.image_id.as_deref()).collect();
+        assert_eq!(image_ids, vec![Some("ami-2"), None, Some("ami-1"), None]);
+        assert_eq!(rows[0].snapshot_id(), "snap-3");
+    }
+
+    #[test]
+    fn ages() {
+        assert_eq!(age(&ago(Duration::seconds(90))), "1m");
+        assert_eq!(age(&ago(Duration::minutes(59))), "59m");
+        assert_eq!(age(&ago(Duration::minutes(3 * 60 + 5))), "3h");
+        assert_eq!(age(&ago(Duration::days(12) + Duration::hours(1))), "12d");
+        // clock skew doesn't make snapshots younger than new
+        assert_eq!(age(&ago(Duration::minutes(-5))), "0m");
+
+        assert_eq!(age(&None), "");
+        assert_eq!(age(&Some("yesterday".to_string())), "");
+    }
+}